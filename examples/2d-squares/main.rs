@@ -227,6 +227,7 @@ unsafe fn create_graph(
             };
 
             ctx.compute_shader_create(info)
+                .expect("Can't create shader")
         };
 
         struct MovePass {
@@ -303,7 +304,7 @@ unsafe fn create_graph(
                 )),
             };
 
-            ctx.vertex_shader_create(info)
+            ctx.vertex_shader_create(info).expect("Can't create shader")
         };
 
         let fragment_shader = {
@@ -316,6 +317,7 @@ unsafe fn create_graph(
             };
 
             ctx.fragment_shader_create(info)
+                .expect("Can't create shader")
         };
 
         struct Pass2D {
@@ -344,9 +346,12 @@ unsafe fn create_graph(
                             specialization: vec![],
                         }),
                         geometry: None,
+                        tess_control: None,
+                        tess_eval: None,
                     },
                     primitive: graph::Primitive::TriangleStrip,
-                    blend_modes: vec![graph::BlendMode::Alpha],
+                    rasterizer: graph::Rasterizer::default(),
+                    blend: graph::BlendDesc::new(vec![graph::BlendMode::Alpha]),
                     materials: vec![(0, self.mat_instance.material())],
                     push_constants: Some(0..20),
                 }
@@ -363,6 +368,9 @@ unsafe fn create_graph(
                             height: 1.0,
                         },
                         format: image::ImageFormat::RgbaUnorm,
+                        samples: 1,
+                        layers: 1,
+                        kind: image::ViewKind::D2,
                     },
                 );
 
@@ -115,6 +115,8 @@ pub unsafe fn image_create_with_content(
         format,
         dimension,
         target_offset: (0, 0, 0),
+        level: 0,
+        layer: 0,
     };
 
     submit.image_upload_data(ctx, img, upload).ok()?;
@@ -128,6 +130,7 @@ pub unsafe fn image_create_with_content(
             sampler::WrapMode::Clamp,
             sampler::WrapMode::Clamp,
         ),
+        lod_range: (0.0, 0.0),
     };
 
     let sampler = ctx.sampler_create(sampler_create);
@@ -135,6 +135,7 @@ unsafe fn create_graph(
         };
 
         ctx.compute_shader_create(info)
+            .expect("Can't create shader")
     };
 
     let mut builder = GraphBuilder::new("Adder");
@@ -155,7 +155,7 @@ unsafe fn create_graph(
                 )),
             };
 
-            ctx.vertex_shader_create(info)
+            ctx.vertex_shader_create(info).expect("Can't create shader")
         };
 
         let fragment_shader = {
@@ -168,6 +168,7 @@ unsafe fn create_graph(
             };
 
             ctx.fragment_shader_create(info)
+                .expect("Can't create shader")
         };
 
         struct Pass {
@@ -208,11 +209,28 @@ unsafe fn create_graph(
                 }
             }
 
+            fn configs(&self) -> Vec<Self::Config> {
+                vec![
+                    PassConfig {
+                        blend_mode: graph::BlendMode::Alpha,
+                        primitive: graph::Primitive::TriangleList,
+                        use_depth: true,
+                    },
+                    PassConfig {
+                        blend_mode: graph::BlendMode::Add,
+                        primitive: graph::Primitive::LineList,
+                        use_depth: false,
+                    },
+                ]
+            }
+
             fn configure(&self, config: &Self::Config) -> graph::GraphicsPipelineInfo {
                 let depth = if config.use_depth {
                     Some(graph::DepthMode {
                         write: true,
                         func: graph::Comparison::Less,
+                        bias: None,
+                        bounds: None,
                     })
                 } else {
                     None
@@ -232,9 +250,12 @@ unsafe fn create_graph(
                             specialization: vec![],
                         }),
                         geometry: None,
+                        tess_control: None,
+                        tess_eval: None,
                     },
                     primitive: config.primitive,
-                    blend_modes: vec![config.blend_mode],
+                    rasterizer: graph::Rasterizer::default(),
+                    blend: graph::BlendDesc::new(vec![config.blend_mode]),
                     materials: vec![],
                     push_constants: Some(0..128),
                 }
@@ -249,6 +270,9 @@ unsafe fn create_graph(
                             height: 1.0,
                         },
                         format: image::ImageFormat::RgbaUnorm,
+                        samples: 1,
+                        layers: 1,
+                        kind: image::ViewKind::D2,
                     },
                 );
 
@@ -260,6 +284,9 @@ unsafe fn create_graph(
                             height: 1.0,
                         },
                         format: image::ImageFormat::D32Float,
+                        samples: 1,
+                        layers: 1,
+                        kind: image::ViewKind::D2,
                     },
                 );
 
@@ -211,6 +211,9 @@ fn setup_graphs(
                 width: 1.0,
                 height: 1.0,
             },
+            samples: 1,
+            layers: 1,
+            kind: image::ViewKind::D2,
         }
     }
 
@@ -221,6 +224,9 @@ fn setup_graphs(
                 width: 1.0,
                 height: 1.0,
             },
+            samples: 1,
+            layers: 1,
+            kind: image::ViewKind::D2,
         }
     }
 
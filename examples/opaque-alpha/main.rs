@@ -118,7 +118,7 @@ fn create_graph(ctx: &mut Context) -> graph::GraphHandle {
             )),
         };
 
-        ctx.vertex_shader_create(info)
+        ctx.vertex_shader_create(info).expect("Can't create shader")
     };
 
     let fragment = {
@@ -131,6 +131,7 @@ fn create_graph(ctx: &mut Context) -> graph::GraphHandle {
         };
 
         ctx.fragment_shader_create(info)
+            .expect("Can't create shader")
     };
 
     // Opaque pass
@@ -149,6 +150,8 @@ fn create_graph(ctx: &mut Context) -> graph::GraphHandle {
                     depth_mode: Some(graph::DepthMode {
                         write: true,
                         func: graph::Comparison::Less,
+                        bias: None,
+                        bounds: None,
                     }),
                     stencil_mode: None,
                     shaders: graph::GraphicShaders {
@@ -161,9 +164,12 @@ fn create_graph(ctx: &mut Context) -> graph::GraphHandle {
                             specialization: vec![],
                         }),
                         geometry: None,
+                        tess_control: None,
+                        tess_eval: None,
                     },
                     primitive: graph::Primitive::TriangleStrip,
-                    blend_modes: vec![graph::BlendMode::Alpha],
+                    rasterizer: graph::Rasterizer::default(),
+                    blend: graph::BlendDesc::new(vec![graph::BlendMode::Alpha]),
                     materials: vec![],
                     push_constants: Some(
                         // (0..8) canvas_size
@@ -186,6 +192,9 @@ fn create_graph(ctx: &mut Context) -> graph::GraphHandle {
                             height: 1.0,
                         },
                         format: image::ImageFormat::RgbaUnorm,
+                        samples: 1,
+                        layers: 1,
+                        kind: image::ViewKind::D2,
                     },
                 );
 
@@ -197,6 +206,9 @@ fn create_graph(ctx: &mut Context) -> graph::GraphHandle {
                             width: 1.0,
                             height: 1.0,
                         },
+                        samples: 1,
+                        layers: 1,
+                        kind: image::ViewKind::D2,
                     },
                 );
 
@@ -260,6 +272,8 @@ fn create_graph(ctx: &mut Context) -> graph::GraphHandle {
                     depth_mode: Some(graph::DepthMode {
                         write: false,
                         func: graph::Comparison::Less,
+                        bias: None,
+                        bounds: None,
                     }),
                     stencil_mode: None,
                     shaders: graph::GraphicShaders {
@@ -272,9 +286,12 @@ fn create_graph(ctx: &mut Context) -> graph::GraphHandle {
                             specialization: vec![],
                         }),
                         geometry: None,
+                        tess_control: None,
+                        tess_eval: None,
                     },
                     primitive: graph::Primitive::TriangleStrip,
-                    blend_modes: vec![graph::BlendMode::Alpha],
+                    rasterizer: graph::Rasterizer::default(),
+                    blend: graph::BlendDesc::new(vec![graph::BlendMode::Alpha]),
                     materials: vec![],
                     push_constants: Some(
                         // (0..8) canvas_size
@@ -226,6 +226,9 @@ fn create_graph(
                         width: 1.0,
                         height: 1.0,
                     },
+                    samples: 1,
+                    layers: 1,
+                    kind: image::ViewKind::D2,
                 },
             );
 
@@ -247,9 +250,12 @@ fn create_graph(
                         specialization: vec![],
                     }),
                     geometry: None,
+                    tess_control: None,
+                    tess_eval: None,
                 },
                 primitive: graph::Primitive::TriangleList,
-                blend_modes: vec![graph::BlendMode::Alpha],
+                rasterizer: graph::Rasterizer::default(),
+                blend: graph::BlendDesc::new(vec![graph::BlendMode::Alpha]),
                 materials: vec![],
                 push_constants: None,
             }
@@ -283,7 +289,7 @@ fn create_graph(
             entry_point: "VertexMain".into(),
         };
 
-        ctx.vertex_shader_create(info)
+        ctx.vertex_shader_create(info).expect("Can't create shader")
     };
 
     let shader_fragment = {
@@ -296,6 +302,7 @@ fn create_graph(
         };
 
         ctx.fragment_shader_create(info)
+            .expect("Can't create shader")
     };
 
     let pass = TrianglePass {
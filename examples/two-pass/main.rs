@@ -125,6 +125,8 @@ fn init_resources(
                 format: image::ImageFormat::RgbaUnorm,
                 dimension,
                 target_offset: (0, 0, 0),
+                level: 0,
+                layer: 0,
             };
 
             group.image_upload_data(ctx, img, data).unwrap();
@@ -141,6 +143,7 @@ fn init_resources(
                 mag_filter: Filter::Linear,
                 mip_filter: Filter::Linear,
                 wrap_mode: (WrapMode::Clamp, WrapMode::Clamp, WrapMode::Clamp),
+                lod_range: (0.0, 0.0),
             };
 
             ctx.sampler_create(sampler_create)
@@ -234,6 +237,9 @@ fn create_graph(ctx: &mut Context, mat: material::MaterialHandle) -> graph::Grap
                 width: 1.0,
                 height: 1.0,
             },
+            samples: 1,
+            layers: 1,
+            kind: image::ViewKind::D2,
         }
     }
     // test pass
@@ -247,7 +253,7 @@ fn create_graph(ctx: &mut Context, mat: material::MaterialHandle) -> graph::Grap
                 )),
             };
 
-            ctx.vertex_shader_create(info)
+            ctx.vertex_shader_create(info).expect("Can't create shader")
         };
 
         let fragment = {
@@ -260,6 +266,7 @@ fn create_graph(ctx: &mut Context, mat: material::MaterialHandle) -> graph::Grap
             };
 
             ctx.fragment_shader_create(info)
+                .expect("Can't create shader")
         };
 
         struct TestPass {
@@ -312,9 +319,12 @@ fn create_graph(ctx: &mut Context, mat: material::MaterialHandle) -> graph::Grap
                             specialization: vec![],
                         }),
                         geometry: None,
+                        tess_control: None,
+                        tess_eval: None,
                     },
                     primitive: graph::Primitive::TriangleStrip,
-                    blend_modes: vec![graph::BlendMode::Alpha],
+                    rasterizer: graph::Rasterizer::default(),
+                    blend: graph::BlendDesc::new(vec![graph::BlendMode::Alpha]),
                     materials: vec![(0, self.mat)],
                     push_constants: None,
                 }
@@ -367,7 +377,7 @@ fn create_graph(ctx: &mut Context, mat: material::MaterialHandle) -> graph::Grap
                 )),
             };
 
-            ctx.vertex_shader_create(info)
+            ctx.vertex_shader_create(info).expect("Can't create shader")
         };
 
         let fragment = {
@@ -380,6 +390,7 @@ fn create_graph(ctx: &mut Context, mat: material::MaterialHandle) -> graph::Grap
             };
 
             ctx.fragment_shader_create(info)
+                .expect("Can't create shader")
         };
 
         struct ReadPass {
@@ -431,9 +442,12 @@ fn create_graph(ctx: &mut Context, mat: material::MaterialHandle) -> graph::Grap
                             specialization: vec![],
                         }),
                         geometry: None,
+                        tess_control: None,
+                        tess_eval: None,
                     },
                     primitive: graph::Primitive::TriangleStrip,
-                    blend_modes: vec![graph::BlendMode::Alpha],
+                    rasterizer: graph::Rasterizer::default(),
+                    blend: graph::BlendDesc::new(vec![graph::BlendMode::Alpha]),
                     materials: vec![],
                     push_constants: None,
                 }
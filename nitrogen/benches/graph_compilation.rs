@@ -150,6 +150,34 @@ unsafe fn compile_graph(
 }
 
 fn benchmark_graph_compilation(c: &mut Criterion) {
+    c.bench_function_over_inputs(
+        "compile_graph_deep",
+        |b, i| {
+            b.iter(|| {
+                black_box(
+                    build_graph(*i, GraphDependencyType::Deep)
+                        .compile()
+                        .unwrap(),
+                )
+            });
+        },
+        (0..10).map(|i| 1 << i),
+    );
+
+    c.bench_function_over_inputs(
+        "compile_graph_flat",
+        |b, i| {
+            b.iter(|| {
+                black_box(
+                    build_graph(*i, GraphDependencyType::Flat)
+                        .compile()
+                        .unwrap(),
+                )
+            });
+        },
+        (0..10).map(|i| 1 << i),
+    );
+
     let ctx = common::BenchContext::new();
 
     {
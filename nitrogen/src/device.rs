@@ -16,6 +16,9 @@ use std::sync::{Arc, Mutex, MutexGuard};
 pub(crate) struct DeviceContext {
     pub(crate) memory_allocator: RefCell<Allocator>,
 
+    /// Features enabled on the logical device.
+    pub(crate) features: gfx::Features,
+
     pub(crate) graphics_queue_idx: usize,
     pub(crate) compute_queue_idx: usize,
     pub(crate) queue_groups: SmallVec<[types::QueueGroup<gfx::Transfer>; 2]>,
@@ -35,21 +38,24 @@ impl DeviceContext {
         // TODO select best fitting adapter
         let adapter = adapters.remove(0);
 
-        let (device, mut queue_groups, graphics_idx, compute_idx) = {
+        let (device, mut queue_groups, graphics_idx, compute_idx, features) = {
             use gfx::QueueFamily;
 
+            // Compute passes that don't use async compute are submitted to the graphics queue.
+            // Vulkan guarantees that there is a family which supports both.
             let graphics_queue = adapter
                 .queue_families
                 .iter()
-                .filter(|fam| fam.supports_graphics())
+                .filter(|fam| fam.supports_graphics() && fam.supports_compute())
                 .max_by_key(|fam| fam.max_queues())
                 .expect("No suitable graphics queue available");
 
+            // Prefer a dedicated compute family, so async compute work can overlap graphics work.
             let compute_queue = adapter
                 .queue_families
                 .iter()
                 .filter(|fam| fam.supports_compute())
-                .max_by_key(|fam| fam.max_queues())
+                .max_by_key(|fam| (!fam.supports_graphics(), fam.max_queues()))
                 .expect("No suitable compute queue available");
 
             // create device, for that we need a list of all the queues we want to be created.
@@ -67,10 +73,22 @@ impl DeviceContext {
                 2
             };
 
+            // Optional features used by pipeline states are enabled whenever the device
+            // supports them, pipelines requiring unsupported ones fail to be created.
             let features = {
                 use gfx::Features;
 
-                Features::empty()
+                let optional = Features::DEPTH_BOUNDS
+                    | Features::DEPTH_BIAS_CLAMP
+                    | Features::DEPTH_CLAMP
+                    | Features::NON_FILL_POLYGON_MODE
+                    | Features::LINE_WIDTH
+                    | Features::LOGIC_OP
+                    | Features::DUAL_SRC_BLENDING
+                    | Features::INDEPENDENT_BLENDING
+                    | Features::TESSELLATION_SHADER;
+
+                adapter.physical_device.features() & optional
             };
 
             let mut gpu = adapter
@@ -91,7 +109,7 @@ impl DeviceContext {
                 0
             };
 
-            (gpu.device, queues, graphics_idx, compute_idx)
+            (gpu.device, queues, graphics_idx, compute_idx, features)
         };
 
         let queues = queue_groups
@@ -113,6 +131,8 @@ impl DeviceContext {
         DeviceContext {
             memory_allocator: RefCell::new(memory_allocator),
 
+            features,
+
             graphics_queue_idx: graphics_idx,
             compute_queue_idx: compute_idx,
             queue_groups,
@@ -141,6 +161,30 @@ impl DeviceContext {
         unsafe { transmute(self.queues[self.graphics_queue_idx][0].lock().unwrap()) }
     }
 
+    /// The graphics queue group, used to record compute work.
+    pub(crate) fn graphics_compute_queue_group(&self) -> &types::QueueGroup<gfx::Compute> {
+        let queue = &self.queue_groups[self.graphics_queue_idx];
+
+        unsafe {
+            // cast reference to pointer, then cast pointer to other type
+            &*(queue as *const _ as *const _)
+        }
+    }
+
+    /// The graphics queue, used to submit compute work.
+    pub(crate) fn graphics_compute_queue(&self) -> MutexGuard<types::CommandQueue<gfx::Compute>> {
+        use std::mem::transmute;
+        unsafe { transmute(self.queues[self.graphics_queue_idx][0].lock().unwrap()) }
+    }
+
+    pub(crate) fn graphics_queue_family(&self) -> gfx::queue::QueueFamilyId {
+        self.queue_groups[self.graphics_queue_idx].family()
+    }
+
+    pub(crate) fn compute_queue_family(&self) -> gfx::queue::QueueFamilyId {
+        self.queue_groups[self.compute_queue_idx].family()
+    }
+
     pub(crate) fn compute_queue_group(&self) -> &types::QueueGroup<gfx::Compute> {
         let queue = &self.queue_groups[self.compute_queue_idx];
 
@@ -158,15 +202,17 @@ impl DeviceContext {
     pub(crate) fn transfer_queue_group(&self) -> &types::QueueGroup<gfx::Transfer> {
         use std::mem::transmute;
 
+        // Uploaded resources are used on the graphics queue without an ownership transfer, so
+        // the transfers happen on the graphics queue as well.
         // TODO find the "best" queue to use.
-        let queue = &self.queue_groups[self.compute_queue_idx];
+        let queue = &self.queue_groups[self.graphics_queue_idx];
 
         unsafe { transmute(queue) }
     }
 
     pub(crate) fn transfer_queue(&self) -> MutexGuard<types::CommandQueue<gfx::Transfer>> {
         use std::mem::transmute;
-        unsafe { transmute(self.queues[self.compute_queue_idx][0].lock().unwrap()) }
+        unsafe { transmute(self.queues[self.graphics_queue_idx][0].lock().unwrap()) }
     }
 
     pub(crate) unsafe fn release(self) {
@@ -8,26 +8,38 @@ pub mod resource_descriptor;
 
 pub use self::resource_descriptor::*;
 use crate::graph::pass::{ComputePass, ComputePipelineInfo, GraphicsPass, GraphicsPipelineInfo};
-use crate::graph::{ComputePassAccessor, GraphicPassAccessor, PassName, ResourceName};
+use crate::graph::{
+    compile_graph, derive_resource_usage, dot, AliasingPlan, CompileErrors, CompiledGraphInfo,
+    ComputePassAccessor, ExecutionGraph, GraphError, GraphicPassAccessor, PassName, ResourceName,
+};
 use crate::util::CowString;
+use std::any::Any;
 use std::cell::RefCell;
 
 use std::collections::HashMap;
 use std::rc::Rc;
+
+/// Kind of a pass in a graph.
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
-pub(crate) enum PassType {
+pub enum PassType {
+    /// A pass implementing [`ComputePass`](../trait.ComputePass.html).
     Compute,
+    /// A pass implementing [`GraphicsPass`](../trait.GraphicsPass.html).
     Graphics,
 }
 
 pub(crate) struct ComputePassContext<T: ComputePass> {
     pub(crate) pass: T,
     pub(crate) pipeline_infos: RefCell<HashMap<T::Config, ComputePipelineInfo>>,
+    /// Configurations whose pipelines were created during execution.
+    pub(crate) lazy_configs: RefCell<Vec<T::Config>>,
 }
 
 pub(crate) struct GraphicsPassContext<T: GraphicsPass> {
     pub(crate) pass: T,
     pub(crate) pipeline_infos: RefCell<HashMap<T::Config, GraphicsPipelineInfo>>,
+    /// Configurations whose pipelines were created during execution.
+    pub(crate) lazy_configs: RefCell<Vec<T::Config>>,
 }
 
 /// Name of a graph.
@@ -63,22 +75,45 @@ impl GraphBuilder {
         // To get around this, a list of "accessor closures" are provided which hide the concrete
         // type of the pass.
         let accessor = {
+            let async_compute = pass.async_compute();
+
             let pass = ComputePassContext {
                 pass,
                 pipeline_infos: RefCell::new(HashMap::new()),
+                lazy_configs: RefCell::new(vec![]),
             };
 
             let pass_ref_prepare = Rc::new(RefCell::new(pass));
             let pass_ref_describe = pass_ref_prepare.clone();
+            let pass_ref_warm = pass_ref_prepare.clone();
+            let pass_ref_lazy = pass_ref_prepare.clone();
             let pass_ref_execute = pass_ref_prepare.clone();
 
             ComputePassAccessor {
+                async_compute,
                 prepare: Box::new(move |store| {
                     pass_ref_prepare.borrow_mut().pass.prepare(store);
                 }),
                 describe: Box::new(move |res| {
                     pass_ref_describe.borrow_mut().pass.describe(res);
                 }),
+                warm_configs: Box::new(move || {
+                    let pass = pass_ref_warm.borrow();
+                    let mut infos = pass.pipeline_infos.borrow_mut();
+
+                    pass.pass
+                        .configs()
+                        .into_iter()
+                        .map(|config| {
+                            let info = pass.pass.configure(&config);
+                            infos.entry(config).or_insert(info).clone()
+                        })
+                        .collect()
+                }),
+                lazy_configs: Box::new(move || {
+                    let configs = pass_ref_lazy.borrow().lazy_configs.borrow().clone();
+                    Box::new(configs) as Box<dyn Any>
+                }),
                 execute: Box::new(move |store, dispatcher| {
                     let pass = pass_ref_execute.borrow();
                     {
@@ -108,10 +143,13 @@ impl GraphBuilder {
             let pass = GraphicsPassContext {
                 pass,
                 pipeline_infos: RefCell::new(HashMap::new()),
+                lazy_configs: RefCell::new(vec![]),
             };
 
             let pass_ref_prepare = Rc::new(RefCell::new(pass));
             let pass_ref_describe = pass_ref_prepare.clone();
+            let pass_ref_warm = pass_ref_prepare.clone();
+            let pass_ref_lazy = pass_ref_prepare.clone();
             let pass_ref_execute = pass_ref_prepare.clone();
 
             GraphicPassAccessor {
@@ -121,6 +159,23 @@ impl GraphBuilder {
                 describe: Box::new(move |res| {
                     pass_ref_describe.borrow_mut().pass.describe(res);
                 }),
+                warm_configs: Box::new(move || {
+                    let pass = pass_ref_warm.borrow();
+                    let mut infos = pass.pipeline_infos.borrow_mut();
+
+                    pass.pass
+                        .configs()
+                        .into_iter()
+                        .map(|config| {
+                            let info = pass.pass.configure(&config);
+                            infos.entry(config).or_insert(info).clone()
+                        })
+                        .collect()
+                }),
+                lazy_configs: Box::new(move || {
+                    let configs = pass_ref_lazy.borrow().lazy_configs.borrow().clone();
+                    Box::new(configs) as Box<dyn Any>
+                }),
                 execute: Box::new(move |store, dispatcher| {
                     let pass = pass_ref_execute.borrow();
                     {
@@ -143,4 +198,44 @@ impl GraphBuilder {
     pub fn add_target(&mut self, resource_name: impl Into<ResourceName>) {
         self.targets.push(resource_name.into());
     }
+
+    /// Compile the graph and render the result in the [DOT] format.
+    ///
+    /// This does not require a device, so it can be used to inspect a graph description before
+    /// it gets created. For graphs that are already created, see [`Context::graph_export_dot`].
+    ///
+    /// [DOT]: https://www.graphviz.org/doc/info/lang.html
+    /// [`Context::graph_export_dot`]: ../../struct.Context.html#method.graph_export_dot
+    pub fn export_dot(self) -> Result<String, GraphError> {
+        let name = self.name.clone();
+
+        let compiled = compile_graph(self)
+            .map_err(|errors| GraphError::CompilationErrors(errors.diagnostics()))?;
+
+        let exec_graph = ExecutionGraph::new(&compiled);
+
+        Ok(dot::export_dot(&name, &compiled, &exec_graph))
+    }
+
+    /// Compile the graph without creating any device resources.
+    ///
+    /// This performs the same validation, execution ordering and usage derivation as
+    /// [`Context::graph_create`], so graph descriptions can be checked on machines without a
+    /// GPU.
+    ///
+    /// [`Context::graph_create`]: ../../struct.Context.html#method.graph_create
+    pub fn compile(self) -> Result<CompiledGraphInfo, CompileErrors> {
+        let compiled = compile_graph(self)?;
+
+        let exec_graph = ExecutionGraph::new(&compiled);
+        let usages = derive_resource_usage(&exec_graph, &compiled);
+        let aliasing = AliasingPlan::new(&compiled, &exec_graph);
+
+        Ok(CompiledGraphInfo::new(
+            &compiled,
+            &exec_graph,
+            &usages,
+            &aliasing,
+        ))
+    }
 }
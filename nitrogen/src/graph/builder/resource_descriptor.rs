@@ -34,8 +34,8 @@ pub(crate) enum ResourceCreateInfo {
 pub struct ResourceDescriptor {
     /// Mapping from names to resource create information
     pub(crate) resource_creates: Vec<(ResourceName, ResourceCreateInfo)>,
-    /// Mapping from new name to src name
-    pub(crate) resource_moves: Vec<(ResourceName, ResourceName)>,
+    /// Mapping from new name to src name. Also contains the type the resource is moved as.
+    pub(crate) resource_moves: Vec<(ResourceName, ResourceName, ResourceType)>,
 
     /// List of resources that will be read from. Also contains read type and binding
     ///
@@ -46,6 +46,9 @@ pub struct ResourceDescriptor {
 
     /// List of resources that persist executions (backbuffername, localname)
     pub(crate) resource_backbuffer: Vec<(ResourceName, ResourceName)>,
+
+    /// List of image attachments of which only a single layer is rendered to.
+    pub(crate) attachment_layers: Vec<(ResourceName, u16)>,
 }
 
 impl ResourceDescriptor {
@@ -83,7 +86,8 @@ impl ResourceDescriptor {
 
     /// State the dependence on an image resource that will be moved to a new name.
     pub fn image_move<T0: Into<ResourceName>, T1: Into<ResourceName>>(&mut self, from: T0, to: T1) {
-        self.resource_moves.push((to.into(), from.into()));
+        self.resource_moves
+            .push((to.into(), from.into(), ResourceType::Image));
     }
 
     /// State the dependence on a color image that will be used as a color attachment of the
@@ -93,6 +97,26 @@ impl ResourceDescriptor {
             .push((name.into(), W::Image(ImageWriteType::Color), binding));
     }
 
+    /// State the dependence on a multisampled color image that will be used as a color attachment
+    /// of the framebuffer and resolved into a single-sampled image at the end of the pass.
+    pub fn image_write_color_resolve<T0, T1>(
+        &mut self,
+        msaa_name: T0,
+        resolved_name: T1,
+        binding: u8,
+    ) where
+        T0: Into<ResourceName>,
+        T1: Into<ResourceName>,
+    {
+        self.resource_writes
+            .push((msaa_name.into(), W::Image(ImageWriteType::Color), binding));
+        self.resource_writes.push((
+            resolved_name.into(),
+            W::Image(ImageWriteType::Resolve),
+            binding,
+        ));
+    }
+
     /// State the dependence on a depth-stencil image used for reading or writing as a framebuffer
     /// attachment.
     pub fn image_write_depth_stencil<T: Into<ResourceName>>(&mut self, name: T) {
@@ -103,6 +127,14 @@ impl ResourceDescriptor {
         ));
     }
 
+    /// Use only a single layer of an image as a framebuffer attachment.
+    ///
+    /// This can be used to render into a single face of a cube image or a single layer of an
+    /// image array. The image still has to be stated as a color or depth-stencil attachment.
+    pub fn image_attachment_layer<T: Into<ResourceName>>(&mut self, name: T, layer: u16) {
+        self.attachment_layers.push((name.into(), layer));
+    }
+
     /// State the dependence on a storage image used for reading or writing.
     pub fn image_write_storage<T: Into<ResourceName>>(&mut self, name: T, binding: u8) {
         self.resource_writes
@@ -152,7 +184,8 @@ impl ResourceDescriptor {
         from: T0,
         to: T1,
     ) {
-        self.resource_moves.push((to.into(), from.into()));
+        self.resource_moves
+            .push((to.into(), from.into(), ResourceType::Buffer));
     }
 
     /// State the dependence on a storage buffer that will be used for reading or writing.
@@ -206,7 +239,8 @@ impl ResourceDescriptor {
         from: T0,
         to: T1,
     ) {
-        self.resource_moves.push((to.into(), from.into()));
+        self.resource_moves
+            .push((to.into(), from.into(), ResourceType::Virtual));
     }
 
     /// State the dependence on a "virtual" resource.
@@ -246,6 +280,13 @@ pub struct ImageCreateInfo {
     pub format: image::ImageFormat,
     /// Size mode used to determine the dimensions of the image.
     pub size_mode: image::ImageSizeMode,
+    /// Number of layers of the image. Cube images have six layers per cube.
+    pub layers: u16,
+    /// Kind of view used when the image is read from.
+    pub kind: image::ViewKind,
+    /// Number of samples per pixel. Images with more than one sample can only be used as
+    /// attachments and have to be resolved to be read.
+    pub samples: u8,
 }
 
 /// Information needed to create a buffer resource.
@@ -321,6 +362,9 @@ pub enum ImageWriteType {
     /// A depth-stencil attachment of a Framebuffer
     DepthStencil,
 
+    /// The target a multisampled color attachment with the same binding is resolved into.
+    Resolve,
+
     /// A storage descriptor used for reading or writing.
     Storage,
 }
@@ -7,7 +7,7 @@ use std::collections::BTreeMap;
 use super::*;
 use crate::graph::builder::PassType;
 use crate::graph::ResourceDescriptor;
-use crate::graph::{ResourceCreateInfo, ResourceReadType, ResourceWriteType};
+use crate::graph::{ResourceCreateInfo, ResourceReadType, ResourceType, ResourceWriteType};
 
 // the Option<u8> represents a possible sampler binding
 pub(crate) type ResourceRead = (ResourceName, ResourceReadType, u8, Option<u8>);
@@ -17,13 +17,15 @@ pub(crate) struct GraphInput {
     pub(crate) pass_types: BTreeMap<PassId, PassType>,
 
     pub(crate) resource_creates: BTreeMap<PassId, Vec<(ResourceName, ResourceCreateInfo)>>,
-    pub(crate) resource_moves: BTreeMap<PassId, Vec<(ResourceName, ResourceName)>>,
+    pub(crate) resource_moves: BTreeMap<PassId, Vec<(ResourceName, ResourceName, ResourceType)>>,
 
     pub(crate) resource_reads: BTreeMap<PassId, Vec<ResourceRead>>,
     pub(crate) resource_writes: BTreeMap<PassId, Vec<(ResourceName, ResourceWriteType, u8)>>,
 
     // (backbuffer name, local name)
     pub(crate) resource_backbuffer: BTreeMap<PassId, Vec<(ResourceName, ResourceName)>>,
+
+    pub(crate) attachment_layers: BTreeMap<PassId, Vec<(ResourceName, u16)>>,
 }
 
 impl GraphInput {
@@ -42,5 +44,7 @@ impl GraphInput {
         self.resource_writes.insert(id, res.resource_writes);
 
         self.resource_backbuffer.insert(id, res.resource_backbuffer);
+
+        self.attachment_layers.insert(id, res.attachment_layers);
     }
 }
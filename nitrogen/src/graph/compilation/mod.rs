@@ -66,6 +66,76 @@ pub enum CompileError {
         /// Pass in which the resource has been moved before.
         prev_move: PassId,
     },
+    /// Passes depend on each other in a loop, so no execution order can be found.
+    DependencyCycle {
+        /// Passes that make up the loop.
+        ///
+        /// Every pass depends on the resource at the same index, which is created by the next pass
+        /// in the list. The last pass depends on a resource created by the first one.
+        passes: Vec<PassId>,
+        /// Resources through which the passes depend on each other.
+        resources: Vec<ResourceName>,
+    },
+    /// Two resources are bound to the same descriptor binding in one pass.
+    DescriptorBindingConflict {
+        /// Pass in which the conflict occurs.
+        pass: PassId,
+        /// Binding point that is used more than once.
+        binding: u8,
+        /// Resource that attempted to use the binding.
+        res: ResourceName,
+        /// Whether the binding was requested for the sampler of `res`.
+        res_sampler: bool,
+        /// Resource that used the binding before.
+        prev: ResourceName,
+        /// Whether the binding was used by the sampler of `prev`.
+        prev_sampler: bool,
+    },
+    /// Two resources are written to the same color attachment in one pass.
+    ColorAttachmentBindingConflict {
+        /// Pass in which the conflict occurs.
+        pass: PassId,
+        /// Attachment binding that is used more than once.
+        binding: u8,
+        /// Resource that attempted to use the binding.
+        res: ResourceName,
+        /// Resource that used the binding before.
+        prev: ResourceName,
+    },
+    /// The color attachment bindings of a pass are not contiguous starting at 0.
+    ColorAttachmentBindingGap {
+        /// Pass in which the gap occurs.
+        pass: PassId,
+        /// Binding that is not used by any color attachment.
+        binding: u8,
+        /// Resource with the first binding following the gap.
+        next: ResourceName,
+    },
+    /// A multisampled image is used as something other than an attachment.
+    ///
+    /// Multisampled images can't be sampled or used as storage images, they have to be resolved
+    /// into a single-sampled image first.
+    MultisampledImageNotAttachment {
+        /// Name of the image.
+        res: ResourceName,
+        /// Pass in which the image is used.
+        pass: PassId,
+    },
+    /// A resolve attachment doesn't resolve a multisampled image into a single-sampled image.
+    InvalidResolve {
+        /// Pass in which the resolve happens.
+        pass: PassId,
+        /// Color attachment binding of the resolve.
+        binding: u8,
+        /// Color attachment that is resolved.
+        source: ResourceName,
+        /// Number of samples of `source`.
+        source_samples: u8,
+        /// Image the color attachment is resolved into.
+        target: ResourceName,
+        /// Number of samples of `target`.
+        target_samples: u8,
+    },
 }
 
 impl CompileError {
@@ -121,12 +191,130 @@ impl CompileError {
                 pass_name,
                 prev_move_pass,)
             }
+            CompileError::DependencyCycle { passes, resources } => {
+                let steps = passes
+                    .iter()
+                    .zip(resources.iter())
+                    .enumerate()
+                    .map(|(i, (pass, res))| {
+                        let next = passes[(i + 1) % passes.len()];
+
+                        format!(
+                            "\"{}\" depends on \"{}\" from \"{}\"",
+                            pass_names[pass.0], res, pass_names[next.0],
+                        )
+                    })
+                    .collect::<Vec<_>>();
+
+                format!(
+                    "Passes depend on each other in a cycle: {}.",
+                    steps.join(", "),
+                )
+            }
+            CompileError::DescriptorBindingConflict {
+                pass,
+                binding,
+                res,
+                res_sampler,
+                prev,
+                prev_sampler,
+            } => {
+                let pass_name = pass_names[pass.0].clone();
+
+                let describe = |name: &ResourceName, sampler: bool| {
+                    if sampler {
+                        format!("the sampler of \"{}\"", name)
+                    } else {
+                        format!("\"{}\"", name)
+                    }
+                };
+
+                format!(
+                    "Binding {} in pass \"{}\" is used by {}, but was used by {} before.",
+                    binding,
+                    pass_name,
+                    describe(&res, res_sampler),
+                    describe(&prev, prev_sampler),
+                )
+            }
+            CompileError::ColorAttachmentBindingConflict {
+                pass,
+                binding,
+                res,
+                prev,
+            } => {
+                let pass_name = pass_names[pass.0].clone();
+
+                format!(
+                    "Color attachment {} in pass \"{}\" is written by \"{}\", but was written by \"{}\" before.",
+                    binding, pass_name, res, prev,
+                )
+            }
+            CompileError::ColorAttachmentBindingGap {
+                pass,
+                binding,
+                next,
+            } => {
+                let pass_name = pass_names[pass.0].clone();
+
+                format!(
+                    "Color attachment {} in pass \"{}\" is not written, but \"{}\" uses a higher binding. Color attachments must be bound contiguously starting at 0.",
+                    binding, pass_name, next,
+                )
+            }
+            CompileError::MultisampledImageNotAttachment { res, pass } => {
+                let pass_name = pass_names[pass.0].clone();
+
+                format!(
+                    "Multisampled image \"{}\" is used as a descriptor in pass \"{}\". Multisampled images can only be used as attachments.",
+                    res, pass_name,
+                )
+            }
+            CompileError::InvalidResolve {
+                pass,
+                binding,
+                source,
+                source_samples,
+                target,
+                target_samples,
+            } => {
+                let pass_name = pass_names[pass.0].clone();
+
+                format!(
+                    "Color attachment {} in pass \"{}\" resolves \"{}\" with {} sample(s) into \"{}\" with {} sample(s). Resolves need a multisampled source and a single-sampled target.",
+                    binding, pass_name, source, source_samples, target, target_samples,
+                )
+            }
         }
     }
 }
 
+/// Errors found while compiling a graph.
+#[derive(Debug, Clone)]
+pub struct CompileErrors {
+    pub(crate) pass_names: Vec<PassName>,
+    /// All errors found in the graph.
+    pub errors: Vec<CompileError>,
+}
+
+impl CompileErrors {
+    /// Retrieve the name of a pass referenced in one of the errors.
+    pub fn pass_name(&self, pass: PassId) -> Option<&PassName> {
+        self.pass_names.get(pass.0)
+    }
+
+    /// Create human readable messages for all errors.
+    pub fn diagnostics(&self) -> Vec<String> {
+        self.errors
+            .iter()
+            .cloned()
+            .map(|err| err.diagnostic(&self.pass_names))
+            .collect()
+    }
+}
+
 pub(crate) struct CompiledGraph {
-    pub(crate) _pass_names: Vec<PassName>,
+    pub(crate) pass_names: Vec<PassName>,
 
     pub(crate) compute_passes: HashMap<PassId, ComputePassAccessor>,
     pub(crate) graphic_passes: HashMap<PassId, GraphicPassAccessor>,
@@ -140,9 +328,7 @@ pub(crate) struct CompiledGraph {
     pub(crate) targets: HashSet<ResourceId>,
 }
 
-pub(crate) fn compile_graph(
-    builder: GraphBuilder,
-) -> Result<CompiledGraph, (Vec<PassName>, Vec<CompileError>)> {
+pub(crate) fn compile_graph(builder: GraphBuilder) -> Result<CompiledGraph, CompileErrors> {
     let mut errors = vec![];
 
     let mut input = GraphInput::default();
@@ -183,6 +369,23 @@ pub(crate) fn compile_graph(
     // replace all resource names with IDs.
     let resolved = resolve_input(input, &mut errors);
 
+    // passes that (indirectly) depend on themselves can't be put in any order.
+    for cycle in resolved.dependency_cycles() {
+        let (passes, resources) = cycle
+            .into_iter()
+            .map(|(pass, res)| {
+                let name = resolved
+                    .resource_name(res)
+                    .cloned()
+                    .unwrap_or_else(|| format!("{:?}", res).into());
+
+                (pass, name)
+            })
+            .unzip();
+
+        errors.push(CompileError::DependencyCycle { passes, resources });
+    }
+
     // replace target names with IDs
     let targets = builder
         .targets
@@ -235,7 +438,7 @@ pub(crate) fn compile_graph(
 
     if errors.is_empty() {
         Ok(CompiledGraph {
-            _pass_names: pass_names,
+            pass_names,
 
             contextual_passes,
             contextual_resources,
@@ -249,6 +452,397 @@ pub(crate) fn compile_graph(
             targets,
         })
     } else {
-        Err((pass_names, errors))
+        Err(CompileErrors { pass_names, errors })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::graph::test_util::{buffer_info, image_info, DescribePass};
+    use crate::graph::ImageCreateInfo;
+    use crate::image::ViewKind;
+
+    fn compile_errors(builder: GraphBuilder) -> Vec<CompileError> {
+        match compile_graph(builder) {
+            Ok(_) => vec![],
+            Err(errors) => errors.errors,
+        }
+    }
+
+    #[test]
+    fn chain_has_no_cycle() {
+        let mut builder = GraphBuilder::new("chain");
+
+        builder.add_compute_pass(
+            "first",
+            DescribePass::new(|res: &mut ResourceDescriptor| res.virtual_create("a")),
+        );
+        builder.add_compute_pass(
+            "second",
+            DescribePass::new(|res: &mut ResourceDescriptor| {
+                res.virtual_read("a");
+                res.virtual_create("b");
+            }),
+        );
+        builder.add_target("b");
+
+        assert!(compile_errors(builder).is_empty());
+    }
+
+    #[test]
+    fn cycle_is_detected() {
+        let mut builder = GraphBuilder::new("cycle");
+
+        builder.add_compute_pass(
+            "first",
+            DescribePass::new(|res: &mut ResourceDescriptor| {
+                res.virtual_read("b");
+                res.virtual_create("a");
+            }),
+        );
+        builder.add_compute_pass(
+            "second",
+            DescribePass::new(|res: &mut ResourceDescriptor| {
+                res.virtual_read("a");
+                res.virtual_create("b");
+            }),
+        );
+        builder.add_target("b");
+
+        let errors = compile_errors(builder);
+        assert_eq!(errors.len(), 1);
+
+        match &errors[0] {
+            CompileError::DependencyCycle { passes, resources } => {
+                assert_eq!(passes, &[PassId(0), PassId(1)]);
+                assert_eq!(resources, &["b", "a"]);
+            }
+            err => panic!("unexpected error {:?}", err),
+        }
+    }
+
+    #[test]
+    fn descriptor_binding_conflicts_are_detected() {
+        let mut builder = GraphBuilder::new("descriptors");
+
+        builder.add_compute_pass(
+            "create",
+            DescribePass::new(|res: &mut ResourceDescriptor| {
+                res.image_create("image", image_info());
+                res.buffer_create("buffer", buffer_info());
+            }),
+        );
+        builder.add_compute_pass(
+            "use",
+            DescribePass::new(|res: &mut ResourceDescriptor| {
+                res.buffer_write_storage("buffer", 1);
+                res.image_read_color("image", 1, Some(2));
+                res.virtual_create("out");
+            }),
+        );
+        builder.add_target("out");
+
+        let errors = compile_errors(builder);
+        assert_eq!(errors.len(), 1);
+
+        match &errors[0] {
+            CompileError::DescriptorBindingConflict {
+                pass,
+                binding,
+                res,
+                res_sampler,
+                prev,
+                prev_sampler,
+            } => {
+                assert_eq!(*pass, PassId(1));
+                assert_eq!(*binding, 1);
+                assert_eq!(res, "image");
+                assert!(!*res_sampler);
+                assert_eq!(prev, "buffer");
+                assert!(!*prev_sampler);
+            }
+            err => panic!("unexpected error {:?}", err),
+        }
+    }
+
+    #[test]
+    fn sampler_binding_conflict_is_detected() {
+        let mut builder = GraphBuilder::new("sampler");
+
+        builder.add_compute_pass(
+            "create",
+            DescribePass::new(|res: &mut ResourceDescriptor| {
+                res.image_create("a", image_info());
+                res.image_create("b", image_info());
+            }),
+        );
+        builder.add_compute_pass(
+            "use",
+            DescribePass::new(|res: &mut ResourceDescriptor| {
+                res.image_read_color("a", 0, Some(1));
+                res.image_read_color("b", 1, None);
+                res.virtual_create("out");
+            }),
+        );
+        builder.add_target("out");
+
+        let errors = compile_errors(builder);
+        assert_eq!(errors.len(), 1);
+
+        match &errors[0] {
+            CompileError::DescriptorBindingConflict {
+                binding,
+                res,
+                res_sampler,
+                prev,
+                prev_sampler,
+                ..
+            } => {
+                assert_eq!(*binding, 1);
+                assert_eq!(res, "b");
+                assert!(!*res_sampler);
+                assert_eq!(prev, "a");
+                assert!(*prev_sampler);
+            }
+            err => panic!("unexpected error {:?}", err),
+        }
+    }
+
+    #[test]
+    fn resolve_attachments_share_color_bindings() {
+        let mut builder = GraphBuilder::new("attachments");
+
+        builder.add_graphics_pass(
+            "msaa",
+            DescribePass::new(|res: &mut ResourceDescriptor| {
+                res.image_create(
+                    "msaa",
+                    ImageCreateInfo {
+                        samples: 4,
+                        ..image_info()
+                    },
+                );
+                res.image_create("resolved", image_info());
+                res.image_write_color_resolve("msaa", "resolved", 0);
+            }),
+        );
+        builder.add_target("resolved");
+
+        assert!(compile_errors(builder).is_empty());
+    }
+
+    #[test]
+    fn sample_counts_are_checked() {
+        let mut builder = GraphBuilder::new("samples");
+
+        let msaa_info = ImageCreateInfo {
+            samples: 4,
+            ..image_info()
+        };
+
+        builder.add_graphics_pass(
+            "render",
+            DescribePass::new(move |res: &mut ResourceDescriptor| {
+                res.image_create("single", image_info());
+                res.image_create("resolved", image_info());
+                res.image_write_color_resolve("single", "resolved", 0);
+
+                res.image_create("msaa", msaa_info.clone());
+                res.image_create("msaa_target", msaa_info.clone());
+                res.image_write_color_resolve("msaa", "msaa_target", 1);
+            }),
+        );
+        builder.add_graphics_pass(
+            "sample",
+            DescribePass::new(|res: &mut ResourceDescriptor| {
+                res.image_create("out", image_info());
+                res.image_write_color("out", 0);
+                res.image_read_color("msaa", 0, Some(1));
+                res.virtual_read("resolved");
+                res.virtual_read("msaa_target");
+            }),
+        );
+        builder.add_target("out");
+
+        let errors = compile_errors(builder);
+
+        let resolves = errors
+            .iter()
+            .filter_map(|err| match err {
+                CompileError::InvalidResolve {
+                    binding,
+                    source_samples,
+                    target_samples,
+                    ..
+                } => Some((*binding, *source_samples, *target_samples)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(errors.len(), 3);
+        assert_eq!(resolves, vec![(0, 1, 1), (1, 4, 4)]);
+        assert!(errors.iter().any(|err| match err {
+            CompileError::MultisampledImageNotAttachment { res, .. } => res == "msaa",
+            _ => false,
+        }));
+    }
+
+    #[test]
+    fn attachment_layers_are_resolved() {
+        let mut builder = GraphBuilder::new("layers");
+
+        builder.add_graphics_pass(
+            "face",
+            DescribePass::new(|res: &mut ResourceDescriptor| {
+                res.image_create(
+                    "cube",
+                    ImageCreateInfo {
+                        layers: 6,
+                        kind: ViewKind::Cube,
+                        ..image_info()
+                    },
+                );
+                res.image_write_color("cube", 0);
+                res.image_attachment_layer("cube", 3);
+            }),
+        );
+        builder.add_target("cube");
+
+        let compiled = compile_graph(builder).unwrap();
+        let resolved = &compiled.graph_resources;
+
+        let cube = resolved.name_lookup["cube"];
+        assert_eq!(resolved.pass_attachment_layers[&PassId(0)][&cube], 3);
+    }
+
+    #[test]
+    fn attachment_layers_of_invalid_resources_are_detected() {
+        let mut builder = GraphBuilder::new("layers");
+
+        builder.add_graphics_pass(
+            "face",
+            DescribePass::new(|res: &mut ResourceDescriptor| {
+                res.image_create("image", image_info());
+                res.image_write_color("image", 0);
+                res.image_attachment_layer("missing", 0);
+            }),
+        );
+        builder.add_target("image");
+
+        let errors = compile_errors(builder);
+        assert_eq!(errors.len(), 1);
+
+        match &errors[0] {
+            CompileError::ReferencedInvalidResource { res, .. } => assert_eq!(res, "missing"),
+            err => panic!("unexpected error {:?}", err),
+        }
+    }
+
+    #[test]
+    fn color_attachment_bindings_are_checked() {
+        let mut builder = GraphBuilder::new("attachments");
+
+        builder.add_compute_pass(
+            "duplicate",
+            DescribePass::new(|res: &mut ResourceDescriptor| {
+                res.image_create("a", image_info());
+                res.image_create("b", image_info());
+                res.image_write_color("a", 0);
+                res.image_write_color("b", 0);
+            }),
+        );
+        builder.add_compute_pass(
+            "gap",
+            DescribePass::new(|res: &mut ResourceDescriptor| {
+                res.image_create("c", image_info());
+                res.image_create("d", image_info());
+                res.image_write_color("c", 0);
+                res.image_write_color("d", 2);
+            }),
+        );
+        builder.add_target("b");
+        builder.add_target("d");
+
+        let errors = compile_errors(builder);
+        assert_eq!(errors.len(), 2);
+
+        match &errors[0] {
+            CompileError::ColorAttachmentBindingConflict {
+                pass,
+                binding,
+                res,
+                prev,
+            } => {
+                assert_eq!(*pass, PassId(0));
+                assert_eq!(*binding, 0);
+                assert_eq!(res, "b");
+                assert_eq!(prev, "a");
+            }
+            err => panic!("unexpected error {:?}", err),
+        }
+
+        match &errors[1] {
+            CompileError::ColorAttachmentBindingGap {
+                pass,
+                binding,
+                next,
+            } => {
+                assert_eq!(*pass, PassId(1));
+                assert_eq!(*binding, 1);
+                assert_eq!(next, "d");
+            }
+            err => panic!("unexpected error {:?}", err),
+        }
+    }
+
+    #[test]
+    fn resource_type_mismatch_is_detected() {
+        let mut builder = GraphBuilder::new("types");
+
+        builder.add_compute_pass(
+            "create",
+            DescribePass::new(|res: &mut ResourceDescriptor| {
+                res.image_create("image", image_info());
+                res.buffer_create("buffer", buffer_info());
+                res.virtual_create("virtual");
+            }),
+        );
+        builder.add_compute_pass(
+            "use",
+            DescribePass::new(|res: &mut ResourceDescriptor| {
+                res.buffer_read_storage("image", 0);
+                res.image_move("buffer", "moved");
+                res.image_read_color("virtual", 1, None);
+                res.virtual_read("image");
+            }),
+        );
+        builder.add_target("moved");
+
+        let errors = compile_errors(builder);
+
+        let mismatches = errors
+            .iter()
+            .filter_map(|err| match err {
+                CompileError::ResourceTypeMismatch {
+                    res,
+                    used_as,
+                    expected,
+                    ..
+                } => Some((res.as_ref(), *used_as, *expected)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(errors.len(), 3);
+        assert_eq!(
+            mismatches,
+            vec![
+                ("buffer", ResourceType::Image, ResourceType::Buffer),
+                ("image", ResourceType::Buffer, ResourceType::Image),
+                ("virtual", ResourceType::Image, ResourceType::Virtual),
+            ]
+        );
     }
 }
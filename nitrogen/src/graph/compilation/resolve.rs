@@ -7,7 +7,7 @@ use std::collections::{BTreeMap, BTreeSet};
 use super::*;
 
 use super::GraphInput;
-use crate::graph::builder::resource_descriptor::ImageInfo;
+use crate::graph::builder::resource_descriptor::{ImageInfo, ImageReadType};
 use crate::graph::pass::dispatcher::ResourceAccessType;
 use crate::graph::PassType;
 
@@ -38,6 +38,8 @@ pub(crate) struct GraphWithNamesResolved {
     pub(crate) pass_writes: BTreeMap<PassId, BTreeSet<(ResourceId, ResourceWriteType, u8)>>,
     /// Resources that a pass reads from
     pub(crate) pass_reads: BTreeMap<PassId, BTreeSet<ReadsByResource>>,
+    /// Attachments of which a pass only renders into a single layer
+    pub(crate) pass_attachment_layers: BTreeMap<PassId, BTreeMap<ResourceId, u16>>,
 }
 
 impl GraphWithNamesResolved {
@@ -137,7 +139,9 @@ impl GraphWithNamesResolved {
 
                     match write_type {
                         ResourceWriteType::Image(img) => match img {
-                            ImageWriteType::Color | ImageWriteType::DepthStencil => {
+                            ImageWriteType::Color
+                            | ImageWriteType::DepthStencil
+                            | ImageWriteType::Resolve => {
                                 let bb = self.is_backbuffer_resource(res_id);
                                 let context = self.is_resource_context_dependent(res_id);
 
@@ -164,6 +168,77 @@ impl GraphWithNamesResolved {
         }
     }
 
+    pub(crate) fn resource_name(&self, id: ResourceId) -> Option<&ResourceName> {
+        self.name_lookup
+            .iter()
+            .find(|(_, res)| **res == id)
+            .map(|(name, _)| name)
+    }
+
+    /// Find all loops in the dependencies between passes.
+    ///
+    /// Each cycle is a list of `(pass, resource)` pairs. The pass depends on the resource, which
+    /// is created by the pass of the next pair (wrapping around at the end).
+    pub(crate) fn dependency_cycles(&self) -> Vec<Vec<(PassId, ResourceId)>> {
+        #[derive(Copy, Clone, PartialEq, Eq)]
+        enum Mark {
+            Visiting,
+            Done,
+        }
+
+        // Plain depth-first search. Every edge that points back to a pass which is still being
+        // visited closes a cycle, which is the part of the path starting at that pass.
+        fn visit(
+            graph: &GraphWithNamesResolved,
+            pass: PassId,
+            marks: &mut BTreeMap<PassId, Mark>,
+            path: &mut Vec<(PassId, ResourceId)>,
+            cycles: &mut Vec<Vec<(PassId, ResourceId)>>,
+        ) {
+            marks.insert(pass, Mark::Visiting);
+
+            if let Some(depends) = graph.pass_ext_depends.get(&pass) {
+                for res in depends {
+                    let dep_pass = match graph.defines.get(res) {
+                        Some(dep_pass) => *dep_pass,
+                        None => continue,
+                    };
+
+                    path.push((pass, *res));
+
+                    match marks.get(&dep_pass) {
+                        Some(Mark::Visiting) => {
+                            let start = path
+                                .iter()
+                                .position(|(p, _)| *p == dep_pass)
+                                .expect("Pass is visited but not on the path");
+
+                            cycles.push(path[start..].to_vec());
+                        }
+                        Some(Mark::Done) => {}
+                        None => visit(graph, dep_pass, marks, path, cycles),
+                    }
+
+                    path.pop();
+                }
+            }
+
+            marks.insert(pass, Mark::Done);
+        }
+
+        let mut marks = BTreeMap::new();
+        let mut path = vec![];
+        let mut cycles = vec![];
+
+        for pass in self.pass_types.keys() {
+            if !marks.contains_key(pass) {
+                visit(self, *pass, &mut marks, &mut path, &mut cycles);
+            }
+        }
+
+        cycles
+    }
+
     pub(crate) fn is_backbuffer_resource(&self, id: ResourceId) -> Option<ResourceName> {
         let info = if let Some((_, info)) = self.create_info(id) {
             info
@@ -195,9 +270,6 @@ pub(crate) fn resolve_input(
     input: GraphInput,
     errors: &mut Vec<CompileError>,
 ) -> GraphWithNamesResolved {
-    // TODO check for duplicated binding points everywhere?
-    // TODO cycle detection?
-
     let mut resource_name_lookup = BTreeMap::new();
 
     let mut resource_defines = BTreeMap::new();
@@ -209,6 +281,7 @@ pub(crate) fn resolve_input(
     let mut pass_ext_depends = BTreeMap::<_, BTreeSet<_>>::new();
     let mut pass_writes = BTreeMap::<_, BTreeSet<_>>::new();
     let mut pass_reads = BTreeMap::<_, BTreeSet<_>>::new();
+    let mut pass_attachment_layers = BTreeMap::<_, BTreeMap<_, _>>::new();
 
     // generate IDs for all "new" resources.
 
@@ -236,7 +309,7 @@ pub(crate) fn resolve_input(
 
     for (pass, ress) in &input.resource_moves {
         let creates = pass_creates.entry(*pass).or_default();
-        for (new_name, _old_name) in ress {
+        for (new_name, _old_name, _ty) in ress {
             if let Some(id) = resource_name_lookup.get(new_name) {
                 errors.push(CompileError::ResourceRedefined {
                     pass: *pass,
@@ -257,10 +330,13 @@ pub(crate) fn resolve_input(
 
     // "back-reference" old resources
 
+    // moves can only be type-checked once all of them are known.
+    let mut move_types = vec![];
+
     for (pass, ress) in input.resource_moves {
         let depends = pass_ext_depends.entry(pass).or_default();
 
-        for (new_name, old_name) in ress {
+        for (new_name, old_name, ty) in ress {
             let old_id = if let Some(id) = resource_name_lookup.get(&old_name) {
                 *id
             } else {
@@ -297,6 +373,8 @@ pub(crate) fn resolve_input(
             resource_moves_from.insert(new_id, old_id);
             resource_moves_to.insert(old_id, new_id);
 
+            move_types.push((pass, old_name, new_id, ty));
+
             // If the old id was something that is made in another pass it means we depend on
             // another pass
             if !pass_creates
@@ -309,9 +387,16 @@ pub(crate) fn resolve_input(
         }
     }
 
+    // Bindings used by each pass. Descriptors (including samplers) and color attachments each
+    // have their own range of binding points.
+    let mut descriptor_bindings = BTreeMap::<_, BTreeMap<u8, (ResourceName, bool)>>::new();
+    let mut color_bindings = BTreeMap::<_, BTreeMap<u8, ResourceName>>::new();
+
     for (pass, ress) in input.resource_writes {
         let depends = pass_ext_depends.entry(pass).or_default();
         let pass_writes = pass_writes.entry(pass).or_default();
+        let descriptor_bindings = descriptor_bindings.entry(pass).or_default();
+        let color_bindings = color_bindings.entry(pass).or_default();
 
         for (name, ty, binding) in ress {
             let id = if let Some(id) = resource_name_lookup.get(&name) {
@@ -324,7 +409,30 @@ pub(crate) fn resolve_input(
                 continue;
             };
 
-            pass_writes.insert((id, ty, binding));
+            if pass_writes.insert((id, ty, binding)) {
+                match ty {
+                    ResourceWriteType::Image(ImageWriteType::Color) => {
+                        if let Some(prev) = color_bindings.get(&binding) {
+                            errors.push(CompileError::ColorAttachmentBindingConflict {
+                                pass,
+                                binding,
+                                res: name.clone(),
+                                prev: prev.clone(),
+                            });
+                        } else {
+                            color_bindings.insert(binding, name.clone());
+                        }
+                    }
+                    // depth-stencil attachments don't have a binding and resolve attachments
+                    // share the binding of the color attachment they resolve.
+                    ResourceWriteType::Image(ImageWriteType::DepthStencil)
+                    | ResourceWriteType::Image(ImageWriteType::Resolve) => {}
+                    ResourceWriteType::Image(ImageWriteType::Storage)
+                    | ResourceWriteType::Buffer(_) => {
+                        bind_descriptor(descriptor_bindings, errors, pass, binding, &name, false);
+                    }
+                }
+            }
 
             // If the id is something that is made in another pass it means we depend on another
             // pass
@@ -338,9 +446,26 @@ pub(crate) fn resolve_input(
         }
     }
 
+    // color attachments are referenced by their position, so they can't have any holes.
+    for (pass, bindings) in &color_bindings {
+        let gap = bindings
+            .iter()
+            .enumerate()
+            .find(|(i, (binding, _))| *i != usize::from(**binding));
+
+        if let Some((i, (_, next))) = gap {
+            errors.push(CompileError::ColorAttachmentBindingGap {
+                pass: *pass,
+                binding: i as u8,
+                next: next.clone(),
+            });
+        }
+    }
+
     for (pass, ress) in input.resource_reads {
         let depends = pass_ext_depends.entry(pass).or_default();
         let pass_reads = pass_reads.entry(pass).or_default();
+        let descriptor_bindings = descriptor_bindings.entry(pass).or_default();
 
         for (name, ty, binding, sampler_binding) in ress {
             let id = if let Some(id) = resource_name_lookup.get(&name) {
@@ -353,7 +478,27 @@ pub(crate) fn resolve_input(
                 continue;
             };
 
-            pass_reads.insert((id, ty, binding, sampler_binding));
+            if pass_reads.insert((id, ty, binding, sampler_binding)) {
+                match ty {
+                    // neither of these are accessed through descriptors.
+                    ResourceReadType::Virtual => {}
+                    ResourceReadType::Image(ImageReadType::DepthStencil) => {}
+                    ResourceReadType::Image(_) | ResourceReadType::Buffer(_) => {
+                        bind_descriptor(descriptor_bindings, errors, pass, binding, &name, false);
+
+                        if let Some(sampler_binding) = sampler_binding {
+                            bind_descriptor(
+                                descriptor_bindings,
+                                errors,
+                                pass,
+                                sampler_binding,
+                                &name,
+                                true,
+                            );
+                        }
+                    }
+                }
+            }
 
             // If the id is something that is made in another pass it means we depend on another
             // pass
@@ -367,6 +512,24 @@ pub(crate) fn resolve_input(
         }
     }
 
+    for (pass, layers) in input.attachment_layers {
+        let pass_attachment_layers = pass_attachment_layers.entry(pass).or_default();
+
+        for (name, layer) in layers {
+            let id = if let Some(id) = resource_name_lookup.get(&name) {
+                *id
+            } else {
+                errors.push(CompileError::ReferencedInvalidResource {
+                    pass,
+                    res: name.clone(),
+                });
+                continue;
+            };
+
+            pass_attachment_layers.insert(id, layer);
+        }
+    }
+
     for (pass, creates) in input.resource_backbuffer {
         for (_bname, lname) in creates {
             if resource_name_lookup.get(&lname).is_none() {
@@ -379,7 +542,7 @@ pub(crate) fn resolve_input(
         }
     }
 
-    GraphWithNamesResolved {
+    let resolved = GraphWithNamesResolved {
         name_lookup: resource_name_lookup,
 
         pass_types: input.pass_types,
@@ -393,5 +556,162 @@ pub(crate) fn resolve_input(
         pass_ext_depends,
         pass_reads,
         pass_writes,
+        pass_attachment_layers,
+    };
+
+    check_resource_types(&resolved, &move_types, errors);
+    check_sample_counts(&resolved, errors);
+
+    resolved
+}
+
+/// Check that all resources are used as the type they were created as.
+fn check_resource_types(
+    graph: &GraphWithNamesResolved,
+    moves: &[(PassId, ResourceName, ResourceId, ResourceType)],
+    errors: &mut Vec<CompileError>,
+) {
+    let mut check = |pass: PassId, res: ResourceId, name: Option<&ResourceName>, used_as| {
+        let expected = match graph.create_info(res) {
+            Some((_, info)) => ResourceType::from(info),
+            None => return,
+        };
+
+        if expected != used_as {
+            let name = name
+                .or_else(|| graph.resource_name(res))
+                .cloned()
+                .unwrap_or_else(|| format!("{:?}", res).into());
+
+            errors.push(CompileError::ResourceTypeMismatch {
+                res: name,
+                pass,
+                used_as,
+                expected,
+            });
+        }
+    };
+
+    for (pass, old_name, new_id, ty) in moves {
+        check(*pass, *new_id, Some(old_name), *ty);
+    }
+
+    for (pass, writes) in &graph.pass_writes {
+        for (res, ty, _) in writes {
+            check(*pass, *res, None, ResourceType::from(*ty));
+        }
+    }
+
+    for (pass, reads) in &graph.pass_reads {
+        for (res, ty, _, _) in reads {
+            // virtual reads only express a dependency, so they are valid on any resource.
+            if let ResourceReadType::Virtual = ty {
+                continue;
+            }
+
+            check(*pass, *res, None, ResourceType::from(*ty));
+        }
+    }
+}
+
+/// Check that multisampled images are only used as attachments and that resolve attachments
+/// resolve a multisampled image into a single-sampled one.
+fn check_sample_counts(graph: &GraphWithNamesResolved, errors: &mut Vec<CompileError>) {
+    // `None` for resources which are not images, those are reported as type mismatches.
+    let samples = |res: ResourceId| match graph.create_info(res) {
+        Some((_, ResourceCreateInfo::Image(ImageInfo::Create(info)))) => Some(info.samples),
+        Some((_, ResourceCreateInfo::Image(ImageInfo::BackbufferRead { .. }))) => Some(1),
+        _ => None,
+    };
+
+    let name = |res: ResourceId| {
+        graph
+            .resource_name(res)
+            .cloned()
+            .unwrap_or_else(|| format!("{:?}", res).into())
+    };
+
+    let is_multisampled = |res: ResourceId| samples(res).map_or(false, |samples| samples > 1);
+
+    for (pass, writes) in &graph.pass_writes {
+        for (res, ty, binding) in writes {
+            match ty {
+                ResourceWriteType::Image(ImageWriteType::Resolve) => {
+                    let source = writes.iter().find(|(_, ty, source_binding)| {
+                        *ty == ResourceWriteType::Image(ImageWriteType::Color)
+                            && source_binding == binding
+                    });
+
+                    let source = match source {
+                        Some((source, _, _)) => *source,
+                        None => continue,
+                    };
+
+                    let (source_samples, target_samples) = match (samples(source), samples(*res)) {
+                        (Some(source), Some(target)) => (source, target),
+                        _ => continue,
+                    };
+
+                    if source_samples <= 1 || target_samples != 1 {
+                        errors.push(CompileError::InvalidResolve {
+                            pass: *pass,
+                            binding: *binding,
+                            source: name(source),
+                            source_samples,
+                            target: name(*res),
+                            target_samples,
+                        });
+                    }
+                }
+                ResourceWriteType::Image(ImageWriteType::Storage) => {
+                    if is_multisampled(*res) {
+                        errors.push(CompileError::MultisampledImageNotAttachment {
+                            res: name(*res),
+                            pass: *pass,
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    for (pass, reads) in &graph.pass_reads {
+        for (res, ty, _, _) in reads {
+            let descriptor = match ty {
+                ResourceReadType::Image(ImageReadType::Color)
+                | ResourceReadType::Image(ImageReadType::Storage) => true,
+                _ => false,
+            };
+
+            if descriptor && is_multisampled(*res) {
+                errors.push(CompileError::MultisampledImageNotAttachment {
+                    res: name(*res),
+                    pass: *pass,
+                });
+            }
+        }
+    }
+}
+
+fn bind_descriptor(
+    bindings: &mut BTreeMap<u8, (ResourceName, bool)>,
+    errors: &mut Vec<CompileError>,
+    pass: PassId,
+    binding: u8,
+    res: &ResourceName,
+    sampler: bool,
+) {
+    if let Some((prev, prev_sampler)) = bindings.get(&binding) {
+        errors.push(CompileError::DescriptorBindingConflict {
+            pass,
+            binding,
+            res: res.clone(),
+            res_sampler: sampler,
+            prev: prev.clone(),
+            prev_sampler: *prev_sampler,
+        });
+    } else {
+        bindings.insert(binding, (res.clone(), sampler));
     }
 }
@@ -0,0 +1,293 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Rendering of graphs in the [DOT] format, which is useful to inspect what nitrogen made of a
+//! graph description.
+//!
+//! [DOT]: https://www.graphviz.org/doc/info/lang.html
+
+use super::*;
+
+use crate::graph::builder::resource_descriptor::{ImageInfo, ResourceCreateInfo};
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt::{self, Write};
+
+pub(crate) fn export_dot(name: &str, compiled: &CompiledGraph, exec: &ExecutionGraph) -> String {
+    let mut out = String::new();
+
+    write_graph(&mut out, name, compiled, exec).expect("Writing to a String can't fail");
+
+    out
+}
+
+fn write_graph(
+    out: &mut String,
+    name: &str,
+    compiled: &CompiledGraph,
+    exec: &ExecutionGraph,
+) -> fmt::Result {
+    let graph = &compiled.graph_resources;
+
+    let res_names = graph
+        .name_lookup
+        .iter()
+        .map(|(name, id)| (*id, name))
+        .collect::<BTreeMap<_, _>>();
+
+    let res_list = |ids: &HashSet<ResourceId>| {
+        let names = ids
+            .iter()
+            .filter_map(|id| res_names.get(id))
+            .map(|name| name.as_ref())
+            .collect::<BTreeSet<&str>>();
+
+        names.into_iter().collect::<Vec<_>>().join(", ")
+    };
+
+    writeln!(out, "digraph \"{}\" {{", escape(name))?;
+    writeln!(out, "    compound=true;")?;
+    writeln!(out, "    node [fontname=\"monospace\"];")?;
+
+    // passes, grouped by the batch they are executed in.
+
+    let mut executed = HashSet::new();
+
+    for (i, batch) in exec.pass_execution.iter().enumerate() {
+        let mut label = format!("batch {}", i);
+
+        if !batch.resource_create.is_empty() {
+            label += &format!("\\ncreate: {}", escape(&res_list(&batch.resource_create)));
+        }
+
+        if !batch.resource_destroy.is_empty() {
+            label += &format!("\\ndestroy: {}", escape(&res_list(&batch.resource_destroy)));
+        }
+
+        writeln!(out, "    subgraph cluster_batch_{} {{", i)?;
+        writeln!(out, "        label=\"{}\";", label)?;
+        writeln!(out, "        style=dashed;")?;
+
+        for pass in &batch.passes {
+            write!(out, "    ")?;
+            write_pass(out, compiled, *pass, true)?;
+            executed.insert(*pass);
+        }
+
+        writeln!(out, "    }}")?;
+    }
+
+    // passes that are described but not needed to produce any of the targets.
+    for pass in graph.pass_types.keys() {
+        if !executed.contains(pass) {
+            write_pass(out, compiled, *pass, false)?;
+        }
+    }
+
+    // resources
+
+    for (id, name) in &res_names {
+        let mut label = escape(name);
+
+        match graph.infos.get(id) {
+            Some(ResourceCreateInfo::Image(ImageInfo::Create(info))) => {
+                label += &format!("\\nimage {:?}\\n{:?}", info.format, info.size_mode);
+            }
+            Some(ResourceCreateInfo::Image(ImageInfo::BackbufferRead { name, format })) => {
+                label += &format!("\\nbackbuffer \\\"{}\\\"\\n{:?}", escape(name), format);
+            }
+            Some(ResourceCreateInfo::Buffer(info)) => {
+                label += &format!("\\nbuffer {} bytes\\n{:?}", info.size, info.storage);
+            }
+            Some(ResourceCreateInfo::Virtual) => {
+                label += "\\nvirtual";
+            }
+            None => {
+                label += "\\nmoved";
+            }
+        }
+
+        if compiled.contextual_resources.contains(id) {
+            label += "\\n[context]";
+        }
+
+        let is_target = compiled.targets.contains(id);
+
+        if is_target {
+            label += "\\n[target]";
+        }
+
+        writeln!(
+            out,
+            "    res_{} [shape=ellipse, label=\"{}\"{}];",
+            id.0,
+            label,
+            if is_target { ", peripheries=2" } else { "" },
+        )?;
+    }
+
+    // edges
+
+    for (pass, creates) in &graph.pass_creates {
+        for res in creates {
+            if graph.infos.contains_key(res) {
+                writeln!(
+                    out,
+                    "    pass_{} -> res_{} [label=\"create\"];",
+                    pass.0, res.0
+                )?;
+            }
+        }
+    }
+
+    for (new, old) in &graph.moves_from {
+        let pass = graph.defines[new];
+
+        writeln!(
+            out,
+            "    res_{} -> res_{} [style=dashed, label=\"move ({})\"];",
+            old.0,
+            new.0,
+            escape(&compiled.pass_names[pass.0]),
+        )?;
+    }
+
+    for (pass, writes) in &graph.pass_writes {
+        for (res, ty, binding) in writes {
+            writeln!(
+                out,
+                "    pass_{} -> res_{} [color=red, label=\"write {:?}{}\"];",
+                pass.0,
+                res.0,
+                ty,
+                binding_label(*binding),
+            )?;
+        }
+    }
+
+    for (pass, reads) in &graph.pass_reads {
+        for (res, ty, binding, sampler) in reads {
+            let mut label = format!("read {:?}", ty);
+
+            if *ty != ResourceReadType::Virtual {
+                label += &binding_label(*binding);
+            }
+
+            if let Some(sampler) = sampler {
+                label += &format!("\\nsampler @{}", sampler);
+            }
+
+            writeln!(
+                out,
+                "    res_{} -> pass_{} [color=blue, label=\"{}\"];",
+                res.0, pass.0, label,
+            )?;
+        }
+    }
+
+    writeln!(out, "}}")
+}
+
+fn write_pass(
+    out: &mut String,
+    compiled: &CompiledGraph,
+    pass: PassId,
+    executed: bool,
+) -> fmt::Result {
+    let mut label = escape(&compiled.pass_names[pass.0]);
+
+    let is_graphics = compiled.graphic_passes.contains_key(&pass);
+
+    label += if is_graphics {
+        "\\ngraphics"
+    } else {
+        "\\ncompute"
+    };
+
+    if compiled.contextual_passes.contains(&pass) {
+        label += "\\n[context]";
+    }
+
+    if let Some(names) = compiled.passes_that_render_to_the_backbuffer.get(&pass) {
+        let names = names.iter().map(|name| escape(name)).collect::<Vec<_>>();
+        label += &format!("\\n[backbuffer: {}]", names.join(", "));
+    }
+
+    if !executed {
+        label += "\\n[not executed]";
+    }
+
+    writeln!(
+        out,
+        "    pass_{} [shape=box, style=\"{}\", label=\"{}\"];",
+        pass.0,
+        match (is_graphics, executed) {
+            (true, true) => "rounded",
+            (true, false) => "rounded,dotted",
+            (false, true) => "solid",
+            (false, false) => "dotted",
+        },
+        label,
+    )
+}
+
+fn binding_label(binding: u8) -> String {
+    // depth-stencil attachments don't have a binding.
+    if binding == u8::max_value() {
+        String::new()
+    } else {
+        format!(" @{}", binding)
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::graph::test_util::{image_info, DescribePass};
+
+    #[test]
+    fn export_contains_batches_and_edges() {
+        let mut builder = GraphBuilder::new("dot");
+
+        builder.add_compute_pass(
+            "create",
+            DescribePass::new(|res: &mut ResourceDescriptor| {
+                res.image_create("a", image_info());
+                res.image_write_storage("a", 0);
+            }),
+        );
+        builder.add_compute_pass(
+            "use",
+            DescribePass::new(|res: &mut ResourceDescriptor| {
+                res.image_move("a", "b");
+                res.image_read_color("b", 1, Some(2));
+            }),
+        );
+        builder.add_compute_pass(
+            "unused",
+            DescribePass::new(|res: &mut ResourceDescriptor| res.virtual_create("c")),
+        );
+        builder.add_target("b");
+
+        let dot = builder.export_dot().unwrap();
+
+        assert!(dot.starts_with("digraph \"dot\" {"));
+        assert!(dot.contains("subgraph cluster_batch_0 {"));
+        assert!(dot.contains("subgraph cluster_batch_1 {"));
+        assert!(dot.contains("label=\"batch 0\\ncreate: a\";"));
+        assert!(dot.contains("pass_0 -> res_0 [label=\"create\"];"));
+        assert!(dot.contains("pass_0 -> res_0 [color=red, label=\"write Image(Storage) @0\"];"));
+        assert!(dot.contains("res_0 -> res_2 [style=dashed, label=\"move (use)\"];"));
+        assert!(dot.contains(
+            "res_2 -> pass_1 [color=blue, label=\"read Image(Color) @1\\nsampler @2\"];"
+        ));
+        assert!(dot.contains("unused\\ncompute\\n[not executed]"));
+        assert!(dot.trim_end().ends_with('}'));
+    }
+}
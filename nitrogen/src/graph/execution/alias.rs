@@ -0,0 +1,99 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use super::*;
+
+use crate::graph::compilation::CompiledGraph;
+use crate::graph::{BufferStorageType, ImageInfo, ResourceCreateInfo};
+
+/// Kinds of resources that can share memory with each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum AliasClass {
+    Image { contextual: bool },
+    Buffer { contextual: bool },
+}
+
+/// Assignment of transient resources to memory shared between them.
+///
+/// Resources can only share memory if their lifetimes (the batches between their creation and
+/// destruction) don't overlap.
+#[derive(Debug, Default)]
+pub(crate) struct AliasingPlan {
+    /// Groups of resources that share one block of memory. Every group has at least two
+    /// resources, sorted by the batch they are created in.
+    pub(crate) slots: Vec<Vec<ResourceId>>,
+}
+
+impl AliasingPlan {
+    pub(crate) fn new(compiled: &CompiledGraph, exec: &ExecutionGraph) -> Self {
+        let resolved = &compiled.graph_resources;
+
+        // (batch where the resource is created, batch after which it's destroyed, id, class)
+        let mut lifetimes = vec![];
+
+        for (created, batch) in exec.pass_execution.iter().enumerate() {
+            for res in &batch.resource_create {
+                let contextual = compiled.contextual_resources.contains(res);
+
+                // Backbuffer resources are owned by somebody else and host visible buffers are
+                // accessed directly, so only device-local resources created by the graph are
+                // considered here.
+                let class = match &resolved.infos[res] {
+                    ResourceCreateInfo::Image(ImageInfo::Create(_)) => {
+                        AliasClass::Image { contextual }
+                    }
+                    ResourceCreateInfo::Buffer(buf) => match buf.storage {
+                        BufferStorageType::DeviceLocal => AliasClass::Buffer { contextual },
+                        BufferStorageType::HostVisible => continue,
+                    },
+                    _ => continue,
+                };
+
+                // Resources which are never destroyed (targets) outlive the execution.
+                let destroyed = exec
+                    .pass_execution
+                    .iter()
+                    .position(|batch| batch.resource_destroy.contains(res));
+
+                if let Some(destroyed) = destroyed {
+                    lifetimes.push((created, destroyed, *res, class));
+                }
+            }
+        }
+
+        lifetimes.sort_by_key(|(created, destroyed, res, _)| (*created, *destroyed, *res));
+
+        // Greedy interval partitioning: every resource is put into the first slot of the same
+        // class which is free again by the time the resource is created.
+        let mut slots: Vec<(AliasClass, usize, Vec<ResourceId>)> = vec![];
+
+        for (created, destroyed, res, class) in lifetimes {
+            let free_slot = slots
+                .iter_mut()
+                .find(|(slot_class, end, _)| *slot_class == class && *end < created);
+
+            match free_slot {
+                Some((_, end, members)) => {
+                    *end = destroyed;
+                    members.push(res);
+                }
+                None => {
+                    slots.push((class, destroyed, vec![res]));
+                }
+            }
+        }
+
+        AliasingPlan {
+            slots: slots
+                .into_iter()
+                .map(|(_, _, members)| members)
+                .filter(|members| members.len() > 1)
+                .collect(),
+        }
+    }
+
+    pub(crate) fn is_aliased(&self, res: ResourceId) -> bool {
+        self.slots.iter().any(|members| members.contains(&res))
+    }
+}
@@ -0,0 +1,924 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use super::*;
+
+use crate::graph::compilation::CompiledGraph;
+use crate::graph::{
+    BufferReadType, BufferWriteType, GraphWithNamesResolved, ImageInfo, ImageReadType,
+    ImageWriteType, PassType, ResourceCreateInfo, ResourceReadType, ResourceWriteType,
+};
+
+use gfx::pso::PipelineStage;
+
+use std::ops::Range;
+
+/// Access and layout of a resource.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ResourceAccess {
+    Image(gfx::image::Access, gfx::image::Layout),
+    Buffer(gfx::buffer::Access),
+}
+
+impl ResourceAccess {
+    fn is_write(self) -> bool {
+        use gfx::buffer::Access as BufferAccess;
+        use gfx::image::Access as ImageAccess;
+
+        match self {
+            ResourceAccess::Image(access, _) => access.intersects(
+                ImageAccess::SHADER_WRITE
+                    | ImageAccess::COLOR_ATTACHMENT_WRITE
+                    | ImageAccess::DEPTH_STENCIL_ATTACHMENT_WRITE
+                    | ImageAccess::TRANSFER_WRITE
+                    | ImageAccess::HOST_WRITE
+                    | ImageAccess::MEMORY_WRITE,
+            ),
+            ResourceAccess::Buffer(access) => access.intersects(
+                BufferAccess::SHADER_WRITE
+                    | BufferAccess::TRANSFER_WRITE
+                    | BufferAccess::HOST_WRITE
+                    | BufferAccess::MEMORY_WRITE,
+            ),
+        }
+    }
+
+    fn is_empty(self) -> bool {
+        match self {
+            ResourceAccess::Image(access, _) => access.is_empty(),
+            ResourceAccess::Buffer(access) => access.is_empty(),
+        }
+    }
+
+    pub(crate) fn layout(self) -> Option<gfx::image::Layout> {
+        match self {
+            ResourceAccess::Image(_, layout) => Some(layout),
+            ResourceAccess::Buffer(_) => None,
+        }
+    }
+
+    /// The same layout, but without any memory access.
+    fn without_access(self) -> Self {
+        match self {
+            ResourceAccess::Image(_, layout) => {
+                ResourceAccess::Image(gfx::image::Access::empty(), layout)
+            }
+            ResourceAccess::Buffer(_) => ResourceAccess::Buffer(gfx::buffer::Access::empty()),
+        }
+    }
+
+    /// No access and, for images, undefined contents.
+    fn undefined(self) -> Self {
+        match self {
+            ResourceAccess::Image(..) => {
+                ResourceAccess::Image(gfx::image::Access::empty(), gfx::image::Layout::Undefined)
+            }
+            ResourceAccess::Buffer(_) => ResourceAccess::Buffer(gfx::buffer::Access::empty()),
+        }
+    }
+}
+
+/// The way a resource is used by a pass.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ResourceState {
+    pub(crate) stages: PipelineStage,
+    pub(crate) access: ResourceAccess,
+}
+
+impl ResourceState {
+    /// Combine two uses of the same resource in one pass.
+    ///
+    /// If the uses need different layouts, the general layout is used.
+    fn merge(self, other: ResourceState) -> ResourceState {
+        let access = match (self.access, other.access) {
+            (ResourceAccess::Image(a, layout_a), ResourceAccess::Image(b, layout_b)) => {
+                let layout = if layout_a == layout_b {
+                    layout_a
+                } else {
+                    gfx::image::Layout::General
+                };
+
+                ResourceAccess::Image(a | b, layout)
+            }
+            (ResourceAccess::Buffer(a), ResourceAccess::Buffer(b)) => ResourceAccess::Buffer(a | b),
+            _ => unreachable!("Resource used as image and buffer. Should be a compile error"),
+        };
+
+        ResourceState {
+            stages: self.stages | other.stages,
+            access,
+        }
+    }
+}
+
+/// A transition of a resource from one state to another.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Barrier {
+    /// The resource at the start of the move-chain.
+    pub(crate) res: ResourceId,
+    pub(crate) stages: Range<PipelineStage>,
+    pub(crate) access: Range<ResourceAccess>,
+    pub(crate) transfer: Option<QueueTransfer>,
+}
+
+/// Half of a transfer of a resource between queues.
+///
+/// Both halves perform the same layout transition. If both queues belong to the same family no
+/// ownership transfer is needed, so the release is skipped and the acquire is an ordinary
+/// barrier.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum QueueTransfer {
+    /// Recorded on the queue which used the resource last.
+    Release(Range<QueueType>),
+    /// Recorded on the queue which uses the resource next.
+    Acquire(Range<QueueType>),
+}
+
+/// Barriers of a single pass.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PassBarriers {
+    /// Barriers that have to be recorded before the pass is executed.
+    pub(crate) before: Vec<Barrier>,
+    /// Barriers that have to be recorded after the pass is executed.
+    pub(crate) after: Vec<Barrier>,
+    /// Stages of the pass which have to wait for previously submitted passes.
+    pub(crate) wait_stages: PipelineStage,
+    /// Earlier passes which access the same resources in a conflicting way.
+    pub(crate) dependencies: Vec<PassId>,
+}
+
+/// Synchronization of graph resources between passes.
+///
+/// Every pass puts the resources it uses into the state it needs. Transitions are only made when
+/// the layout changes or when there is a hazard with the previous use.
+///
+/// If the previous use was on the same queue, a barrier can wait for the stages of the previous
+/// use directly. Otherwise the previous use is synchronized with a semaphore and the ownership of
+/// the resource is transferred to the other queue. When the contents don't have to be preserved
+/// the barrier only changes the layout.
+///
+/// The passes every pass depends on are recorded as well, so that submissions only have to wait
+/// for the work they actually need.
+#[derive(Debug, Default)]
+pub(crate) struct BarrierPlan {
+    pub(crate) passes: HashMap<PassId, PassBarriers>,
+}
+
+impl BarrierPlan {
+    pub(crate) fn new(
+        compiled: &CompiledGraph,
+        exec: &ExecutionGraph,
+        aliasing: &AliasingPlan,
+    ) -> Self {
+        let resolved = &compiled.graph_resources;
+
+        let mut plan = BarrierPlan::default();
+
+        // Latest state of every resource, with the pass that left it in that state.
+        let mut current: HashMap<ResourceId, (PassId, ResourceState)> = HashMap::new();
+
+        // Resources that share memory have to wait for the previous resource in the same memory.
+        let mut alias_prev = HashMap::new();
+        for members in &aliasing.slots {
+            for pair in members.windows(2) {
+                alias_prev.insert(pair[1], pair[0]);
+            }
+        }
+
+        // Passes that accessed a resource since it was last modified.
+        let mut accesses: HashMap<ResourceId, Accesses> = HashMap::new();
+
+        let queues = exec
+            .pass_execution
+            .iter()
+            .flat_map(|batch| &batch.passes)
+            .map(|pass| (*pass, pass_queue(compiled, *pass)))
+            .collect::<HashMap<_, _>>();
+
+        for batch in &exec.pass_execution {
+            for pass in &batch.passes {
+                let pass_ty = resolved.pass_types[pass];
+                let queue = queues[pass];
+
+                let mut barriers = PassBarriers::default();
+
+                let states = pass_resource_states(resolved, *pass);
+
+                // Virtual resources can stand for anything, so the whole pass has to wait.
+                let has_virtual_reads = resolved.pass_reads[pass]
+                    .iter()
+                    .any(|(_, ty, _, _)| *ty == ResourceReadType::Virtual);
+
+                if states.is_empty() || has_virtual_reads {
+                    barriers.wait_stages = pass_stages(pass_ty);
+                }
+
+                for (res, next) in states {
+                    barriers.wait_stages |= next.stages;
+
+                    let prev = match current.get(&res) {
+                        Some((prev_pass, prev)) => Some((*prev_pass, *prev, false)),
+                        None => alias_prev.get(&res).and_then(|prev_res| {
+                            let (prev_pass, prev) = current.get(prev_res)?;
+                            let state = ResourceState {
+                                stages: prev.stages,
+                                access: next.access.undefined(),
+                            };
+
+                            // The memory is still in use by the previous resource.
+                            if let Some(prev_accesses) = accesses.get(prev_res) {
+                                barriers
+                                    .dependencies
+                                    .extend(prev_accesses.modified_by.iter().cloned());
+                                barriers
+                                    .dependencies
+                                    .extend(prev_accesses.read_by.iter().cloned());
+                            }
+
+                            Some((*prev_pass, state, true))
+                        }),
+                    };
+
+                    current.insert(res, (*pass, next));
+
+                    let (release, barrier) = transition(
+                        resolved,
+                        res,
+                        prev.map(|(prev_pass, prev, aliased)| (queues[&prev_pass], prev, aliased)),
+                        queue,
+                        next,
+                    );
+
+                    if let (Some(release), Some((prev_pass, _, _))) = (release, prev) {
+                        plan.passes.get_mut(&prev_pass).unwrap().after.push(release);
+                    }
+
+                    // Layout transitions change the resource just like writes do.
+                    let modifies = next.access.is_write() || barrier.is_some();
+
+                    let res_accesses = accesses.entry(res).or_default();
+
+                    barriers
+                        .dependencies
+                        .extend(res_accesses.modified_by.iter().cloned());
+
+                    if modifies {
+                        barriers.dependencies.extend(res_accesses.read_by.drain(..));
+                        res_accesses.modified_by = Some(*pass);
+                    } else {
+                        res_accesses.read_by.push(*pass);
+                    }
+
+                    if let Some(barrier) = barrier {
+                        barriers.before.push(barrier);
+                    }
+                }
+
+                barriers.dependencies.sort();
+                barriers.dependencies.dedup();
+                barriers.dependencies.retain(|dep| dep != pass);
+
+                plan.passes.insert(*pass, barriers);
+            }
+        }
+
+        // Images that outlive the execution are handed back in the general layout.
+        let mut outliving = compiled
+            .targets
+            .iter()
+            .filter_map(|id| resolved.moved_from(*id))
+            .collect::<Vec<_>>();
+
+        outliving.extend(resolved.infos.iter().filter_map(|(id, info)| match info {
+            ResourceCreateInfo::Image(ImageInfo::BackbufferRead { .. }) => Some(*id),
+            _ => None,
+        }));
+
+        outliving.sort();
+        outliving.dedup();
+
+        for res in outliving {
+            let (pass, state) = match current.get(&res) {
+                Some(last) => *last,
+                None => continue,
+            };
+
+            let access = match state.access {
+                ResourceAccess::Image(access, layout) if layout != gfx::image::Layout::General => {
+                    ResourceAccess::Image(access, layout)
+                }
+                _ => continue,
+            };
+
+            let barrier = Barrier {
+                res,
+                stages: state.stages..PipelineStage::BOTTOM_OF_PIPE,
+                access: access
+                    ..ResourceAccess::Image(
+                        gfx::image::Access::empty(),
+                        gfx::image::Layout::General,
+                    ),
+                transfer: None,
+            };
+
+            plan.passes.get_mut(&pass).unwrap().after.push(barrier);
+        }
+
+        plan
+    }
+}
+
+#[derive(Debug, Default)]
+struct Accesses {
+    modified_by: Option<PassId>,
+    read_by: Vec<PassId>,
+}
+
+/// Barriers needed to get a resource from its previous use into the state `next`.
+///
+/// `prev` is the queue of the previous pass, the state it left the resource in and whether the
+/// previous use was by another resource sharing the same memory.
+///
+/// Returns the barrier releasing the resource from the previous queue, if any, and the barrier
+/// recorded before the pass.
+fn transition(
+    resolved: &GraphWithNamesResolved,
+    res: ResourceId,
+    prev: Option<(QueueType, ResourceState, bool)>,
+    queue: QueueType,
+    next: ResourceState,
+) -> (Option<Barrier>, Option<Barrier>) {
+    let (prev_queue, prev, aliased) = match prev {
+        Some(prev) => prev,
+        None => {
+            // first use in this execution. Backbuffer images are expected to be in the general
+            // layout, everything else has undefined contents.
+            let access = match resolved.infos.get(&res) {
+                Some(ResourceCreateInfo::Image(ImageInfo::BackbufferRead { .. })) => {
+                    ResourceAccess::Image(gfx::image::Access::empty(), gfx::image::Layout::General)
+                }
+                _ => next.access.undefined(),
+            };
+
+            if access.layout() == next.access.layout() {
+                return (None, None);
+            }
+
+            let barrier = Barrier {
+                res,
+                stages: next.stages..next.stages,
+                access: access..next.access,
+                transfer: None,
+            };
+
+            return (None, Some(barrier));
+        }
+    };
+
+    let layout_changes = prev.access.layout() != next.access.layout();
+
+    if prev_queue == queue {
+        let hazard = prev.access.is_write() || (next.access.is_write() && !prev.access.is_empty());
+
+        if !(layout_changes || hazard || aliased) {
+            return (None, None);
+        }
+
+        let barrier = Barrier {
+            res,
+            stages: prev.stages..next.stages,
+            access: prev.access..next.access,
+            transfer: None,
+        };
+
+        (None, Some(barrier))
+    } else if aliased {
+        // The semaphore between the queues takes care of execution and memory dependencies and
+        // the contents of the previous resource are not needed anymore.
+        if !layout_changes {
+            return (None, None);
+        }
+
+        let barrier = Barrier {
+            res,
+            stages: next.stages..next.stages,
+            access: prev.access.without_access()..next.access,
+            transfer: None,
+        };
+
+        (None, Some(barrier))
+    } else {
+        let queues = prev_queue..queue;
+
+        let release = Barrier {
+            res,
+            stages: prev.stages..PipelineStage::BOTTOM_OF_PIPE,
+            access: prev.access..next.access.without_access(),
+            transfer: Some(QueueTransfer::Release(queues.clone())),
+        };
+
+        let acquire = Barrier {
+            res,
+            stages: PipelineStage::TOP_OF_PIPE..next.stages,
+            access: prev.access.without_access()..next.access,
+            transfer: Some(QueueTransfer::Acquire(queues)),
+        };
+
+        (Some(release), Some(acquire))
+    }
+}
+
+fn pass_stages(ty: PassType) -> PipelineStage {
+    match ty {
+        PassType::Graphics => {
+            PipelineStage::DRAW_INDIRECT
+                | PipelineStage::VERTEX_INPUT
+                | shader_stages(ty)
+                | PipelineStage::EARLY_FRAGMENT_TESTS
+                | PipelineStage::LATE_FRAGMENT_TESTS
+                | PipelineStage::COLOR_ATTACHMENT_OUTPUT
+        }
+        PassType::Compute => PipelineStage::DRAW_INDIRECT | shader_stages(ty),
+    }
+}
+
+fn shader_stages(ty: PassType) -> PipelineStage {
+    match ty {
+        PassType::Graphics => PipelineStage::VERTEX_SHADER | PipelineStage::FRAGMENT_SHADER,
+        PassType::Compute => PipelineStage::COMPUTE_SHADER,
+    }
+}
+
+fn read_state(ty: ResourceReadType, pass_ty: PassType) -> Option<ResourceState> {
+    use gfx::buffer::Access as BufferAccess;
+    use gfx::image::{Access as ImageAccess, Layout};
+
+    let shader = shader_stages(pass_ty);
+    let depth_tests = PipelineStage::EARLY_FRAGMENT_TESTS | PipelineStage::LATE_FRAGMENT_TESTS;
+
+    let (stages, access) = match ty {
+        ResourceReadType::Image(ImageReadType::Color) => (
+            shader,
+            ResourceAccess::Image(ImageAccess::SHADER_READ, Layout::ShaderReadOnlyOptimal),
+        ),
+        ResourceReadType::Image(ImageReadType::Storage) => (
+            shader,
+            ResourceAccess::Image(ImageAccess::SHADER_READ, Layout::General),
+        ),
+        ResourceReadType::Image(ImageReadType::DepthStencil) => (
+            depth_tests,
+            ResourceAccess::Image(
+                ImageAccess::DEPTH_STENCIL_ATTACHMENT_READ,
+                Layout::DepthStencilReadOnlyOptimal,
+            ),
+        ),
+        ResourceReadType::Buffer(BufferReadType::Uniform) => (
+            shader,
+            ResourceAccess::Buffer(BufferAccess::CONSTANT_BUFFER_READ),
+        ),
+        ResourceReadType::Buffer(_) => (shader, ResourceAccess::Buffer(BufferAccess::SHADER_READ)),
+        ResourceReadType::Virtual => return None,
+    };
+
+    Some(ResourceState { stages, access })
+}
+
+fn write_state(ty: ResourceWriteType, pass_ty: PassType) -> ResourceState {
+    use gfx::buffer::Access as BufferAccess;
+    use gfx::image::{Access as ImageAccess, Layout};
+
+    let shader = shader_stages(pass_ty);
+    let depth_tests = PipelineStage::EARLY_FRAGMENT_TESTS | PipelineStage::LATE_FRAGMENT_TESTS;
+
+    // Attachments are loaded, so they are read as well.
+    let (stages, access) = match ty {
+        ResourceWriteType::Image(ImageWriteType::Color) => (
+            PipelineStage::COLOR_ATTACHMENT_OUTPUT,
+            ResourceAccess::Image(
+                ImageAccess::COLOR_ATTACHMENT_READ | ImageAccess::COLOR_ATTACHMENT_WRITE,
+                Layout::ColorAttachmentOptimal,
+            ),
+        ),
+        // Resolving only writes to the attachment.
+        ResourceWriteType::Image(ImageWriteType::Resolve) => (
+            PipelineStage::COLOR_ATTACHMENT_OUTPUT,
+            ResourceAccess::Image(
+                ImageAccess::COLOR_ATTACHMENT_WRITE,
+                Layout::ColorAttachmentOptimal,
+            ),
+        ),
+        ResourceWriteType::Image(ImageWriteType::DepthStencil) => (
+            depth_tests,
+            ResourceAccess::Image(
+                ImageAccess::DEPTH_STENCIL_ATTACHMENT_READ
+                    | ImageAccess::DEPTH_STENCIL_ATTACHMENT_WRITE,
+                Layout::DepthStencilAttachmentOptimal,
+            ),
+        ),
+        ResourceWriteType::Image(ImageWriteType::Storage) => (
+            shader,
+            ResourceAccess::Image(
+                ImageAccess::SHADER_READ | ImageAccess::SHADER_WRITE,
+                Layout::General,
+            ),
+        ),
+        ResourceWriteType::Buffer(BufferWriteType::Storage)
+        | ResourceWriteType::Buffer(BufferWriteType::StorageTexel) => (
+            shader,
+            ResourceAccess::Buffer(BufferAccess::SHADER_READ | BufferAccess::SHADER_WRITE),
+        ),
+    };
+
+    ResourceState { stages, access }
+}
+
+/// States of all resources used by a pass, by the resource at the start of the move-chain.
+pub(crate) fn pass_resource_states(
+    resolved: &GraphWithNamesResolved,
+    pass: PassId,
+) -> BTreeMap<ResourceId, ResourceState> {
+    let pass_ty = resolved.pass_types[&pass];
+
+    let reads = resolved
+        .pass_reads
+        .get(&pass)
+        .into_iter()
+        .flatten()
+        .filter_map(|(res, ty, _, _)| Some((*res, read_state(*ty, pass_ty)?)));
+
+    let writes = resolved
+        .pass_writes
+        .get(&pass)
+        .into_iter()
+        .flatten()
+        .map(|(res, ty, _)| (*res, write_state(*ty, pass_ty)));
+
+    let mut states = BTreeMap::new();
+
+    for (res, state) in reads.chain(writes) {
+        let origin = match resolved.moved_from(res) {
+            Some(origin) => origin,
+            None => continue,
+        };
+
+        let state = match states.get(&origin) {
+            Some(prev) => state.merge(*prev),
+            None => state,
+        };
+
+        states.insert(origin, state);
+    }
+
+    states
+}
+
+/// State a resource has to be in while a pass is executed.
+pub(crate) fn pass_resource_state(
+    resolved: &GraphWithNamesResolved,
+    pass: PassId,
+    res: ResourceId,
+) -> Option<ResourceState> {
+    let origin = resolved.moved_from(res)?;
+    pass_resource_states(resolved, pass).remove(&origin)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::graph::compilation::compile_graph;
+    use crate::graph::test_util::{buffer_info, image_info, DescribePass};
+    use crate::graph::{GraphBuilder, ImageCreateInfo, ResourceDescriptor};
+
+    use gfx::image::{Access, Layout};
+
+    fn plan(builder: GraphBuilder) -> (CompiledGraph, BarrierPlan) {
+        let compiled = compile_graph(builder).unwrap();
+        let exec = ExecutionGraph::new(&compiled);
+        let aliasing = AliasingPlan::new(&compiled, &exec);
+        let plan = BarrierPlan::new(&compiled, &exec, &aliasing);
+
+        (compiled, plan)
+    }
+
+    fn pass_id(compiled: &CompiledGraph, name: &str) -> PassId {
+        PassId(compiled.pass_names.iter().position(|n| n == name).unwrap())
+    }
+
+    fn res_id(compiled: &CompiledGraph, name: &str) -> ResourceId {
+        compiled.graph_resources.name_lookup[name]
+    }
+
+    #[test]
+    fn render_then_sample() {
+        let mut builder = GraphBuilder::new("barriers");
+
+        builder.add_graphics_pass(
+            "render",
+            DescribePass::new(|res: &mut ResourceDescriptor| {
+                res.image_create("color", image_info());
+                res.image_write_color("color", 0);
+            }),
+        );
+        builder.add_graphics_pass(
+            "sample",
+            DescribePass::new(|res: &mut ResourceDescriptor| {
+                res.image_create("out", image_info());
+                res.image_write_color("out", 0);
+                res.image_read_color("color", 0, Some(1));
+            }),
+        );
+        builder.add_target("out");
+
+        let (compiled, plan) = plan(builder);
+
+        let color = res_id(&compiled, "color");
+        let out = res_id(&compiled, "out");
+
+        let render = &plan.passes[&pass_id(&compiled, "render")];
+        assert_eq!(
+            render.before,
+            vec![Barrier {
+                res: color,
+                stages: PipelineStage::COLOR_ATTACHMENT_OUTPUT
+                    ..PipelineStage::COLOR_ATTACHMENT_OUTPUT,
+                access: ResourceAccess::Image(Access::empty(), Layout::Undefined)
+                    ..ResourceAccess::Image(
+                        Access::COLOR_ATTACHMENT_READ | Access::COLOR_ATTACHMENT_WRITE,
+                        Layout::ColorAttachmentOptimal,
+                    ),
+                transfer: None,
+            }]
+        );
+        assert!(render.after.is_empty());
+
+        let sample = &plan.passes[&pass_id(&compiled, "sample")];
+        let color_barrier = sample.before.iter().find(|b| b.res == color).unwrap();
+        assert_eq!(
+            color_barrier.stages,
+            PipelineStage::COLOR_ATTACHMENT_OUTPUT
+                ..(PipelineStage::VERTEX_SHADER | PipelineStage::FRAGMENT_SHADER)
+        );
+        assert_eq!(
+            color_barrier.access.end,
+            ResourceAccess::Image(Access::SHADER_READ, Layout::ShaderReadOnlyOptimal)
+        );
+
+        // the target is handed back in the general layout
+        assert_eq!(sample.after.len(), 1);
+        assert_eq!(sample.after[0].res, out);
+        assert_eq!(
+            sample.after[0].access.end,
+            ResourceAccess::Image(Access::empty(), Layout::General)
+        );
+    }
+
+    #[test]
+    fn resolve_targets_are_only_written() {
+        let mut builder = GraphBuilder::new("barriers");
+
+        builder.add_graphics_pass(
+            "render",
+            DescribePass::new(|res: &mut ResourceDescriptor| {
+                res.image_create(
+                    "msaa",
+                    ImageCreateInfo {
+                        samples: 4,
+                        ..image_info()
+                    },
+                );
+                res.image_create("resolved", image_info());
+                res.image_write_color_resolve("msaa", "resolved", 0);
+            }),
+        );
+        builder.add_target("resolved");
+
+        let (compiled, plan) = plan(builder);
+
+        let resolved = res_id(&compiled, "resolved");
+
+        let render = &plan.passes[&pass_id(&compiled, "render")];
+        let barrier = render.before.iter().find(|b| b.res == resolved).unwrap();
+
+        assert_eq!(
+            barrier.access.end,
+            ResourceAccess::Image(
+                Access::COLOR_ATTACHMENT_WRITE,
+                Layout::ColorAttachmentOptimal
+            )
+        );
+    }
+
+    #[test]
+    fn compute_to_graphics_on_the_graphics_queue() {
+        let mut builder = GraphBuilder::new("barriers");
+
+        builder.add_compute_pass(
+            "compute",
+            DescribePass::new(|res: &mut ResourceDescriptor| {
+                res.image_create("storage", image_info());
+                res.image_write_storage("storage", 0);
+            }),
+        );
+        builder.add_graphics_pass(
+            "render",
+            DescribePass::new(|res: &mut ResourceDescriptor| {
+                res.image_create("out", image_info());
+                res.image_write_color("out", 0);
+                res.image_read_color("storage", 0, Some(1));
+            }),
+        );
+        builder.add_target("out");
+
+        let (compiled, plan) = plan(builder);
+
+        let storage = res_id(&compiled, "storage");
+
+        let render = &plan.passes[&pass_id(&compiled, "render")];
+        let barrier = render.before.iter().find(|b| b.res == storage).unwrap();
+
+        let shader = PipelineStage::VERTEX_SHADER | PipelineStage::FRAGMENT_SHADER;
+        assert_eq!(barrier.stages, PipelineStage::COMPUTE_SHADER..shader);
+        assert_eq!(
+            barrier.access,
+            ResourceAccess::Image(Access::SHADER_READ | Access::SHADER_WRITE, Layout::General)
+                ..ResourceAccess::Image(Access::SHADER_READ, Layout::ShaderReadOnlyOptimal)
+        );
+        assert_eq!(barrier.transfer, None);
+        assert!(plan.passes[&pass_id(&compiled, "compute")].after.is_empty());
+    }
+
+    #[test]
+    fn async_compute_transfers_ownership() {
+        let mut builder = GraphBuilder::new("barriers");
+
+        builder.add_compute_pass(
+            "compute",
+            DescribePass::new_async(|res: &mut ResourceDescriptor| {
+                res.image_create("storage", image_info());
+                res.image_write_storage("storage", 0);
+            }),
+        );
+        builder.add_graphics_pass(
+            "render",
+            DescribePass::new(|res: &mut ResourceDescriptor| {
+                res.image_create("out", image_info());
+                res.image_write_color("out", 0);
+                res.image_read_color("storage", 0, Some(1));
+            }),
+        );
+        builder.add_target("out");
+
+        let (compiled, plan) = plan(builder);
+
+        let storage = res_id(&compiled, "storage");
+        let queues = QueueType::AsyncCompute..QueueType::Graphics;
+
+        let compute = &plan.passes[&pass_id(&compiled, "compute")];
+        assert_eq!(
+            compute.after,
+            vec![Barrier {
+                res: storage,
+                stages: PipelineStage::COMPUTE_SHADER..PipelineStage::BOTTOM_OF_PIPE,
+                access: ResourceAccess::Image(
+                    Access::SHADER_READ | Access::SHADER_WRITE,
+                    Layout::General
+                )
+                    ..ResourceAccess::Image(Access::empty(), Layout::ShaderReadOnlyOptimal),
+                transfer: Some(QueueTransfer::Release(queues.clone())),
+            }]
+        );
+
+        let render = &plan.passes[&pass_id(&compiled, "render")];
+        let barrier = render.before.iter().find(|b| b.res == storage).unwrap();
+
+        let shader = PipelineStage::VERTEX_SHADER | PipelineStage::FRAGMENT_SHADER;
+        assert_eq!(barrier.stages, PipelineStage::TOP_OF_PIPE..shader);
+        assert_eq!(
+            barrier.access,
+            ResourceAccess::Image(Access::empty(), Layout::General)
+                ..ResourceAccess::Image(Access::SHADER_READ, Layout::ShaderReadOnlyOptimal)
+        );
+        assert_eq!(barrier.transfer, Some(QueueTransfer::Acquire(queues)));
+        assert!(render.wait_stages.contains(shader));
+        assert_eq!(render.dependencies, vec![pass_id(&compiled, "compute")]);
+    }
+
+    #[test]
+    fn async_compute_is_ignored_for_targets() {
+        let mut builder = GraphBuilder::new("barriers");
+
+        builder.add_compute_pass(
+            "compute",
+            DescribePass::new_async(|res: &mut ResourceDescriptor| {
+                res.image_create("out", image_info());
+                res.image_write_storage("out", 0);
+            }),
+        );
+        builder.add_target("out");
+
+        let compiled = compile_graph(builder).unwrap();
+
+        assert_eq!(
+            pass_queue(&compiled, pass_id(&compiled, "compute")),
+            QueueType::Graphics
+        );
+    }
+
+    #[test]
+    fn buffer_reads_need_no_barrier() {
+        let mut builder = GraphBuilder::new("barriers");
+
+        builder.add_compute_pass(
+            "write",
+            DescribePass::new(|res: &mut ResourceDescriptor| {
+                res.buffer_create("buffer", buffer_info());
+                res.buffer_write_storage("buffer", 0);
+            }),
+        );
+        builder.add_compute_pass(
+            "read",
+            DescribePass::new(|res: &mut ResourceDescriptor| {
+                res.buffer_create("out", buffer_info());
+                res.buffer_write_storage("out", 0);
+                res.buffer_read_storage("buffer", 1);
+            }),
+        );
+        builder.add_compute_pass(
+            "read_again",
+            DescribePass::new(|res: &mut ResourceDescriptor| {
+                res.buffer_create("out2", buffer_info());
+                res.buffer_write_storage("out2", 0);
+                res.buffer_read_storage("buffer", 1);
+                res.virtual_read("out");
+            }),
+        );
+        builder.add_target("out2");
+
+        let (compiled, plan) = plan(builder);
+
+        let buffer = res_id(&compiled, "buffer");
+
+        // first use of a buffer needs no barrier
+        assert!(plan.passes[&pass_id(&compiled, "write")].before.is_empty());
+
+        // read after write
+        let read = &plan.passes[&pass_id(&compiled, "read")];
+        assert_eq!(read.before.len(), 1);
+        assert_eq!(read.before[0].res, buffer);
+        assert_eq!(
+            read.before[0].access,
+            ResourceAccess::Buffer(
+                gfx::buffer::Access::SHADER_READ | gfx::buffer::Access::SHADER_WRITE
+            )..ResourceAccess::Buffer(gfx::buffer::Access::SHADER_READ)
+        );
+
+        // read after read
+        let read_again = &plan.passes[&pass_id(&compiled, "read_again")];
+        assert!(read_again.before.iter().all(|b| b.res != buffer));
+    }
+
+    #[test]
+    fn dependencies_follow_hazards() {
+        let mut builder = GraphBuilder::new("barriers");
+
+        builder.add_compute_pass(
+            "write",
+            DescribePass::new(|res: &mut ResourceDescriptor| {
+                res.image_create("a", image_info());
+                res.image_write_storage("a", 0);
+            }),
+        );
+        builder.add_compute_pass(
+            "read",
+            DescribePass::new(|res: &mut ResourceDescriptor| {
+                res.image_create("b", image_info());
+                res.image_write_storage("b", 0);
+                res.image_read_storage("a", 1);
+            }),
+        );
+        builder.add_compute_pass(
+            "unrelated",
+            DescribePass::new(|res: &mut ResourceDescriptor| {
+                res.image_create("c", image_info());
+                res.image_write_storage("c", 0);
+            }),
+        );
+        builder.add_target("b");
+        builder.add_target("c");
+
+        let (compiled, plan) = plan(builder);
+
+        let write = pass_id(&compiled, "write");
+
+        assert!(plan.passes[&write].dependencies.is_empty());
+        assert_eq!(
+            plan.passes[&pass_id(&compiled, "read")].dependencies,
+            vec![write]
+        );
+        assert!(plan.passes[&pass_id(&compiled, "unrelated")]
+            .dependencies
+            .is_empty());
+    }
+}
@@ -159,7 +159,7 @@ fn derive_pass(compiled: &CompiledGraph, pass: PassId, usages: &mut ResourceUsag
                 };
 
                 match img {
-                    ImageWriteType::Color => {
+                    ImageWriteType::Color | ImageWriteType::Resolve => {
                         usage |= IUsage::COLOR_ATTACHMENT;
                         usage |= IUsage::TRANSFER_SRC;
                         usage |= IUsage::TRANSFER_DST;
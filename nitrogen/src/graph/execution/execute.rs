@@ -10,6 +10,8 @@ use crate::graph::{
     BufferWriteType, ImageReadType, ImageWriteType, ResourceReadType, ResourceWriteType,
 };
 
+use gfx::pso::PipelineStage;
+use gfx::queue::capability::{Capability, Supports, Transfer};
 use gfx::Device;
 
 use smallvec::SmallVec;
@@ -17,28 +19,102 @@ use smallvec::SmallVec;
 use crate::device::DeviceContext;
 use crate::graph::builder::PassType;
 use crate::graph::pass::dispatcher::{RawComputeDispatcher, RawGraphicsDispatcher};
-use crate::resources::command_pool::{CommandPoolCompute, CommandPoolGraphics};
+use crate::resources::command_pool::{CmdBufType, CommandPoolCompute, CommandPoolGraphics};
 use crate::resources::material::MaterialInstanceHandle;
 use crate::submit_group::QueueSyncRefs;
 
 pub(crate) unsafe fn execute<'a>(
     device: &'a DeviceContext,
     sync: &mut QueueSyncRefs,
-    (pool_gfx, pool_cmpt): (&CommandPoolGraphics, &CommandPoolCompute),
+    (pool_gfx, pool_gfx_cmpt, pool_cmpt): (
+        &CommandPoolGraphics,
+        &CommandPoolCompute,
+        &CommandPoolCompute,
+    ),
     storages: &'a Storages<'a>,
     store: &mut crate::graph::Store,
     graph: &'a mut crate::graph::Graph,
     res: &GraphResources,
 ) -> Result<(), GraphExecError> {
-    // let exec_graph = &graph.exec_graph;
+    let submissions = &graph.submissions.submissions;
 
-    for batch in &graph.exec_graph.pass_execution {
-        for _ in 0..batch.passes.len() {
-            let sem = sync.sem_pool.alloc();
-            sync.sem_list.add_next_semaphore(sem);
+    if submissions.is_empty() {
+        return Ok(());
+    }
+
+    // All semaphores are allocated up front, looking them up is only safe once the pool stops
+    // growing.
+    let mut signals = vec![SmallVec::<[usize; 4]>::new(); submissions.len()];
+    let mut waits = vec![SmallVec::<[usize; 4]>::new(); submissions.len()];
+
+    for (idx, submission) in submissions.iter().enumerate() {
+        for dep in &submission.waits {
+            let sem = sync.sem_pool.alloc_idx();
+            signals[*dep].push(sem);
+            waits[idx].push(sem);
+        }
+    }
+
+    // Submissions which nothing in this graph waits for signal the semaphores that following
+    // work waits on.
+    for sems in &mut signals {
+        if sems.is_empty() {
+            let sem = sync.sem_pool.alloc_idx();
+            sync.sem_list.add_next_semaphore_idx(sem);
+            sems.push(sem);
+        }
+    }
+
+    let sources = submissions
+        .iter()
+        .enumerate()
+        .filter(|(_, submission)| submission.waits.is_empty())
+        .map(|(idx, _)| idx)
+        .collect::<SmallVec<[usize; 4]>>();
+
+    // A semaphore can only be waited on once, so if there are multiple submissions without any
+    // dependencies the previous semaphores get forwarded to each of them.
+    let mut prev_waiter = None;
+
+    if sync.sem_list.has_prev_semaphores() {
+        if sources.len() == 1 {
+            prev_waiter = Some(sources[0]);
+        } else {
+            let forwards = sources
+                .iter()
+                .map(|idx| {
+                    let sem = sync.sem_pool.alloc_idx();
+                    waits[*idx].push(sem);
+                    sem
+                })
+                .collect::<SmallVec<[usize; 4]>>();
+
+            let submission = gfx::Submission {
+                command_buffers: None,
+                wait_semaphores: sync
+                    .sem_pool
+                    .list_prev_sems(sync.sem_list)
+                    .map(|sem| (sem, PipelineStage::BOTTOM_OF_PIPE)),
+                signal_semaphores: forwards.iter().map(|idx| sync.sem_pool.lookup(*idx)),
+            };
+
+            device
+                .graphics_queue()
+                .submit::<CmdBufType<gfx::Graphics>, _, _, _, _>(submission, None);
         }
+    }
 
-        for pass in &batch.passes {
+    for (idx, submission) in submissions.iter().enumerate() {
+        let wait_semaphores = sync
+            .sem_pool
+            .list_prev_sems(sync.sem_list)
+            .filter(|_| prev_waiter == Some(idx))
+            .chain(waits[idx].iter().map(|sem| sync.sem_pool.lookup(*sem)))
+            .map(|sem| (sem, submission.wait_stages));
+
+        let signal_semaphores = signals[idx].iter().map(|sem| sync.sem_pool.lookup(*sem));
+
+        for pass in &submission.passes {
             if let Some(inst) = res.pass_mat_instances.get(pass) {
                 write_pass_descriptor_set(
                     device,
@@ -49,17 +125,25 @@ pub(crate) unsafe fn execute<'a>(
                     *pass,
                 );
             }
+        }
 
-            let ty = graph.compiled_graph.graph_resources.pass_types[pass];
+        match submission.ty {
+            PassType::Compute => {
+                let pool = match submission.queue {
+                    QueueType::Graphics => pool_gfx_cmpt,
+                    QueueType::AsyncCompute => pool_cmpt,
+                };
 
-            match ty {
-                PassType::Compute => {
+                let mut cmd_buf = pool.alloc();
+                cmd_buf.begin();
+
+                for pass in &submission.passes {
                     let accessor = &graph.compiled_graph.compute_passes[pass];
+                    let pass_barriers = &graph.barriers.passes[pass];
 
                     (accessor.prepare)(store);
 
-                    let mut cmd_buf = pool_cmpt.alloc();
-                    cmd_buf.begin();
+                    record_barriers(&mut *cmd_buf, device, storages, res, &pass_barriers.before);
 
                     {
                         let raw_dispatcher = RawComputeDispatcher {
@@ -75,28 +159,37 @@ pub(crate) unsafe fn execute<'a>(
                         (accessor.execute)(store, raw_dispatcher)?;
                     }
 
-                    cmd_buf.finish();
+                    record_barriers(&mut *cmd_buf, device, storages, res, &pass_barriers.after);
+                }
 
-                    {
-                        let submission = gfx::Submission {
-                            command_buffers: Some(&*cmd_buf),
-                            wait_semaphores: sync
-                                .sem_pool
-                                .list_prev_sems(sync.sem_list)
-                                .map(|sem| (sem, gfx::pso::PipelineStage::BOTTOM_OF_PIPE)),
-                            signal_semaphores: sync.sem_pool.list_next_sems(sync.sem_list),
-                        };
+                cmd_buf.finish();
 
-                        device.compute_queue().submit(submission, None);
-                    }
+                {
+                    let mut queue = match submission.queue {
+                        QueueType::Graphics => device.graphics_compute_queue(),
+                        QueueType::AsyncCompute => device.compute_queue(),
+                    };
+
+                    let submission = gfx::Submission {
+                        command_buffers: Some(&*cmd_buf),
+                        wait_semaphores,
+                        signal_semaphores,
+                    };
+
+                    queue.submit(submission, None);
                 }
-                PassType::Graphics => {
+            }
+            PassType::Graphics => {
+                let mut cmd_buf = pool_gfx.alloc();
+                cmd_buf.begin();
+
+                for pass in &submission.passes {
                     let accessor = &graph.compiled_graph.graphic_passes[pass];
+                    let pass_barriers = &graph.barriers.passes[pass];
 
                     (accessor.prepare)(store);
 
-                    let mut cmd_buf = pool_gfx.alloc();
-                    cmd_buf.begin();
+                    record_barriers(&mut *cmd_buf, device, storages, res, &pass_barriers.before);
 
                     {
                         let raw_dispatcher = RawGraphicsDispatcher {
@@ -112,30 +205,122 @@ pub(crate) unsafe fn execute<'a>(
                         (accessor.execute)(store, raw_dispatcher)?;
                     }
 
-                    cmd_buf.finish();
+                    record_barriers(&mut *cmd_buf, device, storages, res, &pass_barriers.after);
+                }
 
-                    {
-                        let submission = gfx::Submission {
-                            command_buffers: Some(&*cmd_buf),
-                            wait_semaphores: sync
-                                .sem_pool
-                                .list_prev_sems(sync.sem_list)
-                                .map(|sem| (sem, gfx::pso::PipelineStage::BOTTOM_OF_PIPE)),
-                            signal_semaphores: sync.sem_pool.list_next_sems(sync.sem_list),
-                        };
+                cmd_buf.finish();
 
-                        device.graphics_queue().submit(submission, None);
-                    }
+                {
+                    let submission = gfx::Submission {
+                        command_buffers: Some(&*cmd_buf),
+                        wait_semaphores,
+                        signal_semaphores,
+                    };
+
+                    device.graphics_queue().submit(submission, None);
                 }
             }
-
-            sync.sem_list.advance();
         }
     }
 
+    sync.sem_list.advance();
+
     Ok(())
 }
 
+/// Record barriers of the barrier plan into a command buffer.
+unsafe fn record_barriers<C>(
+    cmd: &mut CmdBufType<C>,
+    device: &DeviceContext,
+    storages: &Storages,
+    res: &GraphResources,
+    barriers: &[Barrier],
+) where
+    C: Capability + Supports<Transfer>,
+{
+    if barriers.is_empty() {
+        return;
+    }
+
+    let image_storage = storages.image.borrow();
+    let buffer_storage = storages.buffer.borrow();
+
+    let mut stages = PipelineStage::empty()..PipelineStage::empty();
+    let mut raw_barriers = SmallVec::<[_; 8]>::new();
+
+    let family = |queue: &QueueType| match queue {
+        QueueType::Graphics => device.graphics_queue_family(),
+        QueueType::AsyncCompute => device.compute_queue_family(),
+    };
+
+    for barrier in barriers {
+        let families = match &barrier.transfer {
+            Some(QueueTransfer::Release(queues)) | Some(QueueTransfer::Acquire(queues))
+                if family(&queues.start) != family(&queues.end) =>
+            {
+                Some(family(&queues.start)..family(&queues.end))
+            }
+            // Without an ownership transfer the acquire alone does the layout transition.
+            Some(QueueTransfer::Release(_)) => continue,
+            _ => None,
+        };
+
+        let raw = match (barrier.access.start, barrier.access.end) {
+            (
+                ResourceAccess::Image(src_access, src_layout),
+                ResourceAccess::Image(dst_access, dst_layout),
+            ) => {
+                let image = res
+                    .images
+                    .get(&barrier.res)
+                    .and_then(|handle| image_storage.raw(*handle));
+
+                let image = match image {
+                    Some(image) => image,
+                    None => continue,
+                };
+
+                gfx::memory::Barrier::Image {
+                    states: (src_access, src_layout)..(dst_access, dst_layout),
+                    target: image.image.raw(),
+                    families,
+                    range: image.subresource_range(),
+                }
+            }
+            (ResourceAccess::Buffer(src_access), ResourceAccess::Buffer(dst_access)) => {
+                let buffer = res
+                    .buffers
+                    .get(&barrier.res)
+                    .and_then(|handle| buffer_storage.raw(*handle));
+
+                let buffer = match buffer {
+                    Some(buffer) => buffer,
+                    None => continue,
+                };
+
+                gfx::memory::Barrier::Buffer {
+                    states: src_access..dst_access,
+                    target: buffer.buffer.raw(),
+                    families,
+                    range: None..None,
+                }
+            }
+            _ => continue,
+        };
+
+        stages.start |= barrier.stages.start;
+        stages.end |= barrier.stages.end;
+
+        raw_barriers.push(raw);
+    }
+
+    if raw_barriers.is_empty() {
+        return;
+    }
+
+    cmd.pipeline_barrier(stages, gfx::memory::Dependencies::empty(), raw_barriers);
+}
+
 /// Write resources to the pass descriptor set.
 unsafe fn write_pass_descriptor_set(
     device: &DeviceContext,
@@ -155,6 +340,15 @@ unsafe fn write_pass_descriptor_set(
     let instance = material.instance_raw(pass_mat_instance.instance)?;
     let set = &instance.set;
 
+    let states = pass_resource_states(resolved_graph, pass);
+
+    let layout = |rid: &ResourceId| {
+        states
+            .get(rid)
+            .and_then(|state| state.access.layout())
+            .unwrap_or(gfx::image::Layout::General)
+    };
+
     let reads = resolved_graph.pass_reads[&pass]
         .iter()
         .map(|(rid, ty, binding, samp)| {
@@ -173,7 +367,7 @@ unsafe fn write_pass_descriptor_set(
                                 array_offset: 0,
                                 descriptors: std::iter::once(gfx::pso::Descriptor::Image(
                                     &image.view,
-                                    gfx::image::Layout::General,
+                                    layout(rid),
                                 )),
                             };
 
@@ -205,7 +399,7 @@ unsafe fn write_pass_descriptor_set(
                                 array_offset: 0,
                                 descriptors: std::iter::once(gfx::pso::Descriptor::Image(
                                     &image.view,
-                                    gfx::image::Layout::General,
+                                    layout(rid),
                                 )),
                             };
 
@@ -255,7 +449,9 @@ unsafe fn write_pass_descriptor_set(
                 ResourceWriteType::Image(img) => {
                     match img {
                         // those two use render pass attachments, not descriptor sets
-                        ImageWriteType::Color | ImageWriteType::DepthStencil => None,
+                        ImageWriteType::Color
+                        | ImageWriteType::DepthStencil
+                        | ImageWriteType::Resolve => None,
                         ImageWriteType::Storage => {
                             let img_handle = res.images[rid];
                             let image = image_storage.raw(img_handle).unwrap();
@@ -266,7 +462,7 @@ unsafe fn write_pass_descriptor_set(
                                 array_offset: 0,
                                 descriptors: std::iter::once(gfx::pso::Descriptor::Image(
                                     &image.view,
-                                    gfx::image::Layout::General,
+                                    layout(rid),
                                 )),
                             })
                         }
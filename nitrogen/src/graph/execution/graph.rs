@@ -105,11 +105,15 @@ impl ExecutionGraph {
             pass_execs
                 .into_iter()
                 .map(|batch| {
-                    let deduped = batch
+                    let mut deduped = batch
                         .into_iter()
                         .filter(|pass| !known_nodes.contains(pass))
                         .collect::<Vec<_>>();
 
+                    // Graphics passes have lower ids than compute passes, so sorting groups
+                    // passes that are submitted to the same queue.
+                    deduped.sort();
+
                     for pass in &deduped {
                         known_nodes.insert(*pass);
                     }
@@ -2,26 +2,33 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+mod alias;
+mod barrier;
 mod derive;
 mod execute;
 mod graph;
 mod prepare;
+mod schedule;
 
+pub(crate) use self::alias::*;
+pub(crate) use self::barrier::*;
 pub(crate) use self::derive::*;
 pub(crate) use self::execute::*;
 pub(crate) use self::graph::*;
 pub(crate) use self::prepare::*;
+pub(crate) use self::schedule::*;
 
 pub use self::prepare::PrepareError;
 
 use super::{PassId, ResourceId, Storages};
 use crate::resources::{
     buffer::BufferHandle, image::ImageHandle, pipeline::PipelineHandle,
-    render_pass::RenderPassHandle, sampler::SamplerHandle,
+    render_pass::RenderPassHandle, sampler::SamplerHandle, shader::ShaderStorage,
 };
 use crate::types;
 
 use crate::submit_group::ResourceList;
+use crate::util::allocator::Block;
 
 use std::collections::{BTreeMap, HashMap, HashSet};
 
@@ -52,13 +59,15 @@ impl std::error::Error for GraphExecError {}
 
 #[derive(Debug, Default)]
 pub(crate) struct ResourceUsages {
-    image: HashMap<ResourceId, (gfx::image::Usage, gfx::format::Format)>,
-    buffer: HashMap<ResourceId, gfx::buffer::Usage>,
+    pub(crate) image: HashMap<ResourceId, (gfx::image::Usage, gfx::format::Format)>,
+    pub(crate) buffer: HashMap<ResourceId, gfx::buffer::Usage>,
 }
 
 #[derive(Debug)]
 pub(crate) struct PipelineResources {
     pub(crate) pipeline_handle: PipelineHandle,
+    /// Shader storage generation at the time the pipeline was created.
+    pub(crate) shader_generation: u64,
 }
 
 #[derive(Debug, Default)]
@@ -91,6 +100,71 @@ impl PassResources {
             storages.pipeline.borrow_mut().destroy(res_list, pipes);
         }
     }
+
+    /// Retire all pipelines that use a shader which was replaced after the pipeline was created.
+    ///
+    /// The pipelines get created again the next time a pass uses their configuration.
+    pub(crate) fn retire_outdated_pipelines(
+        &mut self,
+        res_list: &mut ResourceList,
+        storages: &mut Storages,
+    ) {
+        let shaders = storages.shader.borrow();
+
+        let mut outdated = vec![];
+
+        for pipes in self.compute_pipelines.values_mut() {
+            pipes.retain(|desc, res| {
+                let replaced = ShaderStorage::replaced_since(
+                    &shaders.compute_storage,
+                    desc.shader.handle,
+                    res.shader_generation,
+                );
+
+                if replaced {
+                    outdated.push(res.pipeline_handle);
+                }
+
+                !replaced
+            });
+        }
+
+        for pipes in self.graphic_pipelines.values_mut() {
+            pipes.retain(|desc, res| {
+                let gen = res.shader_generation;
+                let shaders_desc = &desc.shaders;
+
+                let vertex = ShaderStorage::replaced_since(
+                    &shaders.vertex_storage,
+                    shaders_desc.vertex.handle,
+                    gen,
+                );
+                let fragment = shaders_desc.fragment.as_ref().map_or(false, |frag| {
+                    ShaderStorage::replaced_since(&shaders.fragment_storage, frag.handle, gen)
+                });
+                let geometry = shaders_desc.geometry.as_ref().map_or(false, |geom| {
+                    ShaderStorage::replaced_since(&shaders.geometry_storage, geom.handle, gen)
+                });
+
+                let tess_control = shaders_desc.tess_control.as_ref().map_or(false, |tesc| {
+                    ShaderStorage::replaced_since(&shaders.tess_control_storage, tesc.handle, gen)
+                });
+                let tess_eval = shaders_desc.tess_eval.as_ref().map_or(false, |tese| {
+                    ShaderStorage::replaced_since(&shaders.tess_eval_storage, tese.handle, gen)
+                });
+
+                let replaced = vertex || fragment || geometry || tess_control || tess_eval;
+
+                if replaced {
+                    outdated.push(res.pipeline_handle);
+                }
+
+                !replaced
+            });
+        }
+
+        storages.pipeline.borrow_mut().destroy(res_list, outdated);
+    }
 }
 
 #[derive(Debug, Default)]
@@ -105,6 +179,9 @@ pub(crate) struct GraphResources {
     pub(crate) images: HashMap<ResourceId, ImageHandle>,
     samplers: HashMap<ResourceId, SamplerHandle>,
     pub(crate) buffers: HashMap<ResourceId, BufferHandle>,
+
+    /// Memory shared by the resources of an aliasing slot.
+    pub(crate) shared_memory: HashMap<usize, Block>,
 }
 
 impl GraphResources {
@@ -137,6 +214,11 @@ impl GraphResources {
             .buffer
             .borrow_mut()
             .destroy(res_list, self.buffers.values());
+
+        // The memory is freed after the resources that are bound to it.
+        for (_, block) in self.shared_memory {
+            res_list.queue_memory(block);
+        }
     }
 }
 
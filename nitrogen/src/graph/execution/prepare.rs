@@ -10,7 +10,7 @@ use gfx;
 use crate::graph::{
     BufferReadType, BufferStorageType, BufferWriteType, ExecutionContext, Graph,
     GraphWithNamesResolved, ImageInfo, ImageReadType, ImageWriteType, ResourceCreateInfo,
-    ResourceReadType, ResourceWriteType,
+    ResourceReadType, ResourceWriteType, Specialization,
 };
 
 use crate::resources::{image, sampler};
@@ -25,9 +25,12 @@ use crate::graph::compilation::CompiledGraph;
 use crate::graph::ResourceName;
 use crate::resources::buffer::BufferError;
 use crate::resources::image::ImageError;
-use crate::resources::material::MaterialStorage;
+use crate::resources::material::{Material, MaterialStorage};
 use crate::resources::pipeline::PipelineError;
 use crate::resources::render_pass::RenderPassError;
+use crate::resources::shader::{PipelineInterface, Shader, ValidationError};
+use crate::resources::vertex_attrib::VertexAttrib;
+use crate::util::allocator::AllocatorError;
 use std::collections::BTreeMap;
 
 /// Errors that can occur when trying to prepare resources for a graph execution.
@@ -43,6 +46,9 @@ pub enum PrepareError {
     #[display(fmt = "Pipeline could not be created because a mandatory shader handle is invalid")]
     InvalidShaderHandle,
 
+    #[display(fmt = "Pipeline does not match the shader interface: {}", _0)]
+    ShaderValidationError(ValidationError),
+
     #[display(fmt = "Resource {:?} is referenced which is invalid", _0)]
     InvalidResource(ResourceId),
 
@@ -55,6 +61,9 @@ pub enum PrepareError {
     #[display(fmt = "Image {:?} is invalid", _0)]
     InvalidImageHandle(ImageHandle),
 
+    #[display(fmt = "Image {:?} has no layer {}", _0, _1)]
+    InvalidImageLayer(ResourceId, u16),
+
     #[display(fmt = "The framebuffer extent could not be inferred")]
     CantInferFramebufferExtent,
 
@@ -75,6 +84,9 @@ pub enum PrepareError {
 
     #[display(fmt = "Error creating a buffer: {}", _0)]
     BufferError(BufferError),
+
+    #[display(fmt = "Error allocating memory shared by multiple resources: {}", _0)]
+    AllocatorError(AllocatorError),
 }
 
 impl std::error::Error for PrepareError {}
@@ -93,6 +105,68 @@ pub(crate) unsafe fn prepare_graphics_pass_base(
     Ok(())
 }
 
+/// Create the pipelines for all configurations a pass expects to use.
+pub(crate) unsafe fn prepare_pass_pipelines(
+    device: &DeviceContext,
+    storages: &Storages,
+    pass_res: &mut PassResources,
+    pass: PassId,
+    compiled: &CompiledGraph,
+) -> Result<(), PrepareError> {
+    let pass_mat = pass_res.pass_material.get(&pass).cloned();
+
+    if let Some(accessor) = compiled.compute_passes.get(&pass) {
+        let pipelines = pass_res.compute_pipelines.entry(pass).or_default();
+
+        for desc in (accessor.warm_configs)() {
+            if pipelines.contains_key(&desc) {
+                continue;
+            }
+
+            let pipe = create_pipeline_compute(device, storages, pass, pass_mat, &desc)?;
+
+            pipelines.insert(
+                desc,
+                PipelineResources {
+                    pipeline_handle: pipe,
+                    shader_generation: storages.shader.borrow().generation,
+                },
+            );
+        }
+    }
+
+    if let Some(accessor) = compiled.graphic_passes.get(&pass) {
+        let render_pass = pass_res.render_passes[&pass];
+        let pipelines = pass_res.graphic_pipelines.entry(pass).or_default();
+
+        for desc in (accessor.warm_configs)() {
+            if pipelines.contains_key(&desc) {
+                continue;
+            }
+
+            let pipe = create_pipeline_graphics(
+                device,
+                storages,
+                &compiled.graph_resources,
+                pass,
+                pass_mat,
+                &desc,
+                render_pass,
+            )?;
+
+            pipelines.insert(
+                desc,
+                PipelineResources {
+                    pipeline_handle: pipe,
+                    shader_generation: storages.shader.borrow().generation,
+                },
+            );
+        }
+    }
+
+    Ok(())
+}
+
 pub(crate) struct ResourcePrepareOptions {
     pub(crate) create_non_contextual: bool,
     pub(crate) create_contextual: bool,
@@ -119,8 +193,28 @@ pub(crate) unsafe fn prepare_resources(
 
     let pass_res = &graph.pass_resources;
 
+    // Resources that share memory have to be created together, so the memory can satisfy the
+    // requirements of all of them.
+    for (slot, members) in graph.aliasing.slots.iter().enumerate() {
+        // all resources in a slot are either contextual or not.
+        let is_contextual = compiled.contextual_resources.contains(&members[0]);
+
+        let create = (is_contextual && options.create_contextual)
+            || (!is_contextual && options.create_non_contextual);
+
+        if create {
+            create_aliased_resources(
+                device, storages, res_list, usages, res, resolved, slot, members, context,
+            )?;
+        }
+    }
+
     for batch in &exec.pass_execution {
         for res_id in &batch.resource_create {
+            if graph.aliasing.is_aliased(*res_id) {
+                continue;
+            }
+
             let info = &resolved.infos[res_id];
 
             let is_contextual = compiled.contextual_resources.contains(res_id);
@@ -256,6 +350,18 @@ unsafe fn create_render_pass(
     let mut has_depth_write = false;
     let mut has_depth_read = false;
 
+    // Images are transitioned into the layout the pass needs before the render pass begins,
+    // so the render pass itself does not change any layouts.
+    let states = pass_resource_states(resolved_graph, pass);
+
+    let layout_of = |res: ResourceId| {
+        resolved_graph
+            .moved_from(res)
+            .and_then(|origin| states.get(&origin))
+            .and_then(|state| state.access.layout())
+            .unwrap_or(gfx::image::Layout::General)
+    };
+
     let mut attachments = {
         resolved_graph.pass_writes[&pass]
             .iter()
@@ -269,17 +375,11 @@ unsafe fn create_render_pass(
                 _ => false,
             })
             .filter_map(|(res, _ty, binding)| {
-                let (_origin, info) = resolved_graph.create_info(*res)?;
-
-                let format = match info {
-                    ResourceCreateInfo::Image(ImageInfo::Create(img)) => img.format.into(),
-                    ResourceCreateInfo::Image(ImageInfo::BackbufferRead { format, .. }) => *format,
-                    _ => unreachable!(),
-                };
+                let (format, samples) = attachment_format(resolved_graph, *res)?;
 
                 let load_op = gfx::pass::AttachmentLoadOp::Load;
 
-                let initial_layout = gfx::image::Layout::General;
+                let layout = layout_of(*res);
 
                 let (ops, stencil) = {
                     // applies to color AND depth
@@ -297,11 +397,10 @@ unsafe fn create_render_pass(
                     *binding,
                     gfx::pass::Attachment {
                         format: Some(format),
-                        samples: 1,
+                        samples,
                         ops,
                         stencil_ops: stencil,
-                        // TODO Better layout transitions
-                        layouts: initial_layout..gfx::image::Layout::General,
+                        layouts: layout..layout,
                     },
                 ))
             })
@@ -316,27 +415,20 @@ unsafe fn create_render_pass(
                     .map(|(res, _, _, _)| {
                         has_depth_read = true;
 
-                        let (_origin, info) = resolved_graph.create_info(*res).unwrap();
-
-                        let format = match info {
-                            ResourceCreateInfo::Image(ImageInfo::Create(img)) => img.format.into(),
-                            ResourceCreateInfo::Image(ImageInfo::BackbufferRead {
-                                format, ..
-                            }) => *format,
-                            _ => unreachable!(),
-                        };
+                        let (format, samples) = attachment_format(resolved_graph, *res).unwrap();
+                        let layout = layout_of(*res);
 
                         (
                             u8::max_value(),
                             gfx::pass::Attachment {
                                 format: Some(format),
-                                samples: 1,
+                                samples,
                                 ops: gfx::pass::AttachmentOps {
                                     load: gfx::pass::AttachmentLoadOp::Load,
                                     store: gfx::pass::AttachmentStoreOp::DontCare,
                                 },
                                 stencil_ops: gfx::pass::AttachmentOps::DONT_CARE,
-                                layouts: gfx::image::Layout::General..gfx::image::Layout::General,
+                                layouts: layout..layout,
                             },
                         )
                     }),
@@ -361,19 +453,59 @@ unsafe fn create_render_pass(
         attachments[depth_binding].0 = depth_binding as _;
     }
 
-    let mut attachments_desc = attachments
+    let attachments_desc = attachments
         .as_slice()
         .iter()
         .enumerate()
-        .map(|(i, _)| (i, gfx::image::Layout::ColorAttachmentOptimal))
+        .map(|(i, (_, attachment))| (i, attachment.layouts.start))
         .collect::<SmallVec<[_; 16]>>();
 
-    if has_depth {
-        attachments_desc[depth_binding].1 = gfx::image::Layout::DepthStencilAttachmentOptimal;
-    }
-
     let color_desc = &attachments_desc[0..depth_binding];
 
+    // Resolve attachments come after all color and depth attachments. The contents are
+    // overwritten by the resolve, so they don't have to be loaded.
+    let mut resolves = resolved_graph.pass_writes[&pass]
+        .iter()
+        .filter(|(_, ty, _)| *ty == ResourceWriteType::Image(ImageWriteType::Resolve))
+        .filter_map(|(res, _ty, binding)| {
+            let (format, _) = attachment_format(resolved_graph, *res)?;
+            let layout = layout_of(*res);
+
+            Some((
+                *binding,
+                gfx::pass::Attachment {
+                    format: Some(format),
+                    samples: 1,
+                    ops: gfx::pass::AttachmentOps {
+                        load: gfx::pass::AttachmentLoadOp::DontCare,
+                        store: gfx::pass::AttachmentStoreOp::Store,
+                    },
+                    stencil_ops: gfx::pass::AttachmentOps::DONT_CARE,
+                    layouts: layout..layout,
+                },
+            ))
+        })
+        .collect::<SmallVec<[_; 16]>>();
+
+    resolves.as_mut_slice().sort_by_key(|(binding, _)| *binding);
+
+    // If any color attachment is resolved, every color attachment needs a resolve reference.
+    let resolve_desc = if resolves.is_empty() {
+        SmallVec::<[_; 16]>::new()
+    } else {
+        attachments[0..depth_binding]
+            .iter()
+            .map(
+                |(binding, _)| match resolves.iter().position(|(b, _)| b == binding) {
+                    Some(idx) => (attachments.len() + idx, resolves[idx].1.layouts.start),
+                    None => (ATTACHMENT_UNUSED, gfx::image::Layout::Undefined),
+                },
+            )
+            .collect()
+    };
+
+    attachments.extend(resolves);
+
     let depth_stencil_desc = if has_depth {
         Some(&attachments_desc[depth_binding])
     } else {
@@ -384,7 +516,7 @@ unsafe fn create_render_pass(
         colors: color_desc,
         depth_stencil: depth_stencil_desc,
         inputs: &[],
-        resolves: &[],
+        resolves: &resolve_desc,
         preserves: &[],
     };
 
@@ -417,6 +549,47 @@ unsafe fn create_render_pass(
     Ok(render_pass)
 }
 
+/// Attachment reference which is not used by a subpass (`VK_ATTACHMENT_UNUSED`).
+const ATTACHMENT_UNUSED: gfx::pass::AttachmentId = u32::max_value() as _;
+
+/// Format and number of samples of an image used as an attachment.
+fn attachment_format(
+    resolved: &GraphWithNamesResolved,
+    res: ResourceId,
+) -> Option<(gfx::format::Format, gfx::image::NumSamples)> {
+    let (_origin, info) = resolved.create_info(res)?;
+
+    match info {
+        ResourceCreateInfo::Image(ImageInfo::Create(img)) => Some((img.format.into(), img.samples)),
+        ResourceCreateInfo::Image(ImageInfo::BackbufferRead { format, .. }) => Some((*format, 1)),
+        _ => unreachable!(),
+    }
+}
+
+/// Number of samples of the color and depth attachments of a graphics pass.
+pub(crate) fn pass_samples(resolved: &GraphWithNamesResolved, pass: PassId) -> u8 {
+    let writes = resolved.pass_writes[&pass]
+        .iter()
+        .filter(|(_, ty, _)| match ty {
+            ResourceWriteType::Image(ImageWriteType::Color) => true,
+            ResourceWriteType::Image(ImageWriteType::DepthStencil) => true,
+            _ => false,
+        })
+        .map(|(res, _, _)| *res);
+
+    let reads = resolved.pass_reads[&pass]
+        .iter()
+        .filter(|(_, ty, _, _)| *ty == ResourceReadType::Image(ImageReadType::DepthStencil))
+        .map(|(res, _, _, _)| *res);
+
+    writes
+        .chain(reads)
+        .filter_map(|res| attachment_format(resolved, res))
+        .map(|(_, samples)| samples)
+        .max()
+        .unwrap_or(1)
+}
+
 unsafe fn create_framebuffer(
     device: &DeviceContext,
     storages: &Storages,
@@ -450,36 +623,56 @@ unsafe fn create_framebuffer(
             .as_mut_slice()
             .sort_by_key(|(_, _, binding)| binding);
 
+        // Resolve attachments come after all color and depth attachments
+        let mut sorted_resolves = resolved.pass_writes[&pass]
+            .iter()
+            .filter(|(_, ty, _)| *ty == ResourceWriteType::Image(ImageWriteType::Resolve))
+            .collect::<SmallVec<[_; 16]>>();
+
+        sorted_resolves
+            .as_mut_slice()
+            .sort_by_key(|(_, _, binding)| binding);
+
+        let layers = &resolved.pass_attachment_layers[&pass];
+
+        let image_view_dims = |res_id: ResourceId| -> Result<_, PrepareError> {
+            let layer = layers.get(&res_id).cloned().unwrap_or(0);
+
+            let res_id = resolved
+                .moved_from(res_id)
+                .ok_or_else(|| PrepareError::InvalidResource(res_id))?;
+            let (_, create_info) = resolved
+                .create_info(res_id)
+                .ok_or_else(|| PrepareError::InvalidResource(res_id))?;
+
+            let handle = match create_info {
+                ResourceCreateInfo::Image(ImageInfo::BackbufferRead { name, .. }) => backbuffer
+                    .images
+                    .get(name)
+                    .ok_or_else(|| PrepareError::InvalidBackbufferResource(name.clone()))?,
+                ResourceCreateInfo::Image(ImageInfo::Create(_)) => res
+                    .images
+                    .get(&res_id)
+                    .ok_or_else(|| PrepareError::InvalidImageResource(res_id))?,
+                // buffer attachments???
+                _ => unreachable!(),
+            };
+
+            let image = image_storage
+                .raw(*handle)
+                .ok_or_else(|| PrepareError::InvalidImageHandle(*handle))?;
+
+            let view = image
+                .layer_view(layer)
+                .ok_or_else(|| PrepareError::InvalidImageLayer(res_id, layer))?;
+
+            Ok((view, &image.dimension))
+        };
+
         // resolve all the references, preserve error
         let mut res_view_dims = sorted_attachments
             .into_iter()
-            .map(|(res_id, _, _)| -> Result<_, PrepareError> {
-                let res_id = resolved
-                    .moved_from(*res_id)
-                    .ok_or_else(|| PrepareError::InvalidResource(*res_id))?;
-                let (_, create_info) = resolved
-                    .create_info(res_id)
-                    .ok_or_else(|| PrepareError::InvalidResource(res_id))?;
-
-                let handle = match create_info {
-                    ResourceCreateInfo::Image(ImageInfo::BackbufferRead { name, .. }) => backbuffer
-                        .images
-                        .get(name)
-                        .ok_or_else(|| PrepareError::InvalidBackbufferResource(name.clone()))?,
-                    ResourceCreateInfo::Image(ImageInfo::Create(_)) => res
-                        .images
-                        .get(&res_id)
-                        .ok_or_else(|| PrepareError::InvalidImageResource(res_id))?,
-                    // buffer attachments???
-                    _ => unreachable!(),
-                };
-
-                let image = image_storage
-                    .raw(*handle)
-                    .ok_or_else(|| PrepareError::InvalidImageHandle(*handle))?;
-
-                Ok((&image.view, &image.dimension))
-            })
+            .map(|(res_id, _, _)| image_view_dims(*res_id))
             // depth textures might be "read" from when using for testing without writing
             .chain(
                 resolved.pass_reads[&pass]
@@ -488,19 +681,12 @@ unsafe fn create_framebuffer(
                         ResourceReadType::Image(ImageReadType::DepthStencil) => true,
                         _ => false,
                     })
-                    .map(|(res_id, _, _, _)| -> Result<_, PrepareError> {
-                        let res_id = resolved
-                            .moved_from(*res_id)
-                            .ok_or_else(|| PrepareError::InvalidResource(*res_id))?;
-
-                        let handle = res.images[&res_id];
-
-                        let image = image_storage
-                            .raw(handle)
-                            .ok_or_else(|| PrepareError::InvalidImageHandle(handle))?;
-
-                        Ok((&image.view, &image.dimension))
-                    }),
+                    .map(|(res_id, _, _, _)| image_view_dims(*res_id)),
+            )
+            .chain(
+                sorted_resolves
+                    .into_iter()
+                    .map(|(res_id, _, _)| image_view_dims(*res_id)),
             );
 
         // fold all the results into array, aborting on the first encountered error
@@ -551,11 +737,11 @@ pub(crate) unsafe fn create_pipeline_compute(
     let shader_storage = storages.shader.borrow();
     let mut pipeline_storage = storages.pipeline.borrow_mut();
 
-    let layouts = create_pipeline_base(&*material_storage, pass_material, &info.materials[..]);
+    let sets = create_pipeline_base(&*material_storage, pass_material, &info.materials[..]);
 
-    let layouts = layouts
-        .into_iter()
-        .map(|(_, data)| data)
+    let layouts = sets
+        .values()
+        .map(|mat| &mat.desc_set_layout)
         .collect::<Vec<_>>();
 
     let mut push_constants = SmallVec::<[_; 1]>::new();
@@ -567,6 +753,9 @@ pub(crate) unsafe fn create_pipeline_compute(
         .raw_compute(info.shader.handle)
         .ok_or(PrepareError::InvalidShaderHandle)?;
 
+    let interface = pipeline_interface(&sets, info.push_constant_range.as_ref(), None);
+    validate_shader(shader, &interface, &info.shader.specialization)?;
+
     let create_info = crate::pipeline::ComputePipelineCreateInfo {
         shader: crate::pipeline::ShaderInfo {
             content: shader.spirv_content.as_slice(),
@@ -585,7 +774,8 @@ pub(crate) unsafe fn create_pipeline_compute(
 pub(crate) unsafe fn create_pipeline_graphics(
     device: &DeviceContext,
     storages: &Storages,
-    _pass: PassId,
+    resolved: &GraphWithNamesResolved,
+    pass: PassId,
     pass_material: Option<MaterialHandle>,
     info: &GraphicsPipelineInfo,
     render_pass: RenderPassHandle,
@@ -596,11 +786,11 @@ pub(crate) unsafe fn create_pipeline_graphics(
     let shader_storage = storages.shader.borrow();
     let mut pipeline_storage = storages.pipeline.borrow_mut();
 
-    let layouts = create_pipeline_base(&*material_storage, pass_material, &info.materials[..]);
+    let sets = create_pipeline_base(&*material_storage, pass_material, &info.materials[..]);
 
-    let layouts = layouts
-        .into_iter()
-        .map(|(_, data)| data)
+    let layouts = sets
+        .values()
+        .map(|mat| &mat.desc_set_layout)
         .collect::<Vec<_>>();
 
     let mut push_constants = SmallVec::<[_; 1]>::new();
@@ -608,11 +798,22 @@ pub(crate) unsafe fn create_pipeline_graphics(
         push_constants.push((range.start / 4)..(range.end / 4));
     }
 
+    // only the inputs of the vertex shader are provided by the vertex attributes, the inputs of
+    // later stages are outputs of the stage before.
+    let vertex_interface = pipeline_interface(
+        &sets,
+        info.push_constants.as_ref(),
+        info.vertex_attrib.as_ref(),
+    );
+    let interface = pipeline_interface(&sets, info.push_constants.as_ref(), None);
+
     let vertex_shader = {
         let raw = shader_storage
             .raw_vertex(info.shaders.vertex.handle)
             .ok_or(PrepareError::InvalidShaderHandle)?;
 
+        validate_shader(raw, &vertex_interface, &info.shaders.vertex.specialization)?;
+
         pipeline::ShaderInfo {
             content: raw.spirv_content.as_slice(),
             entry: &raw.entry_point,
@@ -625,6 +826,8 @@ pub(crate) unsafe fn create_pipeline_graphics(
             .raw_fragment(sh.handle)
             .ok_or(PrepareError::InvalidShaderHandle)?;
 
+        validate_shader(raw, &interface, &sh.specialization)?;
+
         let info = pipeline::ShaderInfo {
             content: raw.spirv_content.as_slice(),
             entry: &raw.entry_point,
@@ -640,6 +843,42 @@ pub(crate) unsafe fn create_pipeline_graphics(
             .raw_geometry(sh.handle)
             .ok_or(PrepareError::InvalidShaderHandle)?;
 
+        validate_shader(raw, &interface, &sh.specialization)?;
+
+        let info = pipeline::ShaderInfo {
+            content: raw.spirv_content.as_slice(),
+            entry: &raw.entry_point,
+            specialization: &sh.specialization,
+        };
+        Some(info)
+    } else {
+        None
+    };
+
+    let tess_control_shader = if let Some(sh) = &info.shaders.tess_control {
+        let raw = shader_storage
+            .raw_tess_control(sh.handle)
+            .ok_or(PrepareError::InvalidShaderHandle)?;
+
+        validate_shader(raw, &interface, &sh.specialization)?;
+
+        let info = pipeline::ShaderInfo {
+            content: raw.spirv_content.as_slice(),
+            entry: &raw.entry_point,
+            specialization: &sh.specialization,
+        };
+        Some(info)
+    } else {
+        None
+    };
+
+    let tess_eval_shader = if let Some(sh) = &info.shaders.tess_eval {
+        let raw = shader_storage
+            .raw_tess_eval(sh.handle)
+            .ok_or(PrepareError::InvalidShaderHandle)?;
+
+        validate_shader(raw, &interface, &sh.specialization)?;
+
         let info = pipeline::ShaderInfo {
             content: raw.spirv_content.as_slice(),
             entry: &raw.entry_point,
@@ -658,13 +897,18 @@ pub(crate) unsafe fn create_pipeline_graphics(
     let create_info = pipeline::GraphicsPipelineCreateInfo {
         vertex_attribs: vertex_attrib_res,
         primitive: info.primitive,
+        rasterizer: info.rasterizer,
         shader_vertex: vertex_shader,
         shader_fragment: fragment_shader,
         shader_geometry: geometry_shader,
+        shader_tess_control: tess_control_shader,
+        shader_tess_eval: tess_eval_shader,
         descriptor_set_layout: &layouts[..],
         push_constants: push_constants.as_slice(),
-        blend_modes: &info.blend_modes[..],
+        blend: &info.blend,
         depth_mode: info.depth_mode,
+        stencil_mode: info.stencil_mode,
+        samples: pass_samples(resolved, pass),
     };
 
     let pipeline_handle = pipeline_storage.create_graphics_pipeline(
@@ -719,69 +963,21 @@ unsafe fn create_resource(
         }
 
         ResourceCreateInfo::Image(ImageInfo::Create(img)) => {
-            // find out the size and kind of the image
-
-            let raw_dim = img.size_mode.absolute(context.reference_size);
-            let dim = image::ImageDimension::D2 {
-                x: raw_dim.0,
-                y: raw_dim.1,
-            };
-
-            let kind = image::ViewKind::D2;
-
-            let format = img.format;
-
-            let is_depth_stencil = format.is_depth_stencil();
-
-            let num_mips = if is_depth_stencil { 0 } else { 1 };
-
-            // any flags that will be needed
-            let usages = &usages.image[&id];
-
-            // let's go!
-            let create_info = image::ImageCreateInfo {
-                dimension: dim,
-                num_layers: 1,
-                num_samples: 1,
-                num_mipmaps: num_mips,
-                format,
-                swizzle: image::Swizzle::NO,
-                kind,
-                usage: usages.0,
-                is_transient: false,
-            };
+            let usage = usages.image[&id].0;
+            let create_info = image_create_info(img, usage, context);
 
             let img_handle = image_storage.create(device, create_info)?;
 
-            let old_image = res.images.insert(id, img_handle);
-
-            // If the image is used for sampling then it means some other pass will read from it
-            // as a color image. In that case we create a sampler for this image as well
-            let old_sampler = if usages.0.contains(gfx::image::Usage::SAMPLED) {
-                let sampler = sampler_storage.create(
-                    device,
-                    sampler::SamplerCreateInfo {
-                        min_filter: sampler::Filter::Linear,
-                        mip_filter: sampler::Filter::Linear,
-                        mag_filter: sampler::Filter::Linear,
-                        wrap_mode: (
-                            sampler::WrapMode::Clamp,
-                            sampler::WrapMode::Clamp,
-                            sampler::WrapMode::Clamp,
-                        ),
-                    },
-                );
-                res.samplers.insert(id, sampler)
-            } else {
-                None
-            };
-
-            if let Some(old_img) = old_image {
-                image_storage.destroy(res_list, &[old_img]);
-            }
-            if let Some(old_samp) = old_sampler {
-                sampler_storage.destroy(res_list, &[old_samp]);
-            }
+            insert_image(
+                device,
+                &mut image_storage,
+                &mut sampler_storage,
+                res_list,
+                res,
+                id,
+                img_handle,
+                usage,
+            );
 
             Ok(())
         }
@@ -824,17 +1020,220 @@ unsafe fn create_resource(
     }
 }
 
+fn image_create_info(
+    img: &crate::graph::ImageCreateInfo,
+    usage: gfx::image::Usage,
+    context: &ExecutionContext,
+) -> image::ImageCreateInfo<gfx::image::Usage> {
+    // find out the size and kind of the image
+
+    let raw_dim = img.size_mode.absolute(context.reference_size);
+    let dim = image::ImageDimension::D2 {
+        x: raw_dim.0,
+        y: raw_dim.1,
+    };
+
+    let kind = img.kind;
+
+    let format = img.format;
+
+    let is_depth_stencil = format.is_depth_stencil();
+
+    let num_mips = if is_depth_stencil { 0 } else { 1 };
+
+    image::ImageCreateInfo {
+        dimension: dim,
+        num_layers: img.layers,
+        num_samples: img.samples,
+        num_mipmaps: num_mips,
+        format,
+        swizzle: image::Swizzle::NO,
+        kind,
+        usage,
+        is_transient: false,
+    }
+}
+
+// Register a newly created image for a resource and replace the previous one.
+#[allow(clippy::too_many_arguments)]
+unsafe fn insert_image(
+    device: &DeviceContext,
+    image_storage: &mut image::ImageStorage,
+    sampler_storage: &mut sampler::SamplerStorage,
+    res_list: &mut ResourceList,
+    res: &mut GraphResources,
+    id: ResourceId,
+    img_handle: ImageHandle,
+    usage: gfx::image::Usage,
+) {
+    let old_image = res.images.insert(id, img_handle);
+
+    // If the image is used for sampling then it means some other pass will read from it
+    // as a color image. In that case we create a sampler for this image as well
+    let old_sampler = if usage.contains(gfx::image::Usage::SAMPLED) {
+        let sampler = sampler_storage.create(
+            device,
+            sampler::SamplerCreateInfo {
+                min_filter: sampler::Filter::Linear,
+                mip_filter: sampler::Filter::Linear,
+                mag_filter: sampler::Filter::Linear,
+                wrap_mode: (
+                    sampler::WrapMode::Clamp,
+                    sampler::WrapMode::Clamp,
+                    sampler::WrapMode::Clamp,
+                ),
+                lod_range: (0.0, 0.0),
+            },
+        );
+        res.samplers.insert(id, sampler)
+    } else {
+        None
+    };
+
+    if let Some(old_img) = old_image {
+        image_storage.destroy(res_list, &[old_img]);
+    }
+    if let Some(old_samp) = old_sampler {
+        sampler_storage.destroy(res_list, &[old_samp]);
+    }
+}
+
+// Create all resources of an aliasing slot and bind them to one shared block of memory.
+#[allow(clippy::too_many_arguments)]
+unsafe fn create_aliased_resources(
+    device: &DeviceContext,
+    storages: &Storages,
+    res_list: &mut ResourceList,
+    usages: &ResourceUsages,
+    res: &mut GraphResources,
+    resolved: &GraphWithNamesResolved,
+    slot: usize,
+    members: &[ResourceId],
+    context: &ExecutionContext,
+) -> Result<(), PrepareError> {
+    use crate::util::allocator::Request;
+
+    enum Unbound {
+        Image(image::UnboundImage, gfx::image::Usage),
+        Buffer(crate::buffer::UnboundBuffer),
+    }
+
+    let mut image_storage = storages.image.borrow_mut();
+    let mut sampler_storage = storages.sampler.borrow_mut();
+    let mut buffer_storage = storages.buffer.borrow_mut();
+
+    let mut unbound = Vec::with_capacity(members.len());
+
+    for id in members {
+        let resource = match &resolved.infos[id] {
+            ResourceCreateInfo::Image(ImageInfo::Create(img)) => {
+                let usage = usages.image[id].0;
+                let create_info = image_create_info(img, usage, context);
+
+                Unbound::Image(image_storage.create_unbound(device, create_info)?, usage)
+            }
+            ResourceCreateInfo::Buffer(buf) => {
+                let create_info = crate::buffer::DeviceLocalCreateInfo {
+                    size: buf.size,
+                    is_transient: false,
+                    usage: usages.buffer[id],
+                };
+
+                Unbound::Buffer(buffer_storage.device_local_create_unbound(device, create_info)?)
+            }
+            _ => unreachable!("Only created images and buffers can share memory"),
+        };
+
+        unbound.push((*id, resource));
+    }
+
+    // The shared memory has to satisfy the requirements of every resource using it.
+    let request = unbound
+        .iter()
+        .map(|(_, resource)| match resource {
+            Unbound::Image(img, _) => img.requirements(),
+            Unbound::Buffer(buf) => buf.requirements(),
+        })
+        .fold(
+            Request {
+                transient: false,
+                properties: gfx::memory::Properties::DEVICE_LOCAL,
+                size: 0,
+                alignment: 1,
+                type_mask: !0,
+            },
+            |req, reqs| Request {
+                size: req.size.max(reqs.size),
+                alignment: req.alignment.max(reqs.alignment),
+                type_mask: req.type_mask & reqs.type_mask,
+                ..req
+            },
+        );
+
+    // If there is no memory type that is compatible with all resources, each resource gets its
+    // own memory instead.
+    let block = if request.type_mask != 0 {
+        let block = device
+            .allocator()
+            .alloc(&device.device, request)
+            .map_err(AllocatorError::from)?;
+
+        Some(block)
+    } else {
+        None
+    };
+
+    for (id, resource) in unbound {
+        match resource {
+            Unbound::Image(img, usage) => {
+                let handle = image_storage.bind(device, img, block.as_ref())?;
+
+                insert_image(
+                    device,
+                    &mut image_storage,
+                    &mut sampler_storage,
+                    res_list,
+                    res,
+                    id,
+                    handle,
+                    usage,
+                );
+            }
+            Unbound::Buffer(buf) => {
+                let handle = buffer_storage.device_local_bind(device, buf, block.as_ref())?;
+
+                if let Some(old_buf) = res.buffers.insert(id, handle) {
+                    buffer_storage.destroy(res_list, &[old_buf]);
+                }
+            }
+        }
+    }
+
+    // The previous block can only be freed once the resources using it are destroyed, which is
+    // taken care of by the resource list.
+    let old_block = match block {
+        Some(block) => res.shared_memory.insert(slot, block),
+        None => res.shared_memory.remove(&slot),
+    };
+
+    if let Some(old_block) = old_block {
+        res_list.queue_memory(old_block);
+    }
+
+    Ok(())
+}
+
 unsafe fn create_pipeline_base<'a>(
     material_storage: &'a MaterialStorage,
     pass_material: Option<MaterialHandle>,
     materials: &[(usize, MaterialHandle)],
-) -> BTreeMap<usize, &'a types::DescriptorSetLayout> {
+) -> BTreeMap<usize, &'a Material> {
     let mut sets = BTreeMap::new();
 
     // base material
     if let Some(pass_material) = pass_material {
         if let Some(mat) = material_storage.raw(pass_material) {
-            sets.insert(0, &mat.desc_set_layout);
+            sets.insert(0, mat);
         }
     }
 
@@ -845,14 +1244,38 @@ unsafe fn create_pipeline_base<'a>(
             None => continue,
         };
 
-        let layout = &mat.desc_set_layout;
-
-        sets.insert(*set, layout);
+        sets.insert(*set, mat);
     }
 
     sets
 }
 
+/// Check that a pipeline provides everything the shader uses.
+fn validate_shader<T>(
+    shader: &Shader<T>,
+    interface: &PipelineInterface,
+    specialization: &[Specialization],
+) -> Result<(), PrepareError> {
+    shader.reflection.validate(interface, specialization)?;
+
+    Ok(())
+}
+
+fn pipeline_interface<'a>(
+    sets: &BTreeMap<usize, &'a Material>,
+    push_constants: Option<&std::ops::Range<u32>>,
+    vertex_attrib: Option<&'a VertexAttrib>,
+) -> PipelineInterface<'a> {
+    PipelineInterface {
+        sets: sets
+            .iter()
+            .map(|(set, mat)| (*set as u32, mat.parameters()))
+            .collect(),
+        push_constants: push_constants.cloned(),
+        vertex_attrib,
+    }
+}
+
 /// Create the material for a pass.
 pub(crate) unsafe fn create_pass_material(
     device: &DeviceContext,
@@ -862,7 +1285,7 @@ pub(crate) unsafe fn create_pass_material(
 ) -> Result<Option<MaterialHandle>, PrepareError> {
     use gfx::Device;
 
-    let (core_desc, core_range) = {
+    let core_desc = {
         let reads = graph.pass_reads[&pass]
             .iter()
             .filter(|(_id, _ty, _, _)| match _ty {
@@ -961,60 +1384,16 @@ pub(crate) unsafe fn create_pass_material(
             .chain(sampler_descriptors)
             .chain(write_descriptors);
 
-        let range = reads
-            .map(|(_, ty, _, _)| {
-                gfx::pso::DescriptorRangeDesc {
-                    ty: match ty {
-                        ResourceReadType::Image(img) => match img {
-                            ImageReadType::Color => gfx::pso::DescriptorType::SampledImage,
-                            ImageReadType::Storage => gfx::pso::DescriptorType::StorageImage,
-                            ImageReadType::DepthStencil => unreachable!(),
-                        },
-                        ResourceReadType::Buffer(buf) => {
-                            match buf {
-                                BufferReadType::Uniform => gfx::pso::DescriptorType::UniformBuffer,
-                                BufferReadType::UniformTexel => {
-                                    // TODO test this
-                                    // does this need samplers? I think so. Let's find out!
-                                    gfx::pso::DescriptorType::UniformTexelBuffer
-                                }
-                                BufferReadType::Storage => gfx::pso::DescriptorType::StorageBuffer,
-                                BufferReadType::StorageTexel => {
-                                    //  TODO test this
-                                    // does this need samplers? I think so. Let's find out!
-                                    gfx::pso::DescriptorType::StorageTexelBuffer
-                                }
-                            }
-                        }
-                        ResourceReadType::Virtual => unreachable!(),
-                    },
-                    count: 1,
-                }
-            })
-            .chain(samplers.map(|_| gfx::pso::DescriptorRangeDesc {
-                ty: gfx::pso::DescriptorType::Sampler,
-                count: 1,
-            }))
-            .chain(writes.map(|(_, ty, _)| gfx::pso::DescriptorRangeDesc {
-                ty: match ty {
-                    ResourceWriteType::Image(img) => match img {
-                        ImageWriteType::Storage => gfx::pso::DescriptorType::StorageImage,
-                        _ => unreachable!(),
-                    },
-                    ResourceWriteType::Buffer(buf) => match buf {
-                        BufferWriteType::Storage => gfx::pso::DescriptorType::StorageBuffer,
-                        _ => unimplemented!(),
-                    },
-                },
-                count: 1,
-            }));
-
-        (descriptors, range)
+        descriptors.collect::<SmallVec<[_; 16]>>()
     };
 
-    let pass_set_layout = device.device.create_descriptor_set_layout(core_desc, &[])?;
+    let pass_set_layout = device
+        .device
+        .create_descriptor_set_layout(&core_desc, &[])?;
+
+    let params = core_desc.iter().map(|desc| (desc.binding, desc.ty));
 
-    let mat = material_storage.create_raw(device, pass_set_layout, core_range, 16);
+    let mat = material_storage.create_raw(device, pass_set_layout, params, 16);
 
     Ok(mat)
 }
@@ -0,0 +1,298 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use super::*;
+
+use crate::graph::compilation::CompiledGraph;
+use crate::graph::{BufferStorageType, ImageInfo, PassType, ResourceCreateInfo, ResourceReadType};
+
+use gfx::pso::PipelineStage;
+
+/// Queue that passes are submitted to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum QueueType {
+    /// Graphics passes and compute passes which don't ask for async compute.
+    Graphics,
+    /// Compute passes which can run at the same time as graphics work.
+    AsyncCompute,
+}
+
+/// Queue a pass is submitted to.
+///
+/// Resources which are accessed outside of the graph are owned by the graphics queue, so passes
+/// using those are never submitted to the async compute queue.
+pub(crate) fn pass_queue(compiled: &CompiledGraph, pass: PassId) -> QueueType {
+    let resolved = &compiled.graph_resources;
+
+    let async_compute = compiled
+        .compute_passes
+        .get(&pass)
+        .map(|accessor| accessor.async_compute)
+        .unwrap_or(false);
+
+    if !async_compute {
+        return QueueType::Graphics;
+    }
+
+    let is_external = |res: ResourceId| {
+        let is_target = compiled
+            .targets
+            .iter()
+            .any(|target| resolved.moved_from(*target) == Some(res));
+
+        let is_external_info = match resolved.infos.get(&res) {
+            Some(ResourceCreateInfo::Image(ImageInfo::BackbufferRead { .. })) => true,
+            Some(ResourceCreateInfo::Buffer(buf)) => match buf.storage {
+                BufferStorageType::HostVisible => true,
+                BufferStorageType::DeviceLocal => false,
+            },
+            _ => false,
+        };
+
+        is_target || is_external_info
+    };
+
+    if pass_resource_states(resolved, pass)
+        .keys()
+        .any(|res| is_external(*res))
+    {
+        QueueType::Graphics
+    } else {
+        QueueType::AsyncCompute
+    }
+}
+
+/// Passes which are recorded into the same command buffer.
+#[derive(Debug, Clone)]
+pub(crate) struct Submission {
+    /// Queue the command buffer is submitted to.
+    pub(crate) queue: QueueType,
+    /// Type of all passes in the submission.
+    pub(crate) ty: PassType,
+    /// Passes in the order they are recorded.
+    pub(crate) passes: Vec<PassId>,
+    /// Indices of earlier submissions that have to finish before this one can start.
+    pub(crate) waits: Vec<usize>,
+    /// Stages which wait for the earlier submissions.
+    pub(crate) wait_stages: PipelineStage,
+}
+
+/// Grouping of passes into queue submissions.
+///
+/// Consecutive passes of the same type on the same queue are recorded into one command buffer,
+/// where they are synchronized with the barriers from the `BarrierPlan`. Submissions only wait
+/// for the submissions containing passes they depend on, so independent graphics and async
+/// compute work can run at the same time.
+#[derive(Debug, Default)]
+pub(crate) struct SubmissionPlan {
+    pub(crate) submissions: Vec<Submission>,
+}
+
+impl SubmissionPlan {
+    pub(crate) fn new(
+        compiled: &CompiledGraph,
+        exec: &ExecutionGraph,
+        barriers: &BarrierPlan,
+    ) -> Self {
+        let resolved = &compiled.graph_resources;
+
+        let mut submissions: Vec<Submission> = vec![];
+        let mut submission_of = HashMap::new();
+
+        for pass in exec.pass_execution.iter().flat_map(|batch| &batch.passes) {
+            let ty = resolved.pass_types[pass];
+            let queue = pass_queue(compiled, *pass);
+            let pass_barriers = &barriers.passes[pass];
+
+            // Virtual reads are not covered by any barrier, so those
+            // always need a semaphore.
+            let virtual_deps = resolved.pass_reads[pass]
+                .iter()
+                .filter(|(_, ty, _, _)| *ty == ResourceReadType::Virtual)
+                .filter_map(|(res, _, _, _)| resolved.defines.get(res))
+                .filter_map(|dep| submission_of.get(dep).cloned())
+                .collect::<Vec<usize>>();
+
+            let deps = pass_barriers
+                .dependencies
+                .iter()
+                .filter_map(|dep| submission_of.get(dep).cloned())
+                .chain(virtual_deps.iter().cloned())
+                .collect::<Vec<usize>>();
+
+            // A pass can only be appended to the last submission if that doesn't make the
+            // passes already in it wait for anything new.
+            let merge = match submissions.last() {
+                Some(last) => {
+                    let last_idx = submissions.len() - 1;
+                    last.queue == queue
+                        && last.ty == ty
+                        && !virtual_deps.contains(&last_idx)
+                        && deps
+                            .iter()
+                            .all(|dep| *dep == last_idx || last.waits.contains(dep))
+                }
+                None => false,
+            };
+
+            if !merge {
+                submissions.push(Submission {
+                    queue,
+                    ty,
+                    passes: vec![],
+                    waits: vec![],
+                    wait_stages: PipelineStage::empty(),
+                });
+            }
+
+            let idx = submissions.len() - 1;
+            let submission = &mut submissions[idx];
+
+            for dep in deps {
+                if dep != idx && !submission.waits.contains(&dep) {
+                    submission.waits.push(dep);
+                }
+            }
+
+            submission.waits.sort();
+            submission.passes.push(*pass);
+            submission.wait_stages |= pass_barriers.wait_stages;
+
+            submission_of.insert(*pass, idx);
+        }
+
+        SubmissionPlan { submissions }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::graph::compilation::compile_graph;
+    use crate::graph::test_util::{image_info, DescribePass};
+    use crate::graph::{GraphBuilder, ResourceDescriptor};
+
+    fn plan(builder: GraphBuilder) -> (CompiledGraph, SubmissionPlan) {
+        let compiled = compile_graph(builder).unwrap();
+        let exec = ExecutionGraph::new(&compiled);
+        let aliasing = AliasingPlan::new(&compiled, &exec);
+        let barriers = BarrierPlan::new(&compiled, &exec, &aliasing);
+        let plan = SubmissionPlan::new(&compiled, &exec, &barriers);
+
+        (compiled, plan)
+    }
+
+    fn pass_names(compiled: &CompiledGraph, submission: &Submission) -> Vec<String> {
+        submission
+            .passes
+            .iter()
+            .map(|pass| compiled.pass_names[pass.0].to_string())
+            .collect()
+    }
+
+    #[test]
+    fn independent_passes_do_not_wait() {
+        let mut builder = GraphBuilder::new("schedule");
+
+        builder.add_graphics_pass(
+            "render",
+            DescribePass::new(|res: &mut ResourceDescriptor| {
+                res.image_create("color", image_info());
+                res.image_write_color("color", 0);
+            }),
+        );
+        builder.add_compute_pass(
+            "simulate",
+            DescribePass::new_async(|res: &mut ResourceDescriptor| {
+                res.image_create("data", image_info());
+                res.image_write_storage("data", 0);
+            }),
+        );
+        builder.add_compute_pass(
+            "combine",
+            DescribePass::new(|res: &mut ResourceDescriptor| {
+                res.image_create("out", image_info());
+                res.image_write_storage("out", 0);
+                res.image_read_color("color", 1, Some(2));
+                res.image_read_storage("data", 3);
+            }),
+        );
+        builder.add_target("out");
+
+        let (compiled, plan) = plan(builder);
+
+        assert_eq!(plan.submissions.len(), 3);
+
+        assert_eq!(pass_names(&compiled, &plan.submissions[0]), vec!["render"]);
+        assert_eq!(
+            pass_names(&compiled, &plan.submissions[1]),
+            vec!["simulate"]
+        );
+        assert!(plan.submissions[0].waits.is_empty());
+        assert!(plan.submissions[1].waits.is_empty());
+
+        assert_eq!(plan.submissions[0].queue, QueueType::Graphics);
+        assert_eq!(plan.submissions[1].queue, QueueType::AsyncCompute);
+
+        // "combine" writes a target, so it stays on the graphics queue.
+        assert_eq!(pass_names(&compiled, &plan.submissions[2]), vec!["combine"]);
+        assert_eq!(plan.submissions[2].queue, QueueType::Graphics);
+        assert_eq!(plan.submissions[2].waits, vec![0, 1]);
+    }
+
+    #[test]
+    fn same_queue_passes_are_merged() {
+        let mut builder = GraphBuilder::new("schedule");
+
+        builder.add_compute_pass(
+            "first",
+            DescribePass::new(|res: &mut ResourceDescriptor| {
+                res.image_create("a", image_info());
+                res.image_write_storage("a", 0);
+            }),
+        );
+        builder.add_compute_pass(
+            "second",
+            DescribePass::new(|res: &mut ResourceDescriptor| {
+                res.image_create("b", image_info());
+                res.image_write_storage("b", 0);
+                res.image_read_storage("a", 1);
+            }),
+        );
+        builder.add_target("b");
+
+        let (compiled, plan) = plan(builder);
+
+        assert_eq!(plan.submissions.len(), 1);
+        assert_eq!(
+            pass_names(&compiled, &plan.submissions[0]),
+            vec!["first", "second"]
+        );
+    }
+
+    #[test]
+    fn virtual_dependencies_are_not_merged() {
+        let mut builder = GraphBuilder::new("schedule");
+
+        builder.add_compute_pass(
+            "first",
+            DescribePass::new(|res: &mut ResourceDescriptor| res.virtual_create("side_effect")),
+        );
+        builder.add_compute_pass(
+            "second",
+            DescribePass::new(|res: &mut ResourceDescriptor| {
+                res.image_create("out", image_info());
+                res.image_write_storage("out", 0);
+                res.virtual_read("side_effect");
+            }),
+        );
+        builder.add_target("out");
+
+        let (_, plan) = plan(builder);
+
+        assert_eq!(plan.submissions.len(), 2);
+        assert_eq!(plan.submissions[1].waits, vec![0]);
+    }
+}
@@ -0,0 +1,375 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Inspectable descriptions of compiled graphs.
+//!
+//! These are created by `GraphBuilder::compile`, which does not need a device.
+
+use super::*;
+
+use std::collections::{BTreeMap, HashSet};
+
+/// Description of what nitrogen made of a graph.
+#[derive(Debug, Clone)]
+pub struct CompiledGraphInfo {
+    /// All passes in the graph, including the ones that are not executed.
+    pub passes: Vec<PassInfo>,
+    /// Batches of passes, in the order they are executed.
+    pub batches: Vec<BatchInfo>,
+    /// All resources in the graph, sorted by name.
+    pub resources: Vec<ResourceInfo>,
+    /// Groups of transient resources that share the same memory.
+    ///
+    /// Resources in a group are never alive at the same time. They are listed in the order they
+    /// are created in.
+    pub shared_memory: Vec<Vec<ResourceName>>,
+}
+
+/// Description of a pass in a compiled graph.
+#[derive(Debug, Clone)]
+pub struct PassInfo {
+    /// Name of the pass.
+    pub name: PassName,
+    /// Kind of the pass.
+    pub ty: PassType,
+    /// Index of the batch the pass is executed in.
+    ///
+    /// `None` if the pass is not needed to produce any of the targets.
+    pub batch: Option<usize>,
+    /// Whether the pass depends on the [`ExecutionContext`](struct.ExecutionContext.html).
+    pub context_dependent: bool,
+    /// Names of the backbuffer resources the pass renders to.
+    pub backbuffer: Vec<ResourceName>,
+}
+
+/// Description of a batch of passes which can be executed in any order.
+#[derive(Debug, Clone)]
+pub struct BatchInfo {
+    /// Names of the passes in the batch.
+    pub passes: Vec<PassName>,
+    /// Resources that are created before the batch is executed.
+    pub creates: Vec<ResourceName>,
+    /// Resources that are destroyed after the batch is executed.
+    pub destroys: Vec<ResourceName>,
+}
+
+/// Description of a resource in a compiled graph.
+#[derive(Debug, Clone)]
+pub struct ResourceInfo {
+    /// Name of the resource.
+    pub name: ResourceName,
+    /// Type the resource was created as.
+    pub ty: ResourceType,
+    /// Name of the pass that created or moved the resource.
+    pub defined_by: PassName,
+    /// Name of the resource this resource was moved from, if any.
+    pub moved_from: Option<ResourceName>,
+    /// Name of the resource at the start of the move-chain.
+    ///
+    /// This is the same as `name` for resources that have not been moved.
+    pub origin: ResourceName,
+    /// Batches in which the underlying resource is alive.
+    ///
+    /// `None` if the resource is not needed to produce any of the targets.
+    pub lifetime: Option<ResourceLifetime>,
+    /// Whether the resource depends on the [`ExecutionContext`](struct.ExecutionContext.html).
+    pub context_dependent: bool,
+    /// Whether the resource is a target of the graph.
+    pub target: bool,
+    /// Usage derived from all the ways the underlying resource is used in the graph.
+    pub usage: ResourceUsage,
+}
+
+/// Batches during which a resource is alive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResourceLifetime {
+    /// Index of the batch before which the resource is created.
+    pub created: usize,
+    /// Index of the batch after which the resource is destroyed.
+    ///
+    /// `None` if the resource is kept after the graph has been executed.
+    pub destroyed: Option<usize>,
+}
+
+/// Usage derived for a resource.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ResourceUsage {
+    /// Usage of an image resource.
+    #[allow(missing_docs)]
+    Image {
+        usage: gfx::image::Usage,
+        format: gfx::format::Format,
+    },
+    /// Usage of a buffer resource.
+    #[allow(missing_docs)]
+    Buffer { usage: gfx::buffer::Usage },
+    /// The resource is virtual or never used.
+    None,
+}
+
+impl CompiledGraphInfo {
+    pub(crate) fn new(
+        compiled: &CompiledGraph,
+        exec: &ExecutionGraph,
+        usages: &ResourceUsages,
+        aliasing: &AliasingPlan,
+    ) -> Self {
+        let graph = &compiled.graph_resources;
+
+        let res_names = graph
+            .name_lookup
+            .iter()
+            .map(|(name, id)| (*id, name.clone()))
+            .collect::<BTreeMap<_, _>>();
+
+        let sorted_names = |ids: &HashSet<ResourceId>| {
+            let mut names = ids
+                .iter()
+                .filter_map(|id| res_names.get(id).cloned())
+                .collect::<Vec<_>>();
+            names.sort();
+            names
+        };
+
+        let batches = exec
+            .pass_execution
+            .iter()
+            .map(|batch| BatchInfo {
+                passes: batch
+                    .passes
+                    .iter()
+                    .map(|pass| compiled.pass_names[pass.0].clone())
+                    .collect(),
+                creates: sorted_names(&batch.resource_create),
+                destroys: sorted_names(&batch.resource_destroy),
+            })
+            .collect();
+
+        let passes = graph
+            .pass_types
+            .iter()
+            .map(|(pass, ty)| PassInfo {
+                name: compiled.pass_names[pass.0].clone(),
+                ty: *ty,
+                batch: exec
+                    .pass_execution
+                    .iter()
+                    .position(|batch| batch.passes.contains(pass)),
+                context_dependent: compiled.contextual_passes.contains(pass),
+                backbuffer: compiled
+                    .passes_that_render_to_the_backbuffer
+                    .get(pass)
+                    .cloned()
+                    .unwrap_or_default(),
+            })
+            .collect();
+
+        let mut resources = res_names
+            .iter()
+            .filter_map(|(id, name)| {
+                let (origin, info) = graph.create_info(*id)?;
+
+                let lifetime = exec
+                    .pass_execution
+                    .iter()
+                    .position(|batch| batch.resource_create.contains(&origin))
+                    .map(|created| ResourceLifetime {
+                        created,
+                        destroyed: exec
+                            .pass_execution
+                            .iter()
+                            .position(|batch| batch.resource_destroy.contains(&origin)),
+                    });
+
+                let usage = if let Some((usage, format)) = usages.image.get(&origin) {
+                    ResourceUsage::Image {
+                        usage: *usage,
+                        format: *format,
+                    }
+                } else if let Some(usage) = usages.buffer.get(&origin) {
+                    ResourceUsage::Buffer { usage: *usage }
+                } else {
+                    ResourceUsage::None
+                };
+
+                Some(ResourceInfo {
+                    name: name.clone(),
+                    ty: ResourceType::from(info),
+                    defined_by: compiled.pass_names[graph.defines[id].0].clone(),
+                    moved_from: graph
+                        .moves_from
+                        .get(id)
+                        .and_then(|prev| res_names.get(prev))
+                        .cloned(),
+                    origin: res_names[&origin].clone(),
+                    lifetime,
+                    context_dependent: compiled.contextual_resources.contains(&origin),
+                    target: compiled.targets.contains(id),
+                    usage,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        resources.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let shared_memory = aliasing
+            .slots
+            .iter()
+            .map(|members| members.iter().map(|id| res_names[id].clone()).collect())
+            .collect();
+
+        CompiledGraphInfo {
+            passes,
+            batches,
+            resources,
+            shared_memory,
+        }
+    }
+
+    /// Names of all executed passes, in execution order.
+    pub fn pass_order(&self) -> impl Iterator<Item = &PassName> {
+        self.batches.iter().flat_map(|batch| batch.passes.iter())
+    }
+
+    /// Retrieve the description of a pass by name.
+    pub fn pass(&self, name: &str) -> Option<&PassInfo> {
+        self.passes.iter().find(|pass| pass.name == name)
+    }
+
+    /// Retrieve the description of a resource by name.
+    pub fn resource(&self, name: &str) -> Option<&ResourceInfo> {
+        self.resources.iter().find(|res| res.name == name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::graph::test_util::{image_info, DescribePass};
+
+    use gfx::image::Usage;
+
+    #[test]
+    fn compile_without_device() {
+        let mut builder = GraphBuilder::new("info");
+
+        builder.add_compute_pass(
+            "blur",
+            DescribePass::new(|res: &mut ResourceDescriptor| {
+                res.image_create("blurred", image_info());
+                res.image_write_color("blurred", 0);
+                res.image_read_color("base", 0, Some(1));
+            }),
+        );
+        builder.add_compute_pass(
+            "base",
+            DescribePass::new(|res: &mut ResourceDescriptor| {
+                res.image_create("base", image_info());
+                res.image_write_color("base", 0);
+            }),
+        );
+        builder.add_compute_pass(
+            "unused",
+            DescribePass::new(|res: &mut ResourceDescriptor| res.virtual_create("nothing")),
+        );
+        builder.add_target("blurred");
+
+        let info = builder.compile().unwrap();
+
+        assert_eq!(info.pass_order().collect::<Vec<_>>(), vec!["base", "blur"]);
+        assert_eq!(info.pass("unused").unwrap().batch, None);
+        assert_eq!(info.pass("blur").unwrap().ty, PassType::Compute);
+
+        assert_eq!(info.batches[0].creates, vec!["base"]);
+        assert_eq!(info.batches[1].destroys, vec!["base"]);
+
+        let base = info.resource("base").unwrap();
+        assert_eq!(base.ty, ResourceType::Image);
+        assert_eq!(
+            base.lifetime,
+            Some(ResourceLifetime {
+                created: 0,
+                destroyed: Some(1),
+            })
+        );
+        assert_eq!(
+            base.usage,
+            ResourceUsage::Image {
+                usage: Usage::COLOR_ATTACHMENT
+                    | Usage::TRANSFER_SRC
+                    | Usage::TRANSFER_DST
+                    | Usage::SAMPLED,
+                format: gfx::format::Format::Rgba8Unorm,
+            }
+        );
+
+        let blurred = info.resource("blurred").unwrap();
+        assert!(blurred.target);
+        assert_eq!(
+            blurred.lifetime,
+            Some(ResourceLifetime {
+                created: 1,
+                destroyed: None,
+            })
+        );
+
+        assert_eq!(info.resource("nothing").unwrap().lifetime, None);
+    }
+
+    #[test]
+    fn transient_resources_share_memory() {
+        let mut builder = GraphBuilder::new("aliasing");
+
+        let chain = [
+            ("a", None),
+            ("b", Some("a")),
+            ("c", Some("b")),
+            ("d", Some("c")),
+        ];
+
+        for (name, input) in chain.iter().cloned() {
+            builder.add_compute_pass(
+                name,
+                DescribePass::new(move |res: &mut ResourceDescriptor| {
+                    res.image_create(name, image_info());
+                    res.image_write_color(name, 0);
+                    if let Some(input) = input {
+                        res.image_read_color(input, 0, Some(1));
+                    }
+                }),
+            );
+        }
+        builder.add_target("d");
+
+        let info = builder.compile().unwrap();
+
+        // "b" is still alive when "c" is created and "d" is a target, so only "a" and "c" can
+        // use the same memory.
+        assert_eq!(info.shared_memory, vec![vec!["a", "c"]]);
+    }
+
+    #[test]
+    fn compile_reports_errors() {
+        let mut builder = GraphBuilder::new("errors");
+
+        builder.add_compute_pass(
+            "pass",
+            DescribePass::new(|res: &mut ResourceDescriptor| res.virtual_read("missing")),
+        );
+
+        let errors = builder.compile().err().unwrap();
+
+        assert_eq!(errors.errors.len(), 1);
+        assert_eq!(errors.diagnostics().len(), 1);
+
+        match &errors.errors[0] {
+            CompileError::ReferencedInvalidResource { res, pass } => {
+                assert_eq!(res, "missing");
+                assert_eq!(errors.pass_name(*pass).unwrap(), "pass");
+            }
+            err => panic!("unexpected error {:?}", err),
+        }
+    }
+}
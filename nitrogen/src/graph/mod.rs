@@ -27,6 +27,14 @@ pub use self::pass::*;
 pub(crate) mod compilation;
 pub(crate) mod execution;
 
+mod dot;
+
+#[cfg(test)]
+pub(crate) mod test_util;
+
+mod info;
+pub use self::info::*;
+
 pub(crate) use self::compilation::*;
 pub(crate) use self::execution::*;
 
@@ -34,7 +42,7 @@ pub use self::execution::Backbuffer;
 pub use self::execution::GraphExecError;
 pub use self::execution::PrepareError;
 
-pub use self::compilation::CompileError;
+pub use self::compilation::{CompileError, CompileErrors};
 
 pub mod store;
 pub use self::store::*;
@@ -42,6 +50,7 @@ pub use self::store::*;
 use crate::resources::image::ImageHandle;
 use crate::resources::shader::ShaderStorage;
 use crate::submit_group::{QueueSyncRefs, ResourceList};
+use std::any::Any;
 use std::cell::RefCell;
 use std::collections::BTreeMap;
 
@@ -72,8 +81,11 @@ pub type ResourceName = CowString;
 // The solution here is to use "accessor closures" which capture the actual value with the
 // associated type and perform further dispatch from there.
 pub(crate) struct ComputePassAccessor {
+    pub(crate) async_compute: bool,
     pub(crate) prepare: Box<dyn Fn(&mut Store)>,
     pub(crate) describe: Box<dyn Fn(&mut ResourceDescriptor)>,
+    pub(crate) warm_configs: Box<dyn Fn() -> Vec<ComputePipelineInfo>>,
+    pub(crate) lazy_configs: Box<dyn Fn() -> Box<dyn Any>>,
     pub(crate) execute: Box<dyn Fn(&Store, RawComputeDispatcher) -> Result<(), GraphExecError>>,
 }
 
@@ -81,6 +93,8 @@ pub(crate) struct ComputePassAccessor {
 pub(crate) struct GraphicPassAccessor {
     pub(crate) prepare: Box<dyn Fn(&mut Store)>,
     pub(crate) describe: Box<dyn Fn(&mut ResourceDescriptor)>,
+    pub(crate) warm_configs: Box<dyn Fn() -> Vec<GraphicsPipelineInfo>>,
+    pub(crate) lazy_configs: Box<dyn Fn() -> Box<dyn Any>>,
     pub(crate) execute: Box<dyn Fn(&Store, RawGraphicsDispatcher) -> Result<(), GraphExecError>>,
 }
 
@@ -100,13 +114,18 @@ pub enum GraphError {
 /// Graphs are conceptually made up of a set of *passes*. Each pass is a single step transformation
 /// in the graph.
 pub struct Graph {
-    pub(crate) _name: GraphName,
+    pub(crate) name: GraphName,
 
     pub(crate) compiled_graph: CompiledGraph,
     pub(crate) exec_graph: ExecutionGraph,
     pub(crate) res_usage: ResourceUsages,
+    pub(crate) aliasing: AliasingPlan,
+    pub(crate) barriers: BarrierPlan,
+    pub(crate) submissions: SubmissionPlan,
 
     pub(crate) pass_resources: PassResources,
+    /// Shader storage generation the pass pipelines were last checked against.
+    pub(crate) shader_generation: u64,
 
     // TODO generalize when backbuffers have more resource types than images.
     pub(crate) backbuffer_compat: Option<BTreeMap<ResourceName, ImageHandle>>,
@@ -131,10 +150,8 @@ impl GraphStorage {
     ) -> Result<GraphHandle, GraphError> {
         let name = builder.name.clone();
 
-        let compiled = compile_graph(builder).map_err(|(names, errors)| {
-            let errors = errors.into_iter().map(|err| err.diagnostic(&names));
-            GraphError::CompilationErrors(errors.collect())
-        })?;
+        let compiled = compile_graph(builder)
+            .map_err(|errors| GraphError::CompilationErrors(errors.diagnostics()))?;
 
         let exec_graph = ExecutionGraph::new(&compiled);
 
@@ -162,22 +179,33 @@ impl GraphStorage {
                         // graphics
                         prepare_graphics_pass_base(device, storages, &mut res, *pass, &compiled)?;
                     }
+
+                    prepare_pass_pipelines(device, storages, &mut res, *pass, &compiled)?;
                 }
             }
 
             res
         };
 
+        let shader_generation = storages.shader.borrow().generation;
+
         let res_usage = derive_resource_usage(&exec_graph, &compiled);
+        let aliasing = AliasingPlan::new(&compiled, &exec_graph);
+        let barriers = BarrierPlan::new(&compiled, &exec_graph, &aliasing);
+        let submissions = SubmissionPlan::new(&compiled, &exec_graph, &barriers);
 
         let graph = Graph {
-            _name: name,
+            name,
 
             compiled_graph: compiled,
             exec_graph,
             res_usage,
+            aliasing,
+            barriers,
+            submissions,
 
             pass_resources,
+            shader_generation,
 
             backbuffer_compat: None,
         };
@@ -204,7 +232,11 @@ impl GraphStorage {
         device: &'a DeviceContext,
         sync: &mut QueueSyncRefs,
         storages: &'a mut Storages<'a>,
-        (pool_gfx, pool_cmpt): (&CommandPoolGraphics, &CommandPoolCompute),
+        pools: (
+            &CommandPoolGraphics,
+            &CommandPoolCompute,
+            &CommandPoolCompute,
+        ),
         store: &mut Store,
         graph_handle: GraphHandle,
         res: &mut GraphResources,
@@ -216,6 +248,18 @@ impl GraphStorage {
             .get_mut(graph_handle)
             .ok_or(GraphExecError::InvalidGraph)?;
 
+        // pipelines using replaced shaders
+        {
+            let generation = storages.shader.borrow().generation;
+
+            if graph.shader_generation != generation {
+                graph
+                    .pass_resources
+                    .retire_outdated_pipelines(sync.res_list, storages);
+                graph.shader_generation = generation;
+            }
+        }
+
         // graph resources
         match res.exec_context.clone() {
             None => {
@@ -331,15 +375,33 @@ impl GraphStorage {
             }
         }
 
-        execution::execute(
-            device,
-            sync,
-            (pool_gfx, pool_cmpt),
-            storages,
-            store,
-            graph,
-            res,
-        )
+        execution::execute(device, sync, pools, storages, store, graph, res)
+    }
+
+    pub(crate) fn lazy_configs<C: Any>(&self, handle: GraphHandle, pass: &str) -> Option<Vec<C>> {
+        let graph = self.storage.get(handle)?;
+        let compiled = &graph.compiled_graph;
+
+        let id = compiled.pass_names.iter().position(|name| name == pass)?;
+        let pass = PassId(id);
+
+        let configs = if let Some(accessor) = compiled.graphic_passes.get(&pass) {
+            (accessor.lazy_configs)()
+        } else {
+            (compiled.compute_passes.get(&pass)?.lazy_configs)()
+        };
+
+        configs.downcast::<Vec<C>>().ok().map(|configs| *configs)
+    }
+
+    pub(crate) fn export_dot(&self, handle: GraphHandle) -> Option<String> {
+        let graph = self.storage.get(handle)?;
+
+        Some(dot::export_dot(
+            &graph.name,
+            &graph.compiled_graph,
+            &graph.exec_graph,
+        ))
     }
 
     pub(crate) fn resource_id(
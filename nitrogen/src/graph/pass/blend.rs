@@ -0,0 +1,476 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Description of how fragment outputs are combined with the contents of color attachments.
+
+use bitflags::bitflags;
+
+use std::hash::{Hash, Hasher};
+
+/// Blend presets used for color attachments.
+///
+/// Every preset can be converted into a [`BlendState`].
+///
+/// [`BlendState`]: struct.BlendState.html
+#[allow(missing_docs)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BlendMode {
+    Alpha,
+    Add,
+    Mul,
+}
+
+impl From<BlendMode> for BlendState {
+    fn from(mode: BlendMode) -> Self {
+        match mode {
+            BlendMode::Alpha => BlendState::ALPHA,
+            BlendMode::Add => BlendState::ADD,
+            BlendMode::Mul => BlendState::MULTIPLY,
+        }
+    }
+}
+
+/// Factor a source or destination value is multiplied with before the blend operation.
+///
+/// The `Src1` factors use the second output of the fragment shader and require the
+/// `dual_src_blending` device feature.
+#[allow(missing_docs)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BlendFactor {
+    Zero,
+    One,
+    SrcColor,
+    OneMinusSrcColor,
+    DstColor,
+    OneMinusDstColor,
+    SrcAlpha,
+    OneMinusSrcAlpha,
+    DstAlpha,
+    OneMinusDstAlpha,
+    ConstantColor,
+    OneMinusConstantColor,
+    ConstantAlpha,
+    OneMinusConstantAlpha,
+    SrcAlphaSaturate,
+    Src1Color,
+    OneMinusSrc1Color,
+    Src1Alpha,
+    OneMinusSrc1Alpha,
+}
+
+impl BlendFactor {
+    fn is_dual_source(self) -> bool {
+        match self {
+            BlendFactor::Src1Color
+            | BlendFactor::OneMinusSrc1Color
+            | BlendFactor::Src1Alpha
+            | BlendFactor::OneMinusSrc1Alpha => true,
+            _ => false,
+        }
+    }
+}
+
+impl From<BlendFactor> for gfx::pso::Factor {
+    fn from(factor: BlendFactor) -> Self {
+        use self::BlendFactor as B;
+        use gfx::pso::Factor as F;
+        match factor {
+            B::Zero => F::Zero,
+            B::One => F::One,
+            B::SrcColor => F::SrcColor,
+            B::OneMinusSrcColor => F::OneMinusSrcColor,
+            B::DstColor => F::DstColor,
+            B::OneMinusDstColor => F::OneMinusDstColor,
+            B::SrcAlpha => F::SrcAlpha,
+            B::OneMinusSrcAlpha => F::OneMinusSrcAlpha,
+            B::DstAlpha => F::DstAlpha,
+            B::OneMinusDstAlpha => F::OneMinusDstAlpha,
+            B::ConstantColor => F::ConstColor,
+            B::OneMinusConstantColor => F::OneMinusConstColor,
+            B::ConstantAlpha => F::ConstAlpha,
+            B::OneMinusConstantAlpha => F::OneMinusConstAlpha,
+            B::SrcAlphaSaturate => F::SrcAlphaSaturate,
+            B::Src1Color => F::Src1Color,
+            B::OneMinusSrc1Color => F::OneMinusSrc1Color,
+            B::Src1Alpha => F::Src1Alpha,
+            B::OneMinusSrc1Alpha => F::OneMinusSrc1Alpha,
+        }
+    }
+}
+
+/// Operation combining the (weighted) source and destination values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BlendOp {
+    /// `src * src_factor + dst * dst_factor`
+    #[allow(missing_docs)]
+    Add { src: BlendFactor, dst: BlendFactor },
+    /// `src * src_factor - dst * dst_factor`
+    #[allow(missing_docs)]
+    Subtract { src: BlendFactor, dst: BlendFactor },
+    /// `dst * dst_factor - src * src_factor`
+    #[allow(missing_docs)]
+    ReverseSubtract { src: BlendFactor, dst: BlendFactor },
+    /// Component-wise minimum of the source and destination values.
+    Min,
+    /// Component-wise maximum of the source and destination values.
+    Max,
+}
+
+impl BlendOp {
+    /// Operation which writes the source value unchanged.
+    pub const REPLACE: BlendOp = BlendOp::Add {
+        src: BlendFactor::One,
+        dst: BlendFactor::Zero,
+    };
+
+    fn factors(self) -> Option<(BlendFactor, BlendFactor)> {
+        match self {
+            BlendOp::Add { src, dst }
+            | BlendOp::Subtract { src, dst }
+            | BlendOp::ReverseSubtract { src, dst } => Some((src, dst)),
+            BlendOp::Min | BlendOp::Max => None,
+        }
+    }
+}
+
+impl From<BlendOp> for gfx::pso::BlendOp {
+    fn from(op: BlendOp) -> Self {
+        use gfx::pso::BlendOp as B;
+        match op {
+            BlendOp::Add { src, dst } => B::Add {
+                src: src.into(),
+                dst: dst.into(),
+            },
+            BlendOp::Subtract { src, dst } => B::Sub {
+                src: src.into(),
+                dst: dst.into(),
+            },
+            BlendOp::ReverseSubtract { src, dst } => B::RevSub {
+                src: src.into(),
+                dst: dst.into(),
+            },
+            BlendOp::Min => B::Min,
+            BlendOp::Max => B::Max,
+        }
+    }
+}
+
+bitflags!(
+
+    /// Components of a color attachment that are written.
+    pub struct ColorMask: u8 {
+        /// Red component.
+        const RED = 0x1;
+        /// Green component.
+        const GREEN = 0x2;
+        /// Blue component.
+        const BLUE = 0x4;
+        /// Alpha component.
+        const ALPHA = 0x8;
+        /// All components.
+        const ALL = 0xF;
+    }
+);
+
+impl From<ColorMask> for gfx::pso::ColorMask {
+    fn from(mask: ColorMask) -> Self {
+        use gfx::pso::ColorMask as C;
+
+        let mut flags = C::empty();
+
+        if mask.contains(ColorMask::RED) {
+            flags |= C::RED;
+        }
+        if mask.contains(ColorMask::GREEN) {
+            flags |= C::GREEN;
+        }
+        if mask.contains(ColorMask::BLUE) {
+            flags |= C::BLUE;
+        }
+        if mask.contains(ColorMask::ALPHA) {
+            flags |= C::ALPHA;
+        }
+
+        flags
+    }
+}
+
+/// Blend state of a single color attachment.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BlendState {
+    /// Operation used for the color components. `None` writes the source color unchanged.
+    pub color: Option<BlendOp>,
+    /// Operation used for the alpha component. `None` writes the source alpha unchanged.
+    pub alpha: Option<BlendOp>,
+    /// Components that are written to the attachment.
+    pub write_mask: ColorMask,
+}
+
+impl Default for BlendState {
+    fn default() -> Self {
+        BlendState::REPLACE
+    }
+}
+
+impl BlendState {
+    /// No blending, the fragment output replaces the attachment contents.
+    ///
+    /// This is the only state that can be used with integer formats.
+    pub const REPLACE: BlendState = BlendState {
+        color: None,
+        alpha: None,
+        write_mask: ColorMask::ALL,
+    };
+
+    /// "Normal" alpha blending with non-premultiplied source colors.
+    pub const ALPHA: BlendState = BlendState {
+        color: Some(BlendOp::Add {
+            src: BlendFactor::SrcAlpha,
+            dst: BlendFactor::OneMinusSrcAlpha,
+        }),
+        alpha: Some(BlendOp::Add {
+            src: BlendFactor::One,
+            dst: BlendFactor::OneMinusSrcAlpha,
+        }),
+        write_mask: ColorMask::ALL,
+    };
+
+    /// Alpha blending with source colors already multiplied by their alpha.
+    pub const PREMULTIPLIED_ALPHA: BlendState = BlendState {
+        color: Some(BlendOp::Add {
+            src: BlendFactor::One,
+            dst: BlendFactor::OneMinusSrcAlpha,
+        }),
+        alpha: Some(BlendOp::Add {
+            src: BlendFactor::One,
+            dst: BlendFactor::OneMinusSrcAlpha,
+        }),
+        write_mask: ColorMask::ALL,
+    };
+
+    /// Additive blending, the source is added to the destination.
+    pub const ADD: BlendState = BlendState {
+        color: Some(BlendOp::Add {
+            src: BlendFactor::One,
+            dst: BlendFactor::One,
+        }),
+        alpha: Some(BlendOp::Add {
+            src: BlendFactor::One,
+            dst: BlendFactor::One,
+        }),
+        write_mask: ColorMask::ALL,
+    };
+
+    /// Subtractive blending, the source is subtracted from the destination.
+    pub const SUBTRACT: BlendState = BlendState {
+        color: Some(BlendOp::ReverseSubtract {
+            src: BlendFactor::One,
+            dst: BlendFactor::One,
+        }),
+        alpha: Some(BlendOp::ReverseSubtract {
+            src: BlendFactor::One,
+            dst: BlendFactor::One,
+        }),
+        write_mask: ColorMask::ALL,
+    };
+
+    /// Multiplicative blending, the destination is multiplied with the source.
+    pub const MULTIPLY: BlendState = BlendState {
+        color: Some(BlendOp::Add {
+            src: BlendFactor::Zero,
+            dst: BlendFactor::SrcColor,
+        }),
+        alpha: Some(BlendOp::Add {
+            src: BlendFactor::Zero,
+            dst: BlendFactor::SrcAlpha,
+        }),
+        write_mask: ColorMask::ALL,
+    };
+
+    /// The component-wise minimum of source and destination is written.
+    pub const MIN: BlendState = BlendState {
+        color: Some(BlendOp::Min),
+        alpha: Some(BlendOp::Min),
+        write_mask: ColorMask::ALL,
+    };
+
+    /// The component-wise maximum of source and destination is written.
+    pub const MAX: BlendState = BlendState {
+        color: Some(BlendOp::Max),
+        alpha: Some(BlendOp::Max),
+        write_mask: ColorMask::ALL,
+    };
+
+    /// Determine if the state uses the second fragment shader output.
+    pub(crate) fn is_dual_source(&self) -> bool {
+        self.color
+            .iter()
+            .chain(self.alpha.iter())
+            .filter_map(|op| op.factors())
+            .any(|(src, dst)| src.is_dual_source() || dst.is_dual_source())
+    }
+}
+
+impl From<BlendState> for gfx::pso::ColorBlendDesc {
+    fn from(state: BlendState) -> Self {
+        let blend = if state.color.is_none() && state.alpha.is_none() {
+            gfx::pso::BlendState::Off
+        } else {
+            gfx::pso::BlendState::On {
+                color: state.color.unwrap_or(BlendOp::REPLACE).into(),
+                alpha: state.alpha.unwrap_or(BlendOp::REPLACE).into(),
+            }
+        };
+
+        gfx::pso::ColorBlendDesc(state.write_mask.into(), blend)
+    }
+}
+
+/// Constant color used by the `Constant*` blend factors.
+#[derive(Clone, Copy, Debug)]
+pub enum BlendConstants {
+    /// The constants are part of the pipeline.
+    Static([f32; 4]),
+    /// The constants are set while recording commands using
+    /// `GraphicsCommandBuffer::set_blend_constants`.
+    Dynamic,
+}
+
+impl Default for BlendConstants {
+    fn default() -> Self {
+        BlendConstants::Static([0.0; 4])
+    }
+}
+
+impl BlendConstants {
+    fn key(&self) -> Option<[u32; 4]> {
+        match self {
+            BlendConstants::Static([r, g, b, a]) => {
+                Some([r.to_bits(), g.to_bits(), b.to_bits(), a.to_bits()])
+            }
+            BlendConstants::Dynamic => None,
+        }
+    }
+}
+
+impl PartialEq for BlendConstants {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for BlendConstants {}
+
+impl Hash for BlendConstants {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state);
+    }
+}
+
+/// Bitwise operations applied to the fragment output and the attachment contents.
+///
+/// Logic operations replace blending for all attachments and require the `logic_op` device
+/// feature.
+#[allow(missing_docs)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LogicOp {
+    Clear,
+    And,
+    AndReverse,
+    Copy,
+    AndInverted,
+    NoOp,
+    Xor,
+    Or,
+    Nor,
+    Equivalent,
+    Invert,
+    OrReverse,
+    CopyInverted,
+    OrInverted,
+    Nand,
+    Set,
+}
+
+impl From<LogicOp> for gfx::pso::LogicOp {
+    fn from(op: LogicOp) -> Self {
+        use gfx::pso::LogicOp as L;
+        match op {
+            LogicOp::Clear => L::Clear,
+            LogicOp::And => L::And,
+            LogicOp::AndReverse => L::AndReverse,
+            LogicOp::Copy => L::Copy,
+            LogicOp::AndInverted => L::AndInverted,
+            LogicOp::NoOp => L::NoOp,
+            LogicOp::Xor => L::Xor,
+            LogicOp::Or => L::Or,
+            LogicOp::Nor => L::Nor,
+            LogicOp::Equivalent => L::Equivalent,
+            LogicOp::Invert => L::Invert,
+            LogicOp::OrReverse => L::OrReverse,
+            LogicOp::CopyInverted => L::CopyInverted,
+            LogicOp::OrInverted => L::OrInverted,
+            LogicOp::Nand => L::Nand,
+            LogicOp::Set => L::Set,
+        }
+    }
+}
+
+/// Blend description for all color attachments of a pass.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct BlendDesc {
+    /// Blend states of the color attachments, in the order the attachments are declared.
+    ///
+    /// Different states for different attachments require the `independent_blending` device
+    /// feature.
+    pub targets: Vec<BlendState>,
+    /// Constant color used by the `Constant*` blend factors.
+    pub constants: BlendConstants,
+    /// Optional logic operation, which replaces blending if present.
+    pub logic_op: Option<LogicOp>,
+}
+
+impl BlendDesc {
+    /// Create a blend description with static zero constants and no logic operation.
+    pub fn new<I>(targets: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<BlendState>,
+    {
+        BlendDesc {
+            targets: targets.into_iter().map(Into::into).collect(),
+            constants: BlendConstants::default(),
+            logic_op: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_convert_to_states() {
+        let desc = BlendDesc::new(vec![BlendMode::Alpha, BlendMode::Add]);
+
+        assert_eq!(desc.targets, vec![BlendState::ALPHA, BlendState::ADD]);
+        assert_eq!(desc.constants, BlendConstants::Static([0.0; 4]));
+        assert_ne!(BlendConstants::Static([0.0; 4]), BlendConstants::Dynamic);
+    }
+
+    #[test]
+    fn dual_source_factors_are_detected() {
+        let state = BlendState {
+            alpha: Some(BlendOp::Add {
+                src: BlendFactor::One,
+                dst: BlendFactor::OneMinusSrc1Alpha,
+            }),
+            ..BlendState::REPLACE
+        };
+
+        assert!(state.is_dual_source());
+        assert!(!BlendState::ALPHA.is_dual_source());
+        assert!(!BlendState::MAX.is_dual_source());
+    }
+}
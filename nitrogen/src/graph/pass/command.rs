@@ -174,6 +174,21 @@ impl<'a> GraphicsCommandBuffer<'a> {
     pub unsafe fn reset_scissor(&mut self) {
         self.encoder.set_scissors(0, &[self.viewport_rect]);
     }
+
+    /// Set the constant color used by the `Constant*` blend factors.
+    ///
+    /// This only has an effect if the pipeline uses `BlendConstants::Dynamic`.
+    pub unsafe fn set_blend_constants(&mut self, constants: [f32; 4]) {
+        self.encoder.set_blend_constants(constants);
+    }
+
+    /// Set the stencil reference value for front- and back-facing primitives.
+    ///
+    /// This only has an effect if the pipeline uses `StencilReference::Dynamic`.
+    pub unsafe fn set_stencil_reference(&mut self, reference: u32) {
+        self.encoder
+            .set_stencil_reference(gfx::pso::Face::all(), reference);
+    }
 }
 
 /// A command buffer used in compute passes.
@@ -10,7 +10,8 @@ use crate::device::DeviceContext;
 use crate::graph::builder::resource_descriptor::ResourceType;
 use crate::graph::builder::{ComputePassContext, GraphicsPassContext};
 use crate::graph::execution::{
-    create_pipeline_compute, GraphResources, PassResources, PipelineResources,
+    create_pipeline_compute, pass_resource_state, GraphResources, PassResources, PipelineResources,
+    ResourceAccess, ResourceState,
 };
 use crate::graph::pass::command::{ComputeCommandBuffer, ReadStorages};
 use crate::graph::pass::{ComputePass, PassId};
@@ -78,7 +79,7 @@ pub enum ResourceRefError {
 
 /// A reference handle to an image resource with write access.
 #[derive(Clone, Copy, Debug)]
-pub struct ImageWriteRef(pub(crate) ImageHandle);
+pub struct ImageWriteRef(pub(crate) ImageHandle, pub(crate) ResourceState);
 /// A reference handle to an image resource with read access.
 #[derive(Clone, Copy, Debug)]
 pub struct ImageReadRef(pub(crate) ImageHandle);
@@ -188,7 +189,10 @@ mod compute {
                 .get(&id)
                 .expect("GraphResources should be compatible");
 
-            Ok(ImageWriteRef(*handle))
+            let state = pass_resource_state(&self.compiled.graph_resources, self.pass_id, id)
+                .expect("Written resources have a state");
+
+            Ok(ImageWriteRef(*handle, state))
         }
 
         /// Retrieve a read-reference to an image resource.
@@ -263,9 +267,14 @@ mod compute {
 
             let mut pipeline_desc_cache = pass_impl.pipeline_infos.borrow_mut();
 
+            // configurations that weren't seen before are reported if they need a new pipeline.
+            let mut lazy_config = None;
+
             let desc = if let Some(desc) = pipeline_desc_cache.get(&config) {
                 desc
             } else {
+                lazy_config = Some(config.clone());
+
                 let desc = pass_impl.pass.configure(&config);
                 pipeline_desc_cache.entry(config).or_insert(desc)
             };
@@ -292,8 +301,13 @@ mod compute {
                     desc.clone(),
                     PipelineResources {
                         pipeline_handle: pipe,
+                        shader_generation: self.storages.shader.borrow().generation,
                     },
                 );
+
+                if let Some(config) = lazy_config {
+                    pass_impl.lazy_configs.borrow_mut().push(config);
+                }
             }
 
             let pipeline_storage = self.storages.pipeline.borrow();
@@ -446,7 +460,10 @@ mod graphics {
                 .get(&id)
                 .expect("GraphResources should be compatible");
 
-            Ok(ImageWriteRef(*handle))
+            let state = pass_resource_state(&self.compiled.graph_resources, self.pass_id, id)
+                .expect("Written resources have a state");
+
+            Ok(ImageWriteRef(*handle, state))
         }
 
         /// Retrieve a read-reference to an image resource.
@@ -515,23 +532,28 @@ mod graphics {
 
             let img = image_storage.raw(image.0)?;
 
+            // The image is in the state the pass needs, so it has to be put back into that state
+            // after clearing.
+            let state = image.1;
+            let pass_state = match state.access {
+                ResourceAccess::Image(access, layout) => (access, layout),
+                ResourceAccess::Buffer(_) => return None,
+            };
+
+            // The contents are cleared anyway, so there is no need to preserve them.
             let entry_barrier = gfx::memory::Barrier::Image {
-                states: (gfx::image::Access::empty(), gfx::image::Layout::Undefined)
+                states: (pass_state.0, gfx::image::Layout::Undefined)
                     ..(
                         gfx::image::Access::TRANSFER_WRITE,
                         gfx::image::Layout::TransferDstOptimal,
                     ),
                 target: img.image.raw(),
                 families: None,
-                range: gfx::image::SubresourceRange {
-                    aspects: img.aspect,
-                    levels: 0..1,
-                    layers: 0..1,
-                },
+                range: img.subresource_range(),
             };
 
             self.cmd.pipeline_barrier(
-                gfx::pso::PipelineStage::TOP_OF_PIPE..gfx::pso::PipelineStage::TRANSFER,
+                state.stages..gfx::pso::PipelineStage::TRANSFER,
                 gfx::memory::Dependencies::empty(),
                 &[entry_barrier],
             );
@@ -549,30 +571,21 @@ mod graphics {
                     }
                     _ => gfx::command::ClearDepthStencil(1.0, 0),
                 },
-                &[gfx::image::SubresourceRange {
-                    aspects: img.aspect,
-                    levels: 0..1,
-                    layers: 0..1,
-                }],
+                &[img.subresource_range()],
             );
 
             let exit_barrier = gfx::memory::Barrier::Image {
                 states: (
                     gfx::image::Access::TRANSFER_WRITE,
                     gfx::image::Layout::TransferDstOptimal,
-                )
-                    ..(gfx::image::Access::empty(), gfx::image::Layout::General),
+                )..pass_state,
                 target: img.image.raw(),
                 families: None,
-                range: gfx::image::SubresourceRange {
-                    aspects: img.aspect,
-                    levels: 0..1,
-                    layers: 0..1,
-                },
+                range: img.subresource_range(),
             };
 
             self.cmd.pipeline_barrier(
-                gfx::pso::PipelineStage::TRANSFER..gfx::pso::PipelineStage::BOTTOM_OF_PIPE,
+                gfx::pso::PipelineStage::TRANSFER..state.stages,
                 gfx::memory::Dependencies::empty(),
                 &[exit_barrier],
             );
@@ -601,9 +614,14 @@ mod graphics {
 
             let mut pipeline_desc_cache = pass_impl.pipeline_infos.borrow_mut();
 
+            // configurations that weren't seen before are reported if they need a new pipeline.
+            let mut lazy_config = None;
+
             let desc = if let Some(desc) = pipeline_desc_cache.get(&config) {
                 desc
             } else {
+                lazy_config = Some(config.clone());
+
                 let desc = pass_impl.pass.configure(&config);
                 pipeline_desc_cache.entry(config).or_insert(desc)
             };
@@ -619,6 +637,7 @@ mod graphics {
                 let pipe = create_pipeline_graphics(
                     self.device,
                     self.storages,
+                    &self.compiled.graph_resources,
                     self.pass_id,
                     pass_mat,
                     &desc,
@@ -629,8 +648,13 @@ mod graphics {
                     desc.clone(),
                     PipelineResources {
                         pipeline_handle: pipe,
+                        shader_generation: self.storages.shader.borrow().generation,
                     },
                 );
+
+                if let Some(config) = lazy_config {
+                    pass_impl.lazy_configs.borrow_mut().push(config);
+                }
             }
 
             let pipeline_storage = self.storages.pipeline.borrow();
@@ -4,6 +4,9 @@
 
 //! Functionalities for describing and implementing passes.
 
+pub mod blend;
+pub use self::blend::*;
+
 pub mod command;
 pub use self::command::*;
 
@@ -16,11 +19,12 @@ use crate::material::MaterialHandle;
 use crate::vertex_attrib::VertexAttrib;
 
 use crate::resources::shader::{
-    ComputeShaderHandle, FragmentShaderHandle, GeometryShaderHandle, VertexShaderHandle,
+    ComputeShaderHandle, FragmentShaderHandle, GeometryShaderHandle, TessControlShaderHandle,
+    TessEvalShaderHandle, VertexShaderHandle,
 };
 use smallvec::SmallVec;
 
-use std::hash::Hash;
+use std::hash::{Hash, Hasher};
 
 /// Numerical identifier for a pass.
 #[derive(Copy, Clone, Hash, Eq, PartialEq, Ord, PartialOrd, Debug)]
@@ -28,7 +32,6 @@ pub struct PassId(pub(crate) usize);
 
 /// Primitive mode used for rasterization.
 #[allow(missing_docs)]
-#[repr(u8)]
 #[derive(Ord, PartialOrd, Eq, PartialEq, Copy, Clone, Hash, Debug)]
 pub enum Primitive {
     PointList,
@@ -36,6 +39,11 @@ pub enum Primitive {
     LineStrip,
     TriangleList,
     TriangleStrip,
+    /// Patches with the given number of control points.
+    ///
+    /// The number of control points can't exceed the `max_patch_size` limit of the device.
+    /// Patches can only be used with tessellation shaders, which in turn require this primitive.
+    PatchList(u8),
 }
 
 impl Default for Primitive {
@@ -52,19 +60,115 @@ impl From<Primitive> for gfx::Primitive {
             Primitive::LineStrip => gfx::Primitive::LineStrip,
             Primitive::TriangleList => gfx::Primitive::TriangleList,
             Primitive::TriangleStrip => gfx::Primitive::TriangleStrip,
+            Primitive::PatchList(points) => gfx::Primitive::PatchList(points),
         }
     }
 }
 
-// TODO add more modes
+/// Faces of primitives which are discarded during rasterization.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub enum CullMode {
+    None,
+    Front,
+    Back,
+    FrontAndBack,
+}
 
-/// Blend mode used for color attachments.
+impl From<CullMode> for gfx::pso::Face {
+    fn from(mode: CullMode) -> Self {
+        use gfx::pso::Face;
+        match mode {
+            CullMode::None => Face::empty(),
+            CullMode::Front => Face::FRONT,
+            CullMode::Back => Face::BACK,
+            CullMode::FrontAndBack => Face::all(),
+        }
+    }
+}
+
+/// Winding order of front-facing primitives.
 #[allow(missing_docs)]
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
-pub enum BlendMode {
-    Alpha,
-    Add,
-    Mul,
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub enum FrontFace {
+    Clockwise,
+    CounterClockwise,
+}
+
+impl From<FrontFace> for gfx::pso::FrontFace {
+    fn from(face: FrontFace) -> Self {
+        match face {
+            FrontFace::Clockwise => gfx::pso::FrontFace::Clockwise,
+            FrontFace::CounterClockwise => gfx::pso::FrontFace::CounterClockwise,
+        }
+    }
+}
+
+/// Mode in which polygons are rasterized.
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub enum PolygonMode {
+    /// The whole area of a polygon is rasterized.
+    Fill,
+    /// Only the edges of a polygon are rasterized.
+    Line,
+    /// Only the vertices of a polygon are rasterized.
+    Point,
+}
+
+/// Rasterization state used in graphics pipelines.
+#[derive(Debug, Clone, Copy)]
+pub struct Rasterizer {
+    /// Faces which are discarded.
+    pub cull_mode: CullMode,
+    /// Winding order which determines which faces are front-facing.
+    pub front_face: FrontFace,
+    /// Mode in which polygons are rasterized. Modes other than `PolygonMode::Fill` require the
+    /// `non_fill_polygon_mode` device feature.
+    pub polygon_mode: PolygonMode,
+    /// Clamp depth values instead of clipping primitives at the near and far planes.
+    /// This requires the `depth_clamp` device feature.
+    pub depth_clamping: bool,
+    /// Width of rasterized lines when using `PolygonMode::Line`. Widths other than `1.0` require
+    /// the `line_width` device feature.
+    pub line_width: f32,
+}
+
+impl Default for Rasterizer {
+    fn default() -> Self {
+        Rasterizer {
+            cull_mode: CullMode::None,
+            front_face: FrontFace::CounterClockwise,
+            polygon_mode: PolygonMode::Fill,
+            depth_clamping: false,
+            line_width: 1.0,
+        }
+    }
+}
+
+impl Rasterizer {
+    fn key(&self) -> (CullMode, FrontFace, PolygonMode, bool, u32) {
+        (
+            self.cull_mode,
+            self.front_face,
+            self.polygon_mode,
+            self.depth_clamping,
+            self.line_width.to_bits(),
+        )
+    }
+}
+
+impl PartialEq for Rasterizer {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for Rasterizer {}
+
+impl Hash for Rasterizer {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state);
+    }
 }
 
 /// Depth-test mode description.
@@ -74,6 +178,176 @@ pub struct DepthMode {
     pub func: Comparison,
     /// Flag that determines whether depth values are written back or only used for reading/testing.
     pub write: bool,
+    /// Optional bias added to the depth values of rasterized primitives.
+    pub bias: Option<DepthBias>,
+    /// Optional range the stored depth value has to lie in for a fragment to pass.
+    ///
+    /// This requires the `depth_bounds` device feature.
+    pub bounds: Option<DepthBounds>,
+}
+
+/// Bias applied to the depth values of rasterized primitives.
+///
+/// This is useful to avoid "z-fighting" of coplanar geometry, for example decals or shadow maps.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DepthBias {
+    /// Constant value added to every depth value.
+    pub constant_factor: f32,
+    /// Factor applied to the slope of the primitive.
+    pub slope_factor: f32,
+    /// Maximum (or minimum if negative) bias. Zero disables clamping, other values require the
+    /// `depth_bias_clamp` device feature.
+    pub clamp: f32,
+}
+
+impl DepthBias {
+    fn bits(&self) -> [u32; 3] {
+        [
+            self.constant_factor.to_bits(),
+            self.slope_factor.to_bits(),
+            self.clamp.to_bits(),
+        ]
+    }
+}
+
+impl PartialEq for DepthBias {
+    fn eq(&self, other: &Self) -> bool {
+        self.bits() == other.bits()
+    }
+}
+
+impl Eq for DepthBias {}
+
+impl Hash for DepthBias {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.bits().hash(state);
+    }
+}
+
+impl From<DepthBias> for gfx::pso::DepthBias {
+    fn from(bias: DepthBias) -> Self {
+        gfx::pso::DepthBias {
+            const_factor: bias.constant_factor,
+            slope_factor: bias.slope_factor,
+            clamp: bias.clamp,
+        }
+    }
+}
+
+/// Range of depth values used for depth-bounds testing.
+#[derive(Debug, Clone, Copy)]
+pub struct DepthBounds {
+    /// Smallest depth value that passes the test.
+    pub min: f32,
+    /// Biggest depth value that passes the test.
+    pub max: f32,
+}
+
+impl PartialEq for DepthBounds {
+    fn eq(&self, other: &Self) -> bool {
+        self.min.to_bits() == other.min.to_bits() && self.max.to_bits() == other.max.to_bits()
+    }
+}
+
+impl Eq for DepthBounds {}
+
+impl Hash for DepthBounds {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.min.to_bits().hash(state);
+        self.max.to_bits().hash(state);
+    }
+}
+
+/// Stencil-test mode description.
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub struct StencilMode {
+    /// Test and operations used for front-facing primitives.
+    pub front: StencilFace,
+    /// Test and operations used for back-facing primitives.
+    pub back: StencilFace,
+    /// Bits of the reference and stored values which are used in the comparison.
+    pub compare_mask: u32,
+    /// Bits of the stored value which can be written by the stencil operations.
+    pub write_mask: u32,
+    /// Reference value used for comparing and for `StencilOp::Replace`.
+    pub reference: StencilReference,
+}
+
+impl Default for StencilMode {
+    fn default() -> Self {
+        StencilMode {
+            front: StencilFace::default(),
+            back: StencilFace::default(),
+            compare_mask: !0,
+            write_mask: !0,
+            reference: StencilReference::Static(0),
+        }
+    }
+}
+
+/// Stencil test and operations for primitives of one facing.
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub struct StencilFace {
+    /// Function used to compare the reference value against the stored value.
+    pub func: Comparison,
+    /// Operation performed when the stencil test fails.
+    pub fail: StencilOp,
+    /// Operation performed when the stencil test passes but the depth test fails.
+    pub depth_fail: StencilOp,
+    /// Operation performed when both the stencil and the depth test pass.
+    pub pass: StencilOp,
+}
+
+impl Default for StencilFace {
+    fn default() -> Self {
+        StencilFace {
+            func: Comparison::Always,
+            fail: StencilOp::Keep,
+            depth_fail: StencilOp::Keep,
+            pass: StencilOp::Keep,
+        }
+    }
+}
+
+/// Reference value used in stencil tests.
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub enum StencilReference {
+    /// The reference value is part of the pipeline.
+    Static(u32),
+    /// The reference value is set while recording commands using
+    /// `GraphicsCommandBuffer::set_stencil_reference`.
+    Dynamic,
+}
+
+/// Operations performed on the stored stencil value.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub enum StencilOp {
+    Keep,
+    Zero,
+    Replace,
+    IncrementClamp,
+    IncrementWrap,
+    DecrementClamp,
+    DecrementWrap,
+    Invert,
+}
+
+impl From<StencilOp> for gfx::pso::StencilOp {
+    fn from(op: StencilOp) -> Self {
+        use self::StencilOp as S;
+        use gfx::pso::StencilOp as GS;
+        match op {
+            S::Keep => GS::Keep,
+            S::Zero => GS::Zero,
+            S::Replace => GS::Replace,
+            S::IncrementClamp => GS::IncrementClamp,
+            S::IncrementWrap => GS::IncrementWrap,
+            S::DecrementClamp => GS::DecrementClamp,
+            S::DecrementWrap => GS::DecrementWrap,
+            S::Invert => GS::Invert,
+        }
+    }
 }
 
 /// Comparison modes used for depth and stencil tests.
@@ -116,6 +390,13 @@ pub struct GraphicShaders {
     pub fragment: Option<Shader<FragmentShaderHandle>>,
     /// Optional geometry-shader description.
     pub geometry: Option<Shader<GeometryShaderHandle>>,
+    /// Optional tessellation-control-shader description.
+    ///
+    /// Both tessellation shaders have to be used together with the `PatchList` primitive. This
+    /// requires the `tessellation_shader` device feature.
+    pub tess_control: Option<Shader<TessControlShaderHandle>>,
+    /// Optional tessellation-evaluation-shader description.
+    pub tess_eval: Option<Shader<TessEvalShaderHandle>>,
 }
 
 /// Description of a graphics pass pipeline.
@@ -126,14 +407,15 @@ pub struct GraphicsPipelineInfo {
     /// Depth mode used for a possible depth attachment.
     pub depth_mode: Option<DepthMode>,
     /// Stencil mode used for a possible stencil attachment.
-    /// TODO
-    pub stencil_mode: Option<()>,
+    pub stencil_mode: Option<StencilMode>,
     /// Set of shader programs.
     pub shaders: GraphicShaders,
     /// Primitive mode used for rasterization.
     pub primitive: Primitive,
-    /// Blend modes used for the color attachments.
-    pub blend_modes: Vec<BlendMode>,
+    /// Rasterization state.
+    pub rasterizer: Rasterizer,
+    /// Blend description used for the color attachments.
+    pub blend: BlendDesc,
     /// Materials used in the pass with their associated set-bindings.
     pub materials: Vec<(usize, MaterialHandle)>,
     /// Range of push constants used.
@@ -145,12 +427,22 @@ pub trait GraphicsPass: Sized {
     /// Configuration type of the pass.
     ///
     /// The configuration is used to dispatch work on potentially different pipelines.
-    type Config: Hash + Eq;
+    /// Configurations whose pipelines had to be created during execution are cloned, so they
+    /// can be retrieved with `Context::graph_lazy_configs`.
+    type Config: Hash + Eq + Clone;
 
     /// The `prepare` function is called before every execution and can be used to change
     /// pass-internal state.
     fn prepare(&mut self, _store: &mut super::Store) {}
 
+    /// Configurations which are expected to be used during execution.
+    ///
+    /// Pipelines for these configurations are created together with the graph instead of the
+    /// first time they are used in `with_config`, which avoids hitches during execution.
+    fn configs(&self) -> Vec<Self::Config> {
+        vec![]
+    }
+
     /// Create a graphics-pipeline info from a given configuration.
     fn configure(&self, config: &Self::Config) -> GraphicsPipelineInfo;
 
@@ -226,12 +518,33 @@ pub trait ComputePass: Sized {
     /// Configuration type of the pass.
     ///
     /// The configuration is used to dispatch work on potentially different pipelines.
-    type Config: Hash + Eq;
+    /// Configurations whose pipelines had to be created during execution are cloned, so they
+    /// can be retrieved with `Context::graph_lazy_configs`.
+    type Config: Hash + Eq + Clone;
 
     /// The `prepare` function is called before every execution and can be used to change
     /// pass-internal state.
     fn prepare(&mut self, _store: &mut super::Store) {}
 
+    /// Hint that the pass should be submitted to the async compute queue.
+    ///
+    /// Async compute passes can run at the same time as graphics work. Otherwise the pass is
+    /// submitted to the graphics queue. The hint is ignored for passes that use resources
+    /// which are accessed outside of the graph, like targets or backbuffer images.
+    ///
+    /// The hint is queried once when the pass is added to a `GraphBuilder`.
+    fn async_compute(&self) -> bool {
+        false
+    }
+
+    /// Configurations which are expected to be used during execution.
+    ///
+    /// Pipelines for these configurations are created together with the graph instead of the
+    /// first time they are used in `with_config`, which avoids hitches during execution.
+    fn configs(&self) -> Vec<Self::Config> {
+        vec![]
+    }
+
     /// Create a compute-pipeline info from a given configuration.
     fn configure(&self, config: &Self::Config) -> ComputePipelineInfo;
 
@@ -250,3 +563,46 @@ pub trait ComputePass: Sized {
         dispatcher: &mut ComputeDispatcher<Self>,
     ) -> Result<(), GraphExecError>;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::hash_map::DefaultHasher;
+
+    fn hash<T: Hash>(value: &T) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn rasterizers_hash_by_value() {
+        let wireframe = Rasterizer {
+            polygon_mode: PolygonMode::Line,
+            line_width: 2.0,
+            ..Rasterizer::default()
+        };
+
+        let same = Rasterizer {
+            polygon_mode: PolygonMode::Line,
+            line_width: 2.0,
+            ..Rasterizer::default()
+        };
+        assert_eq!(wireframe, same);
+        assert_eq!(hash(&wireframe), hash(&same));
+
+        let wider = Rasterizer {
+            line_width: 3.0,
+            ..wireframe
+        };
+        assert_ne!(wireframe, wider);
+        assert_ne!(hash(&wireframe), hash(&wider));
+
+        let culled = Rasterizer {
+            cull_mode: CullMode::Back,
+            ..Rasterizer::default()
+        };
+        assert_ne!(culled, Rasterizer::default());
+    }
+}
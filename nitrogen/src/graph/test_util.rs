@@ -0,0 +1,101 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Fixtures shared by the graph tests.
+
+use crate::graph::{
+    BufferCreateInfo, BufferStorageType, ComputeDispatcher, ComputePass, ComputePipelineInfo,
+    GraphExecError, GraphicsDispatcher, GraphicsPass, GraphicsPipelineInfo, ImageCreateInfo,
+    ResourceDescriptor, Store,
+};
+use crate::image::{ImageFormat, ImageSizeMode, ViewKind};
+
+/// Pass that only describes resources, it is never executed.
+pub(crate) struct DescribePass<F> {
+    describe: F,
+    async_compute: bool,
+}
+
+impl<F> DescribePass<F> {
+    pub(crate) fn new(describe: F) -> Self {
+        DescribePass {
+            describe,
+            async_compute: false,
+        }
+    }
+
+    /// Compute pass that prefers the async compute queue.
+    pub(crate) fn new_async(describe: F) -> Self {
+        DescribePass {
+            describe,
+            async_compute: true,
+        }
+    }
+}
+
+impl<F: FnMut(&mut ResourceDescriptor)> ComputePass for DescribePass<F> {
+    type Config = ();
+
+    fn async_compute(&self) -> bool {
+        self.async_compute
+    }
+
+    fn configure(&self, _config: &Self::Config) -> ComputePipelineInfo {
+        unimplemented!()
+    }
+
+    fn describe(&mut self, res: &mut ResourceDescriptor) {
+        (self.describe)(res)
+    }
+
+    unsafe fn execute(
+        &self,
+        _store: &Store,
+        _dispatcher: &mut ComputeDispatcher<Self>,
+    ) -> Result<(), GraphExecError> {
+        unimplemented!()
+    }
+}
+
+impl<F: FnMut(&mut ResourceDescriptor)> GraphicsPass for DescribePass<F> {
+    type Config = ();
+
+    fn configure(&self, _config: &Self::Config) -> GraphicsPipelineInfo {
+        unimplemented!()
+    }
+
+    fn describe(&mut self, res: &mut ResourceDescriptor) {
+        (self.describe)(res)
+    }
+
+    unsafe fn execute(
+        &self,
+        _store: &Store,
+        _dispatcher: &mut GraphicsDispatcher<Self>,
+    ) -> Result<(), GraphExecError> {
+        unimplemented!()
+    }
+}
+
+/// 16x16 color image.
+pub(crate) fn image_info() -> ImageCreateInfo {
+    ImageCreateInfo {
+        format: ImageFormat::RgbaUnorm,
+        size_mode: ImageSizeMode::Absolute {
+            width: 16,
+            height: 16,
+        },
+        samples: 1,
+        layers: 1,
+        kind: ViewKind::D2,
+    }
+}
+
+/// 16 byte device local buffer.
+pub(crate) fn buffer_info() -> BufferCreateInfo {
+    BufferCreateInfo {
+        size: 16,
+        storage: BufferStorageType::DeviceLocal,
+    }
+}
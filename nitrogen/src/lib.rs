@@ -77,6 +77,8 @@ use crate::storage::{Handle, Storage};
 pub mod resources;
 pub use crate::resources::buffer;
 pub use crate::resources::image;
+#[cfg(feature = "texture_loader")]
+pub use crate::resources::image_loader;
 pub use crate::resources::material;
 pub(crate) use crate::resources::pipeline;
 pub(crate) use crate::resources::render_pass;
@@ -138,6 +140,21 @@ impl Context {
     /// The `name` and `version` fields are passed down to the graphics driver. They don't have any
     /// special meaning attached to them (as far as I know)
     pub unsafe fn new(name: &str, version: u32) -> Self {
+        Self::create(name, version, None)
+    }
+
+    /// Create a new `Context` instance with a pipeline cache filled with data previously
+    /// retrieved by [`pipeline_cache_save`].
+    ///
+    /// Data that was saved on a different adapter or driver is discarded, so it's always safe to
+    /// pass in data of an earlier run.
+    ///
+    /// [`pipeline_cache_save`]: #method.pipeline_cache_save
+    pub unsafe fn new_with_pipeline_cache(name: &str, version: u32, cache_data: &[u8]) -> Self {
+        Self::create(name, version, Some(cache_data))
+    }
+
+    unsafe fn create(name: &str, version: u32, cache_data: Option<&[u8]>) -> Self {
         use gfx::adapter::PhysicalDevice;
 
         let instance = back::Instance::create(name, version);
@@ -152,7 +169,7 @@ impl Context {
         let image_storage = image::ImageStorage::new();
         let sampler_storage = sampler::SamplerStorage::new();
         let buffer_storage = buffer::BufferStorage::new(memory_atom_size);
-        let pipeline_storage = pipeline::PipelineStorage::new();
+        let pipeline_storage = pipeline::PipelineStorage::new(&device_ctx, cache_data);
         let render_pass_storage = render_pass::RenderPassStorage::new();
         let material_storage = material::MaterialStorage::new();
         let shader_storage = shader::ShaderStorage::new();
@@ -240,6 +257,7 @@ impl Context {
         self.sampler_storage.into_inner().release(&self.device_ctx);
 
         self.material_storage.into_inner().release(&self.device_ctx);
+        self.pipeline_storage.into_inner().release(&self.device_ctx);
 
         for (_, display) in self.displays {
             display.release(&self.device_ctx);
@@ -248,6 +266,20 @@ impl Context {
         Arc::try_unwrap(self.device_ctx).ok().unwrap().release();
     }
 
+    /// Retrieve the contents of the pipeline cache used for all pipeline creations.
+    ///
+    /// The data can be stored on disk and used with [`new_with_pipeline_cache`] to speed up
+    /// pipeline creation in later runs. If the data can't be retrieved an empty buffer is
+    /// returned, which is also valid to pass to [`new_with_pipeline_cache`].
+    ///
+    /// [`new_with_pipeline_cache`]: #method.new_with_pipeline_cache
+    pub unsafe fn pipeline_cache_save(&self) -> Vec<u8> {
+        self.pipeline_storage
+            .borrow()
+            .cache_data(&self.device_ctx)
+            .unwrap_or_default()
+    }
+
     // image
 
     /// Create image objects and retrieve handles for them.
@@ -384,16 +416,79 @@ impl Context {
             .create(&self.device_ctx, &mut storages, builder)
     }
 
+    /// Render a graph in the [DOT] format, showing passes, resources and execution batches.
+    ///
+    /// Returns `None` if the handle is not valid.
+    ///
+    /// [DOT]: https://www.graphviz.org/doc/info/lang.html
+    pub fn graph_export_dot(&self, graph: graph::GraphHandle) -> Option<String> {
+        self.graph_storage.borrow().export_dot(graph)
+    }
+
+    /// Retrieve the configurations of the pass named `pass` whose pipelines were created during
+    /// execution instead of at graph creation.
+    ///
+    /// Returning them from the pass's `configs` lets the pipelines be created together with the
+    /// graph instead.
+    ///
+    /// Returns `None` if the handle is not valid, the graph has no pass named `pass` or `C` is
+    /// not the `Config` type of the pass.
+    pub fn graph_lazy_configs<C: 'static>(
+        &self,
+        graph: graph::GraphHandle,
+        pass: &str,
+    ) -> Option<Vec<C>> {
+        self.graph_storage.borrow().lazy_configs(graph, pass)
+    }
+
     // shader
 
     /// Create a compute shader and retrieve the handle.
+    ///
+    /// The SPIR-V code is checked to be a valid module containing the entry point as a
+    /// compute shader.
     pub fn compute_shader_create(
         &mut self,
         info: shader::ShaderInfo,
-    ) -> shader::ComputeShaderHandle {
+    ) -> Result<shader::ComputeShaderHandle, shader::ShaderError> {
         self.shader_storage.borrow_mut().create_compute_shader(info)
     }
 
+    /// Compile a compute shader from source code and retrieve the handle.
+    #[cfg(feature = "shader_compile")]
+    pub fn compute_shader_compile(
+        &mut self,
+        source: &shader::ShaderSource,
+    ) -> Result<shader::ComputeShaderHandle, shader::CompileError> {
+        let spirv = source.compile(shader::ShaderStage::Compute)?;
+
+        let handle = self.compute_shader_create(shader::ShaderInfo {
+            spirv_content: &spirv,
+            entry_point: source.entry_point.clone(),
+        })?;
+
+        Ok(handle)
+    }
+
+    /// Replace the program of a compute shader, keeping the handle valid.
+    ///
+    /// Graph pipelines using the shader are retired on the next execution of the graph and
+    /// created again with the new program when their configuration is used.
+    ///
+    /// The new program is checked like in [`compute_shader_create`], the old program stays in use
+    /// if the check fails.
+    ///
+    /// [`compute_shader_create`]: #method.compute_shader_create
+    pub fn compute_shader_replace(
+        &mut self,
+        handle: shader::ComputeShaderHandle,
+        info: shader::ShaderInfo,
+    ) -> Result<(), shader::ShaderError> {
+        self.shader_storage
+            .borrow_mut()
+            .replace_compute_shader(handle, info)
+    }
+
     /// Destroy a compute shader object.
     pub fn compute_shader_destroy(&mut self, handle: shader::ComputeShaderHandle) {
         self.shader_storage
@@ -402,10 +497,51 @@ impl Context {
     }
 
     /// Create a vertex shader and retrieve the handle.
-    pub fn vertex_shader_create(&mut self, info: shader::ShaderInfo) -> shader::VertexShaderHandle {
+    ///
+    /// The SPIR-V code is checked to be a valid module containing the entry point as a
+    /// vertex shader.
+    pub fn vertex_shader_create(
+        &mut self,
+        info: shader::ShaderInfo,
+    ) -> Result<shader::VertexShaderHandle, shader::ShaderError> {
         self.shader_storage.borrow_mut().create_vertex_shader(info)
     }
 
+    /// Compile a vertex shader from source code and retrieve the handle.
+    #[cfg(feature = "shader_compile")]
+    pub fn vertex_shader_compile(
+        &mut self,
+        source: &shader::ShaderSource,
+    ) -> Result<shader::VertexShaderHandle, shader::CompileError> {
+        let spirv = source.compile(shader::ShaderStage::Vertex)?;
+
+        let handle = self.vertex_shader_create(shader::ShaderInfo {
+            spirv_content: &spirv,
+            entry_point: source.entry_point.clone(),
+        })?;
+
+        Ok(handle)
+    }
+
+    /// Replace the program of a vertex shader, keeping the handle valid.
+    ///
+    /// Graph pipelines using the shader are retired on the next execution of the graph and
+    /// created again with the new program when their configuration is used.
+    ///
+    /// The new program is checked like in [`vertex_shader_create`], the old program stays in use
+    /// if the check fails.
+    ///
+    /// [`vertex_shader_create`]: #method.vertex_shader_create
+    pub fn vertex_shader_replace(
+        &mut self,
+        handle: shader::VertexShaderHandle,
+        info: shader::ShaderInfo,
+    ) -> Result<(), shader::ShaderError> {
+        self.shader_storage
+            .borrow_mut()
+            .replace_vertex_shader(handle, info)
+    }
+
     /// Destroy a vertex shader object.
     pub fn vertex_shader_destroy(&mut self, handle: shader::VertexShaderHandle) {
         self.shader_storage
@@ -414,15 +550,53 @@ impl Context {
     }
 
     /// Create a fragment shader and retrieve the handle.
+    ///
+    /// The SPIR-V code is checked to be a valid module containing the entry point as a
+    /// fragment shader.
     pub fn fragment_shader_create(
         &mut self,
         info: shader::ShaderInfo,
-    ) -> shader::FragmentShaderHandle {
+    ) -> Result<shader::FragmentShaderHandle, shader::ShaderError> {
         self.shader_storage
             .borrow_mut()
             .create_fragment_shader(info)
     }
 
+    /// Compile a fragment shader from source code and retrieve the handle.
+    #[cfg(feature = "shader_compile")]
+    pub fn fragment_shader_compile(
+        &mut self,
+        source: &shader::ShaderSource,
+    ) -> Result<shader::FragmentShaderHandle, shader::CompileError> {
+        let spirv = source.compile(shader::ShaderStage::Fragment)?;
+
+        let handle = self.fragment_shader_create(shader::ShaderInfo {
+            spirv_content: &spirv,
+            entry_point: source.entry_point.clone(),
+        })?;
+
+        Ok(handle)
+    }
+
+    /// Replace the program of a fragment shader, keeping the handle valid.
+    ///
+    /// Graph pipelines using the shader are retired on the next execution of the graph and
+    /// created again with the new program when their configuration is used.
+    ///
+    /// The new program is checked like in [`fragment_shader_create`], the old program stays in use
+    /// if the check fails.
+    ///
+    /// [`fragment_shader_create`]: #method.fragment_shader_create
+    pub fn fragment_shader_replace(
+        &mut self,
+        handle: shader::FragmentShaderHandle,
+        info: shader::ShaderInfo,
+    ) -> Result<(), shader::ShaderError> {
+        self.shader_storage
+            .borrow_mut()
+            .replace_fragment_shader(handle, info)
+    }
+
     /// Destroy a fragment shader object.
     pub fn fragment_shader_destroy(&mut self, handle: shader::FragmentShaderHandle) {
         self.shader_storage
@@ -431,15 +605,53 @@ impl Context {
     }
 
     /// Create a geometry shader and retrieve the handle.
+    ///
+    /// The SPIR-V code is checked to be a valid module containing the entry point as a
+    /// geometry shader.
     pub fn geometry_shader_create(
         &mut self,
         info: shader::ShaderInfo,
-    ) -> shader::GeometryShaderHandle {
+    ) -> Result<shader::GeometryShaderHandle, shader::ShaderError> {
         self.shader_storage
             .borrow_mut()
             .create_geometry_shader(info)
     }
 
+    /// Compile a geometry shader from source code and retrieve the handle.
+    #[cfg(feature = "shader_compile")]
+    pub fn geometry_shader_compile(
+        &mut self,
+        source: &shader::ShaderSource,
+    ) -> Result<shader::GeometryShaderHandle, shader::CompileError> {
+        let spirv = source.compile(shader::ShaderStage::Geometry)?;
+
+        let handle = self.geometry_shader_create(shader::ShaderInfo {
+            spirv_content: &spirv,
+            entry_point: source.entry_point.clone(),
+        })?;
+
+        Ok(handle)
+    }
+
+    /// Replace the program of a geometry shader, keeping the handle valid.
+    ///
+    /// Graph pipelines using the shader are retired on the next execution of the graph and
+    /// created again with the new program when their configuration is used.
+    ///
+    /// The new program is checked like in [`geometry_shader_create`], the old program stays in use
+    /// if the check fails.
+    ///
+    /// [`geometry_shader_create`]: #method.geometry_shader_create
+    pub fn geometry_shader_replace(
+        &mut self,
+        handle: shader::GeometryShaderHandle,
+        info: shader::ShaderInfo,
+    ) -> Result<(), shader::ShaderError> {
+        self.shader_storage
+            .borrow_mut()
+            .replace_geometry_shader(handle, info)
+    }
+
     /// Destroy a geometry shader object.
     pub fn geometry_shader_destroy(&mut self, handle: shader::GeometryShaderHandle) {
         self.shader_storage
@@ -447,6 +659,116 @@ impl Context {
             .destroy_geometry_shader(handle);
     }
 
+    /// Create a tessellation control shader and retrieve the handle.
+    ///
+    /// The SPIR-V code is checked to be a valid module containing the entry point as a
+    /// tessellation control shader.
+    pub fn tess_control_shader_create(
+        &mut self,
+        info: shader::ShaderInfo,
+    ) -> Result<shader::TessControlShaderHandle, shader::ShaderError> {
+        self.shader_storage
+            .borrow_mut()
+            .create_tess_control_shader(info)
+    }
+
+    /// Compile a tessellation control shader from source code and retrieve the handle.
+    #[cfg(feature = "shader_compile")]
+    pub fn tess_control_shader_compile(
+        &mut self,
+        source: &shader::ShaderSource,
+    ) -> Result<shader::TessControlShaderHandle, shader::CompileError> {
+        let spirv = source.compile(shader::ShaderStage::TessControl)?;
+
+        let handle = self.tess_control_shader_create(shader::ShaderInfo {
+            spirv_content: &spirv,
+            entry_point: source.entry_point.clone(),
+        })?;
+
+        Ok(handle)
+    }
+
+    /// Replace the program of a tessellation control shader, keeping the handle valid.
+    ///
+    /// Graph pipelines using the shader are retired on the next execution of the graph and
+    /// created again with the new program when their configuration is used.
+    ///
+    /// The new program is checked like in [`tess_control_shader_create`], the old program stays in
+    /// use if the check fails.
+    ///
+    /// [`tess_control_shader_create`]: #method.tess_control_shader_create
+    pub fn tess_control_shader_replace(
+        &mut self,
+        handle: shader::TessControlShaderHandle,
+        info: shader::ShaderInfo,
+    ) -> Result<(), shader::ShaderError> {
+        self.shader_storage
+            .borrow_mut()
+            .replace_tess_control_shader(handle, info)
+    }
+
+    /// Destroy a tessellation control shader object.
+    pub fn tess_control_shader_destroy(&mut self, handle: shader::TessControlShaderHandle) {
+        self.shader_storage
+            .borrow_mut()
+            .destroy_tess_control_shader(handle);
+    }
+
+    /// Create a tessellation evaluation shader and retrieve the handle.
+    ///
+    /// The SPIR-V code is checked to be a valid module containing the entry point as a
+    /// tessellation evaluation shader.
+    pub fn tess_eval_shader_create(
+        &mut self,
+        info: shader::ShaderInfo,
+    ) -> Result<shader::TessEvalShaderHandle, shader::ShaderError> {
+        self.shader_storage
+            .borrow_mut()
+            .create_tess_eval_shader(info)
+    }
+
+    /// Compile a tessellation evaluation shader from source code and retrieve the handle.
+    #[cfg(feature = "shader_compile")]
+    pub fn tess_eval_shader_compile(
+        &mut self,
+        source: &shader::ShaderSource,
+    ) -> Result<shader::TessEvalShaderHandle, shader::CompileError> {
+        let spirv = source.compile(shader::ShaderStage::TessEval)?;
+
+        let handle = self.tess_eval_shader_create(shader::ShaderInfo {
+            spirv_content: &spirv,
+            entry_point: source.entry_point.clone(),
+        })?;
+
+        Ok(handle)
+    }
+
+    /// Replace the program of a tessellation evaluation shader, keeping the handle valid.
+    ///
+    /// Graph pipelines using the shader are retired on the next execution of the graph and
+    /// created again with the new program when their configuration is used.
+    ///
+    /// The new program is checked like in [`tess_eval_shader_create`], the old program stays in
+    /// use if the check fails.
+    ///
+    /// [`tess_eval_shader_create`]: #method.tess_eval_shader_create
+    pub fn tess_eval_shader_replace(
+        &mut self,
+        handle: shader::TessEvalShaderHandle,
+        info: shader::ShaderInfo,
+    ) -> Result<(), shader::ShaderError> {
+        self.shader_storage
+            .borrow_mut()
+            .replace_tess_eval_shader(handle, info)
+    }
+
+    /// Destroy a tessellation evaluation shader object.
+    pub fn tess_eval_shader_destroy(&mut self, handle: shader::TessEvalShaderHandle) {
+        self.shader_storage
+            .borrow_mut()
+            .destroy_tess_eval_shader(handle);
+    }
+
     // submit group
 
     /// Create a new [`SubmitGroup`] to record and execute commands
@@ -12,11 +12,14 @@ use std::collections::BTreeSet;
 
 use crate::device::DeviceContext;
 
-use crate::util::allocator::{AllocatorError, Buffer as AllocBuffer, BufferRequest};
+use crate::util::allocator::{
+    AllocatorError, Block, Buffer as AllocBuffer, BufferRequest,
+    UnboundBuffer as AllocUnboundBuffer,
+};
 use crate::util::storage::{Handle, Storage};
 
 use crate::resources::command_pool::CommandPoolTransfer;
-use crate::submit_group::{QueueSyncRefs, ResourceList};
+use crate::submit_group::{PendingReadback, QueueSyncRefs, ReadbackLayout, ResourceList};
 
 pub(crate) type BufferTypeInternal = AllocBuffer;
 
@@ -30,10 +33,24 @@ pub(crate) type BufferTypeInternal = AllocBuffer;
 pub struct Buffer {
     pub(crate) buffer: BufferTypeInternal,
     size: u64,
-    _usage: gfx::buffer::Usage,
+    usage: gfx::buffer::Usage,
     _properties: gfx::memory::Properties,
 }
 
+/// A buffer that has been created but has no memory bound to it yet.
+pub(crate) struct UnboundBuffer {
+    buffer: AllocUnboundBuffer,
+    size: u64,
+    usage: gfx::buffer::Usage,
+    properties: gfx::memory::Properties,
+}
+
+impl UnboundBuffer {
+    pub(crate) fn requirements(&self) -> gfx::memory::Requirements {
+        self.buffer.requirements()
+    }
+}
+
 /// Opaque handle to a buffer object.
 pub type BufferHandle = Handle<Buffer>;
 
@@ -55,6 +72,12 @@ pub enum BufferError {
 
     #[display(fmt = "The buffer could not be written to (not CPU visible and not TRANSFER_DST)")]
     CantWriteToBuffer,
+
+    #[display(fmt = "The requested range is outside of the buffer")]
+    ReadOutOfBounds,
+
+    #[display(fmt = "The buffer could not be read from (not TRANSFER_SRC)")]
+    CantReadFromBuffer,
 }
 
 impl std::error::Error for BufferError {}
@@ -234,7 +257,7 @@ impl BufferStorage {
             size,
             buffer: raw_buffer,
             _properties: props,
-            _usage: usage,
+            usage,
         };
 
         let handle = self.buffers.insert(buffer);
@@ -287,6 +310,23 @@ impl BufferStorage {
         device: &DeviceContext,
         create_info: DeviceLocalCreateInfo<U>,
     ) -> Result<BufferHandle, BufferError>
+    where
+        U: Clone,
+        U: Into<gfx::buffer::Usage>,
+    {
+        let buffer = self.device_local_create_unbound(device, create_info)?;
+        self.device_local_bind(device, buffer, None)
+    }
+
+    /// Create a device-local buffer object without binding any memory to it.
+    ///
+    /// This is used to find out the memory requirements of buffers which will be bound to
+    /// memory shared with other resources.
+    pub(crate) unsafe fn device_local_create_unbound<U>(
+        &self,
+        device: &DeviceContext,
+        create_info: DeviceLocalCreateInfo<U>,
+    ) -> Result<UnboundBuffer, BufferError>
     where
         U: Clone,
         U: Into<gfx::buffer::Usage>,
@@ -315,13 +355,34 @@ impl BufferStorage {
             size,
         };
 
-        let raw_buffer = allocator.create_buffer(&device.device, req)?;
+        let buffer = allocator.create_buffer_unbound(&device.device, req)?;
 
-        let buffer = Buffer {
+        Ok(UnboundBuffer {
+            buffer,
             size,
+            usage,
+            properties: props,
+        })
+    }
+
+    /// Bind memory to a buffer created with `device_local_create_unbound`.
+    ///
+    /// See `Allocator::bind_buffer` for the meaning of `shared`.
+    pub(crate) unsafe fn device_local_bind(
+        &mut self,
+        device: &DeviceContext,
+        buffer: UnboundBuffer,
+        shared: Option<&Block>,
+    ) -> Result<BufferHandle, BufferError> {
+        let raw_buffer = device
+            .allocator()
+            .bind_buffer(&device.device, buffer.buffer, shared)?;
+
+        let buffer = Buffer {
+            size: buffer.size,
             buffer: raw_buffer,
-            _properties: props,
-            _usage: usage,
+            _properties: buffer.properties,
+            usage: buffer.usage,
         };
 
         let handle = self.buffers.insert(buffer);
@@ -391,6 +452,58 @@ impl BufferStorage {
         Ok(())
     }
 
+    pub(crate) unsafe fn device_local_read(
+        &self,
+        device: &DeviceContext,
+        sync: &mut QueueSyncRefs,
+        cmd_pool: &CommandPoolTransfer,
+        buffer: BufferHandle,
+        range: std::ops::Range<u64>,
+    ) -> Result<PendingReadback, BufferError> {
+        use gfx::buffer::Usage;
+        use gfx::memory::Properties;
+
+        if !self.device_local.contains(&buffer.0) {
+            return Err(BufferError::HandleInvalid);
+        }
+
+        let buffer = self.raw(buffer).ok_or(BufferError::HandleInvalid)?;
+
+        if !buffer.usage.contains(Usage::TRANSFER_SRC) {
+            return Err(BufferError::CantReadFromBuffer);
+        }
+
+        if range.start >= range.end || range.end > buffer.size {
+            return Err(BufferError::ReadOutOfBounds);
+        }
+
+        let layout = ReadbackLayout::bytes((range.end - range.start) as usize);
+
+        let req = BufferRequest {
+            transient: true,
+            properties: Properties::CPU_VISIBLE | Properties::COHERENT,
+            usage: Usage::TRANSFER_DST,
+            size: layout.staging_size(),
+        };
+
+        let staging_buffer = device.allocator().create_buffer(&device.device, req)?;
+
+        crate::transfer::read_buffers(
+            device,
+            sync.sem_pool,
+            sync.sem_list,
+            cmd_pool,
+            &[crate::transfer::BufferReadTransfer {
+                src: &buffer.buffer,
+                dst: &staging_buffer,
+                offset: range.start,
+                size: range.end - range.start,
+            }],
+        );
+
+        Ok(PendingReadback::new(staging_buffer, layout))
+    }
+
     pub fn destroy<B>(&mut self, res_list: &mut ResourceList, buffers: B)
     where
         B: IntoIterator,
@@ -441,7 +554,10 @@ unsafe fn write_data_to_buffer(
 ) -> Result<(), BufferError> {
     use rendy_memory::Block;
 
-    let block = buffer.block_mut();
+    // Memory shared with other resources can't be mapped through a single buffer.
+    let block = buffer
+        .block_mut()
+        .ok_or(gfx::mapping::Error::InvalidAccess)?;
 
     let range = offset..(offset + data.len() as u64);
 
@@ -465,7 +581,7 @@ unsafe fn write_data_to_buffer(
     Ok(())
 }
 
-unsafe fn read_data_from_buffer(
+pub(crate) unsafe fn read_data_from_buffer(
     device: &DeviceContext,
     buffer: &mut BufferTypeInternal,
     offset: u64,
@@ -473,7 +589,9 @@ unsafe fn read_data_from_buffer(
 ) -> Result<(), BufferError> {
     use rendy_memory::Block;
 
-    let block = buffer.block_mut();
+    let block = buffer
+        .block_mut()
+        .ok_or(gfx::mapping::Error::InvalidAccess)?;
 
     let range = offset..(offset + data.len() as u64);
 
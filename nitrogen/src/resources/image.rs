@@ -10,15 +10,20 @@ use gfx::Device;
 use std;
 use std::borrow::Borrow;
 use std::collections::BTreeSet;
+use std::convert::TryFrom;
 use std::hash::{Hash, Hasher};
+use std::ops::Range;
 
-use crate::util::allocator::{AllocatorError, BufferRequest, Image as AllocImage, ImageRequest};
+use crate::util::allocator::{
+    AllocatorError, Block, BufferRequest, Image as AllocImage, ImageRequest,
+    UnboundImage as AllocUnboundImage,
+};
 use crate::util::storage::{Handle, Storage};
 use crate::util::transfer;
 
 use crate::device::DeviceContext;
 use crate::resources::command_pool::CommandPoolTransfer;
-use crate::submit_group::{QueueSyncRefs, ResourceList};
+use crate::submit_group::{PendingReadback, QueueSyncRefs, ReadbackLayout, ResourceList};
 
 /// Source channel for a `Swizzle`
 #[repr(u8)]
@@ -108,6 +113,28 @@ impl ImageDimension {
             D3 { x, y, z } => (*x, *y, *z),
         }
     }
+
+    /// Calculate the dimensions of a mipmap level.
+    ///
+    /// Every level has half the size of the previous one, but is never smaller than one texel.
+    pub fn mip_level(&self, level: u8) -> Self {
+        use self::ImageDimension::*;
+
+        let shrink = |v: u32| v.checked_shr(u32::from(level)).unwrap_or(0).max(1);
+
+        match *self {
+            D1 { x } => D1 { x: shrink(x) },
+            D2 { x, y } => D2 {
+                x: shrink(x),
+                y: shrink(y),
+            },
+            D3 { x, y, z } => D3 {
+                x: shrink(x),
+                y: shrink(y),
+                z: shrink(z),
+            },
+        }
+    }
 }
 
 /// Size mode used for image resources created in graphs.
@@ -160,11 +187,12 @@ pub type ImageHandle = Handle<Image>;
 pub struct ImageCreateInfo<T: Into<gfx::image::Usage>> {
     /// Dimensions ("size") of the image.
     pub dimension: ImageDimension,
-    /// Number of layers of the image. Used for image arrays and cube images.
+    /// Number of layers of the image. Used for image arrays and cube images, which have six
+    /// layers per cube. Zero is treated like one.
     pub num_layers: u16,
     /// Number of samples used for multisampling.
     pub num_samples: u8,
-    /// Number of mipmap images.
+    /// Number of mipmap levels, including the base level. Zero is treated like one.
     pub num_mipmaps: u8,
     /// Format of the image.
     pub format: ImageFormat,
@@ -242,6 +270,36 @@ pub struct ImageUploadInfo<'a> {
     pub dimension: ImageDimension,
     /// Offset in the target image to write to.
     pub target_offset: (u32, u32, u32),
+    /// Mipmap level of the target image to write to.
+    pub level: u8,
+    /// Layer of the target image to write to. For cube images this is the face.
+    pub layer: u16,
+}
+
+/// Description of a region of an image object that should be read into host memory.
+#[derive(Debug, Clone, Copy)]
+pub struct ImageReadInfo {
+    /// The format the data is read as. This has to match the format the image was created with.
+    pub format: ImageFormat,
+    /// The dimensions of the region to read.
+    pub dimension: ImageDimension,
+    /// Offset in the source image to read from.
+    pub source_offset: (u32, u32, u32),
+    /// Mipmap level of the source image to read from.
+    pub level: u8,
+    /// Layer of the source image to read from. For cube images this is the face.
+    pub layer: u16,
+}
+
+/// Region of an image object used as the source or destination of a blit.
+#[derive(Debug, Clone)]
+pub struct ImageBlitRegion {
+    /// Bounds of the region within the mipmap level.
+    pub bounds: Range<gfx::image::Offset>,
+    /// Mipmap level of the region.
+    pub level: u8,
+    /// Layers of the region. For cube images these are the faces.
+    pub layers: Range<u16>,
 }
 
 /// Image formats
@@ -254,12 +312,46 @@ pub enum ImageFormat {
     RgbUnorm,
     RgbaUnorm,
 
+    RgbSrgb,
+    RgbaSrgb,
+
+    BgraUnorm,
+    BgraSrgb,
+
+    R16Float,
+    Rg16Float,
+    Rgba16Float,
+
+    R32Uint,
+    R32Float,
     Rgba32Float,
 
+    /// Packed unsigned float format, also known as R11G11B10.
+    B10g11r11Float,
     E5b9g9r9Float,
 
+    D16Unorm,
+    D24UnormS8Uint,
     D32Float,
     D32FloatS8Uint,
+
+    Bc1RgbaUnorm,
+    Bc1RgbaSrgb,
+    Bc3Unorm,
+    Bc3Srgb,
+    Bc4Unorm,
+    Bc5Unorm,
+    Bc6hUfloat,
+    Bc7Unorm,
+    Bc7Srgb,
+
+    Etc2RgbUnorm,
+    Etc2RgbSrgb,
+    Etc2RgbaUnorm,
+    Etc2RgbaSrgb,
+
+    Astc4x4Unorm,
+    Astc4x4Srgb,
 }
 
 impl Default for ImageFormat {
@@ -278,35 +370,112 @@ impl From<ImageFormat> for gfx::format::Format {
             ImageFormat::RgbUnorm => Format::Rgb8Unorm,
             ImageFormat::RgbaUnorm => Format::Rgba8Unorm,
 
+            ImageFormat::RgbSrgb => Format::Rgb8Srgb,
+            ImageFormat::RgbaSrgb => Format::Rgba8Srgb,
+
+            ImageFormat::BgraUnorm => Format::Bgra8Unorm,
+            ImageFormat::BgraSrgb => Format::Bgra8Srgb,
+
+            ImageFormat::R16Float => Format::R16Sfloat,
+            ImageFormat::Rg16Float => Format::Rg16Sfloat,
+            ImageFormat::Rgba16Float => Format::Rgba16Sfloat,
+
+            ImageFormat::R32Uint => Format::R32Uint,
+            ImageFormat::R32Float => Format::R32Sfloat,
             ImageFormat::Rgba32Float => Format::Rgba32Sfloat,
 
+            ImageFormat::B10g11r11Float => Format::B10g11r11Ufloat,
             ImageFormat::E5b9g9r9Float => Format::E5b9g9r9Ufloat,
 
+            ImageFormat::D16Unorm => Format::D16Unorm,
+            ImageFormat::D24UnormS8Uint => Format::D24UnormS8Uint,
             ImageFormat::D32Float => Format::D32Sfloat,
             ImageFormat::D32FloatS8Uint => Format::D32SfloatS8Uint,
+
+            ImageFormat::Bc1RgbaUnorm => Format::Bc1RgbaUnorm,
+            ImageFormat::Bc1RgbaSrgb => Format::Bc1RgbaSrgb,
+            ImageFormat::Bc3Unorm => Format::Bc3Unorm,
+            ImageFormat::Bc3Srgb => Format::Bc3Srgb,
+            ImageFormat::Bc4Unorm => Format::Bc4Unorm,
+            ImageFormat::Bc5Unorm => Format::Bc5Unorm,
+            ImageFormat::Bc6hUfloat => Format::Bc6hUfloat,
+            ImageFormat::Bc7Unorm => Format::Bc7Unorm,
+            ImageFormat::Bc7Srgb => Format::Bc7Srgb,
+
+            ImageFormat::Etc2RgbUnorm => Format::Etc2R8g8b8Unorm,
+            ImageFormat::Etc2RgbSrgb => Format::Etc2R8g8b8Srgb,
+            ImageFormat::Etc2RgbaUnorm => Format::Etc2R8g8b8a8Unorm,
+            ImageFormat::Etc2RgbaSrgb => Format::Etc2R8g8b8a8Srgb,
+
+            ImageFormat::Astc4x4Unorm => Format::Astc4x4Unorm,
+            ImageFormat::Astc4x4Srgb => Format::Astc4x4Srgb,
         }
     }
 }
 
-impl Into<ImageFormat> for gfx::format::Format {
-    fn into(self) -> ImageFormat {
+/// Error returned when a format has no `ImageFormat` equivalent.
+#[derive(Debug, Display, Clone, Copy, PartialEq)]
+#[display(fmt = "Format {:?} is not supported as an image format", _0)]
+pub struct UnsupportedFormat(pub gfx::format::Format);
+
+impl std::error::Error for UnsupportedFormat {}
+
+impl TryFrom<gfx::format::Format> for ImageFormat {
+    type Error = UnsupportedFormat;
+
+    fn try_from(format: gfx::format::Format) -> Result<Self, Self::Error> {
         use gfx::format::Format;
 
-        match self {
+        let format = match format {
             Format::R8Unorm => ImageFormat::RUnorm,
             Format::Rg8Unorm => ImageFormat::RgUnorm,
             Format::Rgb8Unorm => ImageFormat::RgbUnorm,
             Format::Rgba8Unorm => ImageFormat::RgbaUnorm,
 
+            Format::Rgb8Srgb => ImageFormat::RgbSrgb,
+            Format::Rgba8Srgb => ImageFormat::RgbaSrgb,
+
+            Format::Bgra8Unorm => ImageFormat::BgraUnorm,
+            Format::Bgra8Srgb => ImageFormat::BgraSrgb,
+
+            Format::R16Sfloat => ImageFormat::R16Float,
+            Format::Rg16Sfloat => ImageFormat::Rg16Float,
+            Format::Rgba16Sfloat => ImageFormat::Rgba16Float,
+
+            Format::R32Uint => ImageFormat::R32Uint,
+            Format::R32Sfloat => ImageFormat::R32Float,
             Format::Rgba32Sfloat => ImageFormat::Rgba32Float,
 
+            Format::B10g11r11Ufloat => ImageFormat::B10g11r11Float,
             Format::E5b9g9r9Ufloat => ImageFormat::E5b9g9r9Float,
 
+            Format::D16Unorm => ImageFormat::D16Unorm,
+            Format::D24UnormS8Uint => ImageFormat::D24UnormS8Uint,
             Format::D32Sfloat => ImageFormat::D32Float,
             Format::D32SfloatS8Uint => ImageFormat::D32FloatS8Uint,
 
-            _ => unimplemented!(),
-        }
+            Format::Bc1RgbaUnorm => ImageFormat::Bc1RgbaUnorm,
+            Format::Bc1RgbaSrgb => ImageFormat::Bc1RgbaSrgb,
+            Format::Bc3Unorm => ImageFormat::Bc3Unorm,
+            Format::Bc3Srgb => ImageFormat::Bc3Srgb,
+            Format::Bc4Unorm => ImageFormat::Bc4Unorm,
+            Format::Bc5Unorm => ImageFormat::Bc5Unorm,
+            Format::Bc6hUfloat => ImageFormat::Bc6hUfloat,
+            Format::Bc7Unorm => ImageFormat::Bc7Unorm,
+            Format::Bc7Srgb => ImageFormat::Bc7Srgb,
+
+            Format::Etc2R8g8b8Unorm => ImageFormat::Etc2RgbUnorm,
+            Format::Etc2R8g8b8Srgb => ImageFormat::Etc2RgbSrgb,
+            Format::Etc2R8g8b8a8Unorm => ImageFormat::Etc2RgbaUnorm,
+            Format::Etc2R8g8b8a8Srgb => ImageFormat::Etc2RgbaSrgb,
+
+            Format::Astc4x4Unorm => ImageFormat::Astc4x4Unorm,
+            Format::Astc4x4Srgb => ImageFormat::Astc4x4Srgb,
+
+            format => return Err(UnsupportedFormat(format)),
+        };
+
+        Ok(format)
     }
 }
 
@@ -314,8 +483,10 @@ impl ImageFormat {
     /// Determine if the given format contains a depth component
     pub fn is_depth(self) -> bool {
         match self {
-            ImageFormat::D32FloatS8Uint => true,
+            ImageFormat::D16Unorm => true,
+            ImageFormat::D24UnormS8Uint => true,
             ImageFormat::D32Float => true,
+            ImageFormat::D32FloatS8Uint => true,
             _ => false,
         }
     }
@@ -323,6 +494,7 @@ impl ImageFormat {
     /// Determine if the given format contains a stencil component
     pub fn is_stencil(self) -> bool {
         match self {
+            ImageFormat::D24UnormS8Uint => true,
             ImageFormat::D32FloatS8Uint => true,
             _ => false,
         }
@@ -332,6 +504,50 @@ impl ImageFormat {
     pub fn is_depth_stencil(self) -> bool {
         self.is_depth() && self.is_stencil()
     }
+
+    /// Determine if the given format stores texels in compressed blocks.
+    pub fn is_compressed(self) -> bool {
+        self.block_extent() != (1, 1)
+    }
+
+    /// Width and height in texels of a single block of this format.
+    ///
+    /// Uncompressed formats have a block extent of `(1, 1)`.
+    pub fn block_extent(self) -> (u32, u32) {
+        let (width, height) = gfx::format::Format::from(self).surface_desc().dim;
+        (u32::from(width), u32::from(height))
+    }
+
+    /// Size in bytes of a single block (or texel for uncompressed formats) of this format.
+    pub fn block_size(self) -> u32 {
+        u32::from(gfx::format::Format::from(self).surface_desc().bits / 8)
+    }
+
+    /// Size in bytes of tightly packed data of the given width and height in this format.
+    ///
+    /// Partial blocks at the edges are counted as full blocks.
+    pub fn data_size(self, (width, height): (u32, u32)) -> usize {
+        let (block_width, block_height) = self.block_extent();
+
+        // Rounding up like this can't overflow, unlike adding `block_width - 1` first.
+        let blocks_x = width / block_width + u32::from(width % block_width != 0);
+        let blocks_y = height / block_height + u32::from(height % block_height != 0);
+
+        (blocks_x as usize)
+            .saturating_mul(blocks_y as usize)
+            .saturating_mul(self.block_size() as usize)
+    }
+
+    /// Format with the same components which is used in case this one is not supported.
+    fn fallback(self) -> Option<ImageFormat> {
+        match self {
+            ImageFormat::RgbUnorm => Some(ImageFormat::RgbaUnorm),
+            ImageFormat::RgbSrgb => Some(ImageFormat::RgbaSrgb),
+            ImageFormat::D16Unorm => Some(ImageFormat::D32Float),
+            ImageFormat::D24UnormS8Uint => Some(ImageFormat::D32FloatS8Uint),
+            _ => None,
+        }
+    }
 }
 
 /// Kind of image
@@ -340,7 +556,7 @@ impl ImageFormat {
 /// different between kinds (for example, an array of 2-dimensional images is sampled differently
 /// than a 3D image in regards to mipmaps)
 #[repr(u8)]
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum ViewKind {
     /// One dimensional (N x 1 x 1)
     D1,
@@ -393,6 +609,51 @@ pub struct Image {
     pub(crate) dimension: ImageDimension,
     pub(crate) format: gfx::format::Format,
     pub(crate) usage: gfx::image::Usage,
+    pub(crate) levels: u8,
+    pub(crate) layers: u16,
+    /// Two-dimensional views of every single layer, used for rendering into one layer.
+    ///
+    /// Only created for images with more than one layer.
+    pub(crate) layer_views: Vec<ImageView>,
+}
+
+impl Image {
+    /// The subresource range covering all levels and layers of the image.
+    pub(crate) fn subresource_range(&self) -> image::SubresourceRange {
+        image::SubresourceRange {
+            aspects: self.aspect,
+            levels: 0..self.levels,
+            layers: 0..self.layers,
+        }
+    }
+
+    /// View of a single layer of the image.
+    pub(crate) fn layer_view(&self, layer: u16) -> Option<&ImageView> {
+        if self.layer_views.is_empty() && layer == 0 {
+            Some(&self.view)
+        } else {
+            self.layer_views.get(layer as usize)
+        }
+    }
+}
+
+/// An image that has been created but has no memory bound to it yet.
+pub(crate) struct UnboundImage {
+    image: AllocUnboundImage,
+    format: gfx::format::Format,
+    usage: gfx::image::Usage,
+    aspect: gfx::format::Aspects,
+    dimension: ImageDimension,
+    levels: u8,
+    layers: u16,
+    kind: ViewKind,
+    swizzle: Swizzle,
+}
+
+impl UnboundImage {
+    pub(crate) fn requirements(&self) -> gfx::memory::Requirements {
+        self.image.requirements()
+    }
 }
 
 /// Errors that can occur while operating on image resources.
@@ -416,6 +677,27 @@ pub enum ImageError {
 
     #[display(fmt = "Image can not be used a transfer destination")]
     CantWriteToImage,
+
+    #[display(fmt = "Image needs to be a transfer source and destination to generate mipmaps")]
+    CantGenerateMipmaps,
+
+    #[display(
+        fmt = "Format {:?} does not support generating mipmaps with the requested filter",
+        _0
+    )]
+    MipmapsUnsupported(gfx::format::Format),
+
+    #[display(fmt = "The region or format requested for reading was not valid")]
+    ReadDataInvalid,
+
+    #[display(fmt = "Image can not be used a transfer source")]
+    CantReadFromImage,
+
+    #[display(fmt = "Format {:?} is not supported by the device for this usage", _0)]
+    FormatUnsupported(ImageFormat),
+
+    #[display(fmt = "Images with {} layers can't be viewed as {:?}", _1, _0)]
+    LayerCountInvalid(ViewKind, u16),
 }
 
 impl std::error::Error for ImageError {}
@@ -441,6 +723,10 @@ impl ImageStorage {
         for (_, image) in self.storage {
             alloc.destroy_image(&device.device, image.image);
             device.device.destroy_image_view(image.view);
+
+            for view in image.layer_views {
+                device.device.destroy_image_view(view);
+            }
         }
     }
 
@@ -449,18 +735,25 @@ impl ImageStorage {
         device: &DeviceContext,
         create_info: ImageCreateInfo<T>,
     ) -> Result<ImageHandle, ImageError> {
-        use gfx::format::Format;
+        let image = self.create_unbound(device, create_info)?;
+        self.bind(device, image, None)
+    }
 
+    /// Create an image object without binding any memory to it.
+    ///
+    /// This is used to find out the memory requirements of images which will be bound to
+    /// memory shared with other resources.
+    pub(crate) unsafe fn create_unbound<T: Into<gfx::image::Usage> + Clone>(
+        &self,
+        device: &DeviceContext,
+        create_info: ImageCreateInfo<T>,
+    ) -> Result<UnboundImage, ImageError> {
         let mut allocator = device.allocator();
 
-        let format = create_info.format.into();
+        let usage_flags = create_info.usage.clone().into();
 
-        // some formats are not supported on most GPUs, for example most 24 bit ones.
-        // TODO: this should not use hardcoded values but values from the device info maybe?
-        let format = match format {
-            Format::Rgb8Unorm => Format::Rgba8Unorm,
-            format => format,
-        };
+        let format = supported_format(device, create_info.format, usage_flags)
+            .ok_or(ImageError::FormatUnsupported(create_info.format))?;
 
         let aspect = {
             let mut aspect = gfx::format::Aspects::empty();
@@ -480,56 +773,117 @@ impl ImageStorage {
             aspect
         };
 
+        let levels = create_info.num_mipmaps.max(1);
+        let layers = create_info.num_layers.max(1);
+
+        if !layers_fit_kind(create_info.kind, layers) {
+            return Err(ImageError::LayerCountInvalid(create_info.kind, layers));
+        }
+
         let (image, usage) = {
             let image_kind = match create_info.dimension {
-                ImageDimension::D1 { x } => image::Kind::D1(x, create_info.num_layers),
+                ImageDimension::D1 { x } => image::Kind::D1(x, layers),
                 ImageDimension::D2 { x, y } => {
-                    image::Kind::D2(x, y, create_info.num_layers, create_info.num_samples)
+                    image::Kind::D2(x, y, layers, create_info.num_samples)
                 }
                 ImageDimension::D3 { x, y, z } => image::Kind::D3(x, y, z),
             };
 
             use gfx::memory::Properties;
 
-            let usage_flags = create_info.usage.clone().into();
+            let view_caps = match create_info.kind {
+                ViewKind::Cube | ViewKind::CubeArray => image::ViewCapabilities::KIND_CUBE,
+                _ => image::ViewCapabilities::empty(),
+            };
 
             let req = ImageRequest {
                 transient: create_info.is_transient,
                 properties: Properties::DEVICE_LOCAL,
                 kind: image_kind,
-                level: 1,
+                level: levels,
                 format,
                 tiling: image::Tiling::Optimal,
                 usage: usage_flags,
-                view_caps: image::ViewCapabilities::empty(),
+                view_caps,
             };
 
-            let image = allocator.create_image(&device.device, req)?;
+            let image = allocator.create_image_unbound(&device.device, req)?;
 
             (image, usage_flags)
         };
 
-        let image_view = device.device.create_image_view(
-            image.raw(),
-            create_info.kind.into(),
+        Ok(UnboundImage {
+            image,
             format,
-            create_info.swizzle.into(),
+            usage,
+            aspect,
+            dimension: create_info.dimension,
+            levels,
+            layers,
+            kind: create_info.kind,
+            swizzle: create_info.swizzle,
+        })
+    }
+
+    /// Bind memory to an image created with `create_unbound` and make it usable.
+    ///
+    /// See `Allocator::bind_image` for the meaning of `shared`.
+    pub(crate) unsafe fn bind(
+        &mut self,
+        device: &DeviceContext,
+        image: UnboundImage,
+        shared: Option<&Block>,
+    ) -> Result<ImageHandle, ImageError> {
+        let bound = device
+            .allocator()
+            .bind_image(&device.device, image.image, shared)?;
+
+        let image_view = device.device.create_image_view(
+            bound.raw(),
+            image.kind.into(),
+            image.format,
+            image.swizzle.into(),
             image::SubresourceRange {
-                aspects: aspect,
-                layers: 0..1,
-                levels: 0..1,
+                aspects: image.aspect,
+                layers: 0..image.layers,
+                levels: 0..image.levels,
             },
         )?;
 
+        let mut layer_views = Vec::new();
+
+        if image.layers > 1 {
+            for layer in 0..image.layers {
+                let view = device.device.create_image_view(
+                    bound.raw(),
+                    image::ViewKind::D2,
+                    image.format,
+                    image.swizzle.into(),
+                    image::SubresourceRange {
+                        aspects: image.aspect,
+                        layers: layer..layer + 1,
+                        levels: 0..1,
+                    },
+                )?;
+
+                layer_views.push(view);
+            }
+        }
+
         let img_store = Image {
-            image,
-            format,
-            usage,
-            aspect,
-            dimension: create_info.dimension,
+            image: bound,
+            format: image.format,
+            usage: image.usage,
+            aspect: image.aspect,
+            dimension: image.dimension,
+            levels: image.levels,
+            layers: image.layers,
             view: image_view,
+            layer_views,
         };
 
+        let usage = image.usage;
+
         let handle = self.storage.insert(img_store);
 
         if usage.contains(gfx::image::Usage::TRANSFER_DST) {
@@ -560,36 +914,13 @@ impl ImageStorage {
 
         let mut allocator = device.allocator();
 
-        let dimensions = image.dimension;
+        if data.level >= image.levels || data.layer >= image.layers {
+            return Err(ImageError::UploadDataInvalid);
+        }
 
-        let upload_data_fits = {
-            use self::ImageDimension as I;
-            match (dimensions, data.dimension) {
-                (I::D1 { x: dx }, I::D1 { x: sx }) => (sx + data.target_offset.0) <= dx,
-                (I::D2 { x: dx, y: dy }, I::D2 { x: sx, y: sy }) => {
-                    (sx + data.target_offset.0) <= dx && (sy + data.target_offset.1) <= dy
-                }
-                (
-                    I::D3 {
-                        x: dx,
-                        y: dy,
-                        z: dz,
-                    },
-                    I::D3 {
-                        x: sx,
-                        y: sy,
-                        z: sz,
-                    },
-                ) => {
-                    (sx + data.target_offset.0) <= dx
-                        && (sy + data.target_offset.1) <= dy
-                        && (sz + data.target_offset.2) <= dz
-                }
-                _ => false,
-            }
-        };
+        let dimensions = image.dimension.mip_level(data.level);
 
-        if !upload_data_fits {
+        if !region_fits(dimensions, data.dimension, data.target_offset) {
             return Err(ImageError::UploadDataInvalid);
         }
 
@@ -602,15 +933,36 @@ impl ImageStorage {
             }
         };
 
-        let upload_nums = {
+        let block_size = ImageFormat::try_from(image.format)
+            .map(|format| (format.block_extent(), format.block_size()))
+            .ok();
+
+        // The data has to have the same memory layout as the image it is written to.
+        if block_size != Some((data.format.block_extent(), data.format.block_size())) {
+            return Err(ImageError::UploadDataInvalid);
+        }
+
+        if !region_is_block_aligned(
+            data.format,
+            dimensions,
+            (upload_width, upload_height),
+            data.target_offset,
+        ) {
+            return Err(ImageError::UploadDataInvalid);
+        }
+
+        let layout = {
             let row_align = limits.optimal_buffer_copy_pitch_alignment as u32;
-            image_copy_buffer_size(row_align, &data, (upload_width, upload_height))
+            image_copy_buffer_layout(row_align, image.format, (upload_width, upload_height))
         };
-        let (upload_size, row_pitch, texel_size) = upload_nums;
 
-        debug_assert!(
-            upload_size >= u64::from(upload_width) * u64::from(upload_height) * texel_size as u64
-        );
+        let src_row_size = (layout.row_blocks * layout.block_size) as usize;
+
+        if data.data.len() < src_row_size * layout.rows as usize {
+            return Err(ImageError::UploadDataInvalid);
+        }
+
+        let upload_size = layout.buffer_size();
 
         let buf_req = BufferRequest {
             transient: true,
@@ -625,7 +977,9 @@ impl ImageStorage {
         {
             use rendy_memory::Block;
 
-            let block = staging.block_mut();
+            let block = staging
+                .block_mut()
+                .ok_or(gfx::mapping::Error::InvalidAccess)?;
 
             let range = 0..block.size();
 
@@ -640,14 +994,15 @@ impl ImageStorage {
 
                 // Alignment strikes back again! We do copy all the rows, but the row length in the
                 // staging buffer might be bigger than in the upload data, so we need to construct
-                // a slice for each row instead of just copying *everything*
-                for y in 0..upload_height as usize {
-                    let src_start = y * (upload_width as usize) * texel_size;
-                    let src_end = (y + 1) * (upload_width as usize) * texel_size;
+                // a slice for each row instead of just copying *everything*.
+                // For compressed formats a "row" is a whole row of blocks.
+                for y in 0..layout.rows as usize {
+                    let src_start = y * src_row_size;
+                    let src_end = (y + 1) * src_row_size;
 
                     let row = &data.data[src_start..src_end];
 
-                    let dst_start = y * row_pitch as usize;
+                    let dst_start = y * layout.row_pitch as usize;
                     let dst_end = dst_start + row.len();
 
                     slice[dst_start..dst_end].copy_from_slice(row);
@@ -666,17 +1021,17 @@ impl ImageStorage {
             dst: &image.image,
             subresource_range: gfx::image::SubresourceRange {
                 aspects: gfx::format::Aspects::COLOR,
-                levels: 0..1,
-                layers: 0..1,
+                levels: data.level..data.level + 1,
+                layers: data.layer..data.layer + 1,
             },
             copy_information: gfx::command::BufferImageCopy {
                 buffer_offset: 0,
-                buffer_width: row_pitch / (texel_size as u32),
-                buffer_height: upload_height,
+                buffer_width: layout.buffer_width,
+                buffer_height: layout.buffer_height,
                 image_layers: gfx::image::SubresourceLayers {
                     aspects: gfx::format::Aspects::COLOR,
-                    level: 0,
-                    layers: 0..1,
+                    level: data.level,
+                    layers: data.layer..data.layer + 1,
                 },
                 image_offset: image::Offset {
                     x: data.target_offset.0 as i32,
@@ -704,6 +1059,131 @@ impl ImageStorage {
         Ok(())
     }
 
+    pub(crate) unsafe fn read_data(
+        &self,
+        device: &DeviceContext,
+        sync: &mut QueueSyncRefs,
+        cmd_pool: &CommandPoolTransfer,
+        handle: ImageHandle,
+        info: ImageReadInfo,
+    ) -> Result<PendingReadback, ImageError> {
+        use gfx::memory::Properties;
+        use gfx::PhysicalDevice;
+
+        let image = self.storage.get(handle).ok_or(ImageError::HandleInvalid)?;
+
+        if !image.usage.contains(gfx::image::Usage::TRANSFER_SRC) {
+            return Err(ImageError::CantReadFromImage);
+        }
+
+        // Depth and stencil data can't be read in one go, so only color images are supported.
+        if image.aspect != gfx::format::Aspects::COLOR {
+            return Err(ImageError::ReadDataInvalid);
+        }
+
+        if info.level >= image.levels || info.layer >= image.layers {
+            return Err(ImageError::ReadDataInvalid);
+        }
+
+        let dimensions = image.dimension.mip_level(info.level);
+
+        if !region_fits(dimensions, info.dimension, info.source_offset) {
+            return Err(ImageError::ReadDataInvalid);
+        }
+
+        let (read_width, read_height) = match info.dimension {
+            ImageDimension::D1 { x } => (x, 1),
+            ImageDimension::D2 { x, y } => (x, y),
+            ImageDimension::D3 { .. } => {
+                // TODO support 3D data?
+                return Err(ImageError::ReadDataInvalid);
+            }
+        };
+
+        // The requested format might have been replaced by a supported one with bigger texels
+        // when the image was created, in which case the additional components are dropped.
+        let read_format: gfx::format::Format = info.format.into();
+
+        if supported_format(device, info.format, image.usage) != Some(image.format) {
+            return Err(ImageError::ReadDataInvalid);
+        }
+
+        if !region_is_block_aligned(
+            info.format,
+            dimensions,
+            (read_width, read_height),
+            info.source_offset,
+        ) {
+            return Err(ImageError::ReadDataInvalid);
+        }
+
+        let limits: gfx::Limits = device.adapter.physical_device.limits();
+
+        let copy_layout = {
+            let row_align = limits.optimal_buffer_copy_pitch_alignment as u32;
+            image_copy_buffer_layout(row_align, image.format, (read_width, read_height))
+        };
+
+        // Compressed images are always read in their own format, so the blocks are simply
+        // treated as big texels.
+        let layout = ReadbackLayout {
+            rows: copy_layout.rows as usize,
+            row_pitch: copy_layout.row_pitch as usize,
+            row_texels: copy_layout.row_blocks as usize,
+            src_texel_size: copy_layout.block_size as usize,
+            dst_texel_size: usize::from(read_format.surface_desc().bits / 8),
+        };
+
+        let buf_req = BufferRequest {
+            transient: true,
+            properties: Properties::CPU_VISIBLE | Properties::COHERENT,
+            usage: gfx::buffer::Usage::TRANSFER_DST,
+            size: layout.staging_size(),
+        };
+
+        let staging = device.allocator().create_buffer(&device.device, buf_req)?;
+
+        let transfer_data = transfer::ImageBufferTransfer {
+            src: &image.image,
+            dst: &staging,
+            subresource_range: gfx::image::SubresourceRange {
+                aspects: gfx::format::Aspects::COLOR,
+                levels: info.level..info.level + 1,
+                layers: info.layer..info.layer + 1,
+            },
+            copy_information: gfx::command::BufferImageCopy {
+                buffer_offset: 0,
+                buffer_width: copy_layout.buffer_width,
+                buffer_height: copy_layout.buffer_height,
+                image_layers: gfx::image::SubresourceLayers {
+                    aspects: gfx::format::Aspects::COLOR,
+                    level: info.level,
+                    layers: info.layer..info.layer + 1,
+                },
+                image_offset: image::Offset {
+                    x: info.source_offset.0 as i32,
+                    y: info.source_offset.1 as i32,
+                    z: info.source_offset.2 as i32,
+                },
+                image_extent: image::Extent {
+                    width: read_width,
+                    height: read_height,
+                    depth: 1,
+                },
+            },
+        };
+
+        transfer::copy_images_to_buffers(
+            device,
+            sync.sem_pool,
+            sync.sem_list,
+            cmd_pool,
+            &[transfer_data],
+        );
+
+        Ok(PendingReadback::new(staging, layout))
+    }
+
     pub(crate) fn raw(&self, image: ImageHandle) -> Option<&Image> {
         if self.storage.is_alive(image) {
             Some(&self.storage[image])
@@ -731,6 +1211,10 @@ impl ImageStorage {
                 res_list.queue_image(image.image);
                 res_list.queue_image_view(image.view);
 
+                for view in image.layer_views {
+                    res_list.queue_image_view(view);
+                }
+
                 if self.transfer_dst.contains(&handle.id()) {
                     self.transfer_dst.remove(&handle.id());
                 }
@@ -739,21 +1223,151 @@ impl ImageStorage {
     }
 }
 
-/// Compute the total size in bytes and the row stride
-/// for a buffer that should be used to copy data into an image.
-fn image_copy_buffer_size(
+/// Find the format used for an image with the given format and usage.
+///
+/// Some formats are not supported by most devices (for example most 24 bit ones), in that case
+/// the first supported fallback format is used.
+fn supported_format(
+    device: &DeviceContext,
+    format: ImageFormat,
+    usage: gfx::image::Usage,
+) -> Option<gfx::format::Format> {
+    use gfx::format::ImageFeature;
+    use gfx::image::Usage;
+    use gfx::PhysicalDevice;
+
+    let mut features = ImageFeature::empty();
+
+    if usage.contains(Usage::SAMPLED) {
+        features |= ImageFeature::SAMPLED;
+    }
+    if usage.contains(Usage::STORAGE) {
+        features |= ImageFeature::STORAGE;
+    }
+    if usage.contains(Usage::COLOR_ATTACHMENT) {
+        features |= ImageFeature::COLOR_ATTACHMENT;
+    }
+    if usage.contains(Usage::DEPTH_STENCIL_ATTACHMENT) {
+        features |= ImageFeature::DEPTH_STENCIL_ATTACHMENT;
+    }
+
+    let mut candidate = Some(format);
+
+    while let Some(format) = candidate {
+        let raw = format.into();
+
+        let properties = device.adapter.physical_device.format_properties(Some(raw));
+
+        if properties.optimal_tiling.contains(features) {
+            return Some(raw);
+        }
+
+        candidate = format.fallback();
+    }
+
+    None
+}
+
+/// Check whether an image with the given number of layers can be viewed as `kind`.
+fn layers_fit_kind(kind: ViewKind, layers: u16) -> bool {
+    match kind {
+        ViewKind::D1 | ViewKind::D2 | ViewKind::D3 => layers == 1,
+        ViewKind::D1Array | ViewKind::D2Array => true,
+        ViewKind::Cube => layers == 6,
+        ViewKind::CubeArray => layers % 6 == 0,
+    }
+}
+
+/// Check whether a region with the given offset lies within an image of the given dimensions.
+fn region_fits(image: ImageDimension, region: ImageDimension, offset: (u32, u32, u32)) -> bool {
+    use self::ImageDimension as I;
+
+    match (image, region) {
+        (I::D1 { x: dx }, I::D1 { x: sx }) => (sx + offset.0) <= dx,
+        (I::D2 { x: dx, y: dy }, I::D2 { x: sx, y: sy }) => {
+            (sx + offset.0) <= dx && (sy + offset.1) <= dy
+        }
+        (
+            I::D3 {
+                x: dx,
+                y: dy,
+                z: dz,
+            },
+            I::D3 {
+                x: sx,
+                y: sy,
+                z: sz,
+            },
+        ) => (sx + offset.0) <= dx && (sy + offset.1) <= dy && (sz + offset.2) <= dz,
+        _ => false,
+    }
+}
+
+/// Check whether a region starts and ends on block boundaries of the given format.
+///
+/// Regions reaching the edge of the image may end in a partial block.
+fn region_is_block_aligned(
+    format: ImageFormat,
+    image: ImageDimension,
+    (width, height): (u32, u32),
+    offset: (u32, u32, u32),
+) -> bool {
+    let (block_width, block_height) = format.block_extent();
+    let (image_width, image_height, _) = image.as_triple(1);
+
+    let aligned = |start: u32, size: u32, end: u32, block: u32| {
+        start % block == 0 && (size % block == 0 || start + size == end)
+    };
+
+    aligned(offset.0, width, image_width, block_width)
+        && aligned(offset.1, height, image_height, block_height)
+}
+
+/// Memory layout of a buffer used for copying data between buffers and images.
+struct BufferCopyLayout {
+    /// Size in bytes of a single block (or texel for uncompressed formats).
+    block_size: u32,
+    /// Number of blocks in a row of the copied region.
+    row_blocks: u32,
+    /// Number of block rows of the copied region.
+    rows: u32,
+    /// Distance in bytes between the starts of two rows in the buffer.
+    row_pitch: u32,
+    /// Row length of the buffer in texels, as expected by the copy commands.
+    buffer_width: u32,
+    /// Height of the buffer in texels, as expected by the copy commands.
+    buffer_height: u32,
+}
+
+impl BufferCopyLayout {
+    fn buffer_size(&self) -> u64 {
+        u64::from(self.rows) * u64::from(self.row_pitch)
+    }
+}
+
+/// Compute the layout of a buffer that should be used to copy data
+/// between a buffer and an image region with the given format and dimensions.
+fn image_copy_buffer_layout(
     row_align: u32,
-    upload_info: &ImageUploadInfo,
+    format: gfx::format::Format,
     (width, height): (u32, u32),
-) -> (u64, u32, usize) {
-    let texel_size = upload_info.data.len() / (width * height) as usize;
+) -> BufferCopyLayout {
+    let desc = format.surface_desc();
+
+    let block_size = u32::from(desc.bits / 8);
+    let (block_width, block_height) = (u32::from(desc.dim.0), u32::from(desc.dim.1));
+
+    // Compressed formats store blocks of texels (for example 4x4 for BCn), the copy is
+    // performed in whole blocks, so partial blocks at the edges count as full ones.
+    let row_blocks = (width + block_width - 1) / block_width;
+    let rows = (height + block_height - 1) / block_height;
 
     // Because low level graphics are low level, we need to take care about buffer
     // alignment here.
     //
-    // For example an RGBA8 image with 11 * 11 dims
+    // For example an RGBA8 image with 11 * 10 dims
     // has
-    //  - "texel_size" of 4 (4 components (rgba) with 1 byte size)
+    //  - "block_size" of 4 (4 components (rgba) with 1 byte size)
     //  - "width" of 11
     //  - "height" of 10
     //
@@ -769,14 +1383,111 @@ fn image_copy_buffer_size(
     // `offset_of(x, y + 1) = offset_of(x, y) + n * alignment`
     // (I strongly assume that that's because of SIMD operations)
     //
+    // The copy commands take the row length in texels, not in bytes, so the padding also
+    // has to be a multiple of the block size. That's why whole blocks are added until the
+    // alignment fits (with 3 byte texels a row of 4 bytes couldn't be described otherwise).
+    let row_align = row_align.max(1);
+
+    let mut buffer_row_blocks = row_blocks;
+    while (buffer_row_blocks * block_size) % row_align != 0 {
+        buffer_row_blocks += 1;
+    }
+
+    BufferCopyLayout {
+        block_size,
+        row_blocks,
+        rows,
+        row_pitch: buffer_row_blocks * block_size,
+        buffer_width: buffer_row_blocks * block_width,
+        buffer_height: rows * block_height,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layer_counts_match_view_kinds() {
+        assert!(layers_fit_kind(ViewKind::D2, 1));
+        assert!(!layers_fit_kind(ViewKind::D2, 6));
+        assert!(layers_fit_kind(ViewKind::D2Array, 3));
+        assert!(layers_fit_kind(ViewKind::Cube, 6));
+        assert!(!layers_fit_kind(ViewKind::Cube, 12));
+        assert!(layers_fit_kind(ViewKind::CubeArray, 12));
+        assert!(!layers_fit_kind(ViewKind::CubeArray, 8));
+    }
 
-    // This mask says how many bits from the right need to be 0
-    let row_alignment_mask = row_align as u32 - 1;
+    #[test]
+    fn copy_layout_pads_rows() {
+        let layout = image_copy_buffer_layout(8, ImageFormat::RgbaUnorm.into(), (11, 10));
+
+        assert_eq!(layout.row_blocks, 11);
+        assert_eq!(layout.rows, 10);
+        assert_eq!(layout.row_pitch, 48);
+        assert_eq!(layout.buffer_width, 12);
+        assert_eq!(layout.buffer_size(), 480);
+
+        // 3 byte texels can only be padded with whole texels.
+        let layout = image_copy_buffer_layout(4, ImageFormat::RgbUnorm.into(), (1, 1));
+
+        assert_eq!(layout.row_pitch, 12);
+        assert_eq!(layout.buffer_width, 4);
+    }
 
-    // We add the alignment mask, then cut away everything stuff on the right so it's all 0s
-    let row_pitch = (width * texel_size as u32 + row_alignment_mask) & !row_alignment_mask;
+    #[test]
+    fn copy_layout_counts_blocks() {
+        let layout = image_copy_buffer_layout(1, ImageFormat::Bc7Unorm.into(), (10, 6));
+
+        assert_eq!(layout.block_size, 16);
+        assert_eq!(layout.row_blocks, 3);
+        assert_eq!(layout.rows, 2);
+        assert_eq!(layout.row_pitch, 48);
+        assert_eq!(layout.buffer_width, 12);
+        assert_eq!(layout.buffer_height, 8);
+
+        assert!(ImageFormat::Bc5Unorm.is_compressed());
+        assert!(!ImageFormat::RgbaUnorm.is_compressed());
+        assert_eq!(ImageFormat::Bc1RgbaUnorm.data_size((5, 5)), 4 * 8);
+        assert_eq!(
+            ImageFormat::Bc1RgbaUnorm.data_size((u32::max_value(), 4)),
+            (1 << 30) * 8
+        );
+    }
 
-    let buffer_size = u64::from(height * row_pitch);
+    #[test]
+    fn compressed_regions_need_block_alignment() {
+        let image = ImageDimension::D2 { x: 10, y: 10 };
 
-    (buffer_size, row_pitch, texel_size)
+        assert!(region_is_block_aligned(
+            ImageFormat::Bc7Unorm,
+            image,
+            (4, 4),
+            (4, 0, 0)
+        ));
+        assert!(region_is_block_aligned(
+            ImageFormat::Bc7Unorm,
+            image,
+            (2, 10),
+            (8, 0, 0)
+        ));
+        assert!(!region_is_block_aligned(
+            ImageFormat::Bc7Unorm,
+            image,
+            (4, 4),
+            (2, 0, 0)
+        ));
+        assert!(!region_is_block_aligned(
+            ImageFormat::Bc7Unorm,
+            image,
+            (3, 4),
+            (0, 0, 0)
+        ));
+        assert!(region_is_block_aligned(
+            ImageFormat::RgbaUnorm,
+            image,
+            (3, 5),
+            (1, 1, 0)
+        ));
+    }
 }
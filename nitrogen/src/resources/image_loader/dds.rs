@@ -0,0 +1,347 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Parsing of DDS files, including the DX10 header extension.
+
+use super::{read_u32, sub_slice, ImageFile, Layout, LoadError};
+
+use crate::image::ImageFormat;
+
+pub(crate) const MAGIC: [u8; 4] = *b"DDS ";
+
+const HEADER_SIZE: u32 = 124;
+const DX10_HEADER_SIZE: usize = 20;
+
+const DDSD_MIPMAPCOUNT: u32 = 0x2_0000;
+
+const DDPF_ALPHAPIXELS: u32 = 0x1;
+const DDPF_FOURCC: u32 = 0x4;
+const DDPF_RGB: u32 = 0x40;
+
+const DDSCAPS2_CUBEMAP: u32 = 0x200;
+const DDSCAPS2_VOLUME: u32 = 0x20_0000;
+
+const DX10_DIMENSION_TEXTURE1D: u32 = 2;
+const DX10_DIMENSION_TEXTURE3D: u32 = 4;
+const DX10_MISC_TEXTURECUBE: u32 = 0x4;
+
+/// Parse a DDS file.
+///
+/// Legacy cube maps are expected to contain all six faces.
+pub fn load_dds(data: &[u8]) -> Result<ImageFile, LoadError> {
+    if !data.starts_with(&MAGIC) {
+        return Err(LoadError::UnknownContainer);
+    }
+
+    if read_u32(data, 4)? != HEADER_SIZE {
+        return Err(LoadError::InvalidHeader);
+    }
+
+    let flags = read_u32(data, 8)?;
+    let height = read_u32(data, 12)?;
+    let width = read_u32(data, 16)?;
+    let mip_count = read_u32(data, 28)?;
+
+    let pixel_flags = read_u32(data, 80)?;
+    let four_cc = read_u32(data, 84)?;
+
+    let caps2 = read_u32(data, 112)?;
+
+    let levels = if flags & DDSD_MIPMAPCOUNT != 0 {
+        mip_count.max(1)
+    } else {
+        1
+    };
+
+    let data_offset = 4 + HEADER_SIZE as usize;
+
+    let (layout, data_offset) = if pixel_flags & DDPF_FOURCC != 0 && four_cc == fourcc(b"DX10") {
+        let dxgi_format = read_u32(data, data_offset)?;
+        let dimension = read_u32(data, data_offset + 4)?;
+        let misc_flags = read_u32(data, data_offset + 8)?;
+        let array_size = read_u32(data, data_offset + 12)?;
+
+        if dimension == DX10_DIMENSION_TEXTURE3D {
+            return Err(LoadError::VolumeImage);
+        }
+
+        let format =
+            format_from_dxgi(dxgi_format).ok_or(LoadError::UnsupportedFormat(dxgi_format))?;
+
+        let layout = Layout {
+            format,
+            width,
+            height: if dimension == DX10_DIMENSION_TEXTURE1D {
+                0
+            } else {
+                height
+            },
+            levels,
+            elements: array_size.max(1),
+            faces: if misc_flags & DX10_MISC_TEXTURECUBE != 0 {
+                6
+            } else {
+                1
+            },
+        };
+
+        (layout, data_offset + DX10_HEADER_SIZE)
+    } else {
+        if caps2 & DDSCAPS2_VOLUME != 0 {
+            return Err(LoadError::VolumeImage);
+        }
+
+        let format = legacy_format(data, pixel_flags, four_cc)?;
+
+        let layout = Layout {
+            format,
+            width,
+            height,
+            levels,
+            elements: 1,
+            faces: if caps2 & DDSCAPS2_CUBEMAP != 0 { 6 } else { 1 },
+        };
+
+        (layout, data_offset)
+    };
+
+    let create_info = layout.create_info()?;
+
+    // Unlike KTX2, DDS files store the complete mip chain of one layer after the other.
+    let mut uploads = vec![];
+    let mut offset = data_offset;
+
+    for layer in 0..layout.layers() as u16 {
+        for level in 0..layout.levels as u8 {
+            let size = layout.level_size(level);
+
+            let level_data = sub_slice(data, offset, size)?;
+            offset += size;
+
+            uploads.push(layout.upload(level_data, level, layer));
+        }
+    }
+
+    Ok(ImageFile {
+        create_info,
+        uploads,
+    })
+}
+
+fn fourcc(code: &[u8; 4]) -> u32 {
+    u32::from_le_bytes(*code)
+}
+
+/// Determine the format of a file without DX10 header.
+fn legacy_format(data: &[u8], pixel_flags: u32, four_cc: u32) -> Result<ImageFormat, LoadError> {
+    if pixel_flags & DDPF_FOURCC != 0 {
+        let format = match &four_cc.to_le_bytes() {
+            b"DXT1" => ImageFormat::Bc1RgbaUnorm,
+            b"DXT5" => ImageFormat::Bc3Unorm,
+            b"ATI1" | b"BC4U" => ImageFormat::Bc4Unorm,
+            b"ATI2" | b"BC5U" => ImageFormat::Bc5Unorm,
+            _ => return Err(LoadError::UnsupportedFormat(four_cc)),
+        };
+
+        return Ok(format);
+    }
+
+    if pixel_flags & DDPF_RGB == 0 {
+        return Err(LoadError::InvalidHeader);
+    }
+
+    let bit_count = read_u32(data, 88)?;
+    let red_mask = read_u32(data, 92)?;
+    let alpha_mask = if pixel_flags & DDPF_ALPHAPIXELS != 0 {
+        read_u32(data, 104)?
+    } else {
+        0
+    };
+
+    match (bit_count, red_mask, alpha_mask) {
+        (32, 0x0000_00ff, 0xff00_0000) => Ok(ImageFormat::RgbaUnorm),
+        (32, 0x00ff_0000, 0xff00_0000) => Ok(ImageFormat::BgraUnorm),
+        _ => Err(LoadError::UnsupportedFormat(0)),
+    }
+}
+
+/// Map a `DXGI_FORMAT` value to the matching image format.
+fn format_from_dxgi(format: u32) -> Option<ImageFormat> {
+    let format = match format {
+        61 => ImageFormat::RUnorm,
+        49 => ImageFormat::RgUnorm,
+        28 => ImageFormat::RgbaUnorm,
+        29 => ImageFormat::RgbaSrgb,
+        87 => ImageFormat::BgraUnorm,
+        91 => ImageFormat::BgraSrgb,
+
+        54 => ImageFormat::R16Float,
+        34 => ImageFormat::Rg16Float,
+        10 => ImageFormat::Rgba16Float,
+
+        42 => ImageFormat::R32Uint,
+        41 => ImageFormat::R32Float,
+        2 => ImageFormat::Rgba32Float,
+
+        26 => ImageFormat::B10g11r11Float,
+        67 => ImageFormat::E5b9g9r9Float,
+
+        55 => ImageFormat::D16Unorm,
+        45 => ImageFormat::D24UnormS8Uint,
+        40 => ImageFormat::D32Float,
+
+        71 => ImageFormat::Bc1RgbaUnorm,
+        72 => ImageFormat::Bc1RgbaSrgb,
+        77 => ImageFormat::Bc3Unorm,
+        78 => ImageFormat::Bc3Srgb,
+        80 => ImageFormat::Bc4Unorm,
+        83 => ImageFormat::Bc5Unorm,
+        95 => ImageFormat::Bc6hUfloat,
+        98 => ImageFormat::Bc7Unorm,
+        99 => ImageFormat::Bc7Srgb,
+
+        _ => return None,
+    };
+
+    Some(format)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::image::ViewKind;
+
+    /// Build a DDS header, optionally followed by a DX10 header.
+    fn dds_header(
+        (width, height): (u32, u32),
+        mip_count: u32,
+        pixel_flags: u32,
+        four_cc: &[u8; 4],
+        caps2: u32,
+        dx10: Option<[u32; 5]>,
+    ) -> Vec<u8> {
+        let mut header = vec![0; 4 + HEADER_SIZE as usize];
+
+        let mut write = |offset: usize, value: u32| {
+            header[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+        };
+
+        write(4, HEADER_SIZE);
+        write(8, DDSD_MIPMAPCOUNT);
+        write(12, height);
+        write(16, width);
+        write(28, mip_count);
+        write(76, 32);
+        write(80, pixel_flags);
+        write(84, fourcc(four_cc));
+        write(112, caps2);
+
+        header[..4].copy_from_slice(&MAGIC);
+
+        if let Some(dx10) = dx10 {
+            for value in &dx10 {
+                header.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+
+        header
+    }
+
+    #[test]
+    fn legacy_mip_chain_is_loaded() {
+        let mut file = dds_header((8, 8), 3, DDPF_FOURCC, b"DXT1", 0, None);
+
+        // 8x8, 4x4 and 2x2 levels with 8 byte blocks
+        file.extend_from_slice(&[0; 4 * 8]);
+        file.extend_from_slice(&[1; 8]);
+        file.extend_from_slice(&[2; 8]);
+
+        let image = load_dds(&file).unwrap();
+
+        assert_eq!(image.create_info.format, ImageFormat::Bc1RgbaUnorm);
+        assert_eq!(image.create_info.num_mipmaps, 3);
+        assert_eq!(image.create_info.kind, ViewKind::D2);
+
+        assert_eq!(image.uploads.len(), 3);
+        assert_eq!(image.uploads[0].data.len(), 32);
+        assert_eq!(image.uploads[1].data, &[1; 8][..]);
+        assert_eq!(image.uploads[2].data, &[2; 8][..]);
+        assert_eq!(image.uploads[2].dimension.as_triple(1), (2, 2, 1));
+    }
+
+    #[test]
+    fn dx10_array_is_stored_layer_by_layer() {
+        let dx10 = [83, 3, 0, 2, 0];
+        let mut file = dds_header((4, 4), 2, DDPF_FOURCC, b"DX10", 0, Some(dx10));
+
+        // per layer: one 4x4 block for level 0 and one partial block for level 1
+        for layer in 0..2u8 {
+            file.extend_from_slice(&[layer * 2; 16]);
+            file.extend_from_slice(&[layer * 2 + 1; 16]);
+        }
+
+        let image = crate::resources::image_loader::load(&file).unwrap();
+
+        assert_eq!(image.create_info.format, ImageFormat::Bc5Unorm);
+        assert_eq!(image.create_info.kind, ViewKind::D2Array);
+        assert_eq!(image.create_info.num_layers, 2);
+
+        let uploads = image
+            .uploads
+            .iter()
+            .map(|upload| (upload.layer, upload.level, upload.data[0]))
+            .collect::<Vec<_>>();
+
+        assert_eq!(uploads, vec![(0, 0, 0), (0, 1, 1), (1, 0, 2), (1, 1, 3)]);
+    }
+
+    #[test]
+    fn legacy_cube_and_rgb_masks() {
+        let mut file = dds_header(
+            (1, 1),
+            1,
+            DDPF_RGB | DDPF_ALPHAPIXELS,
+            &[0; 4],
+            DDSCAPS2_CUBEMAP,
+            None,
+        );
+
+        file[88..92].copy_from_slice(&32u32.to_le_bytes());
+        file[92..96].copy_from_slice(&0x00ff_0000u32.to_le_bytes());
+        file[104..108].copy_from_slice(&0xff00_0000u32.to_le_bytes());
+
+        file.extend_from_slice(&[0; 6 * 4]);
+
+        let image = load_dds(&file).unwrap();
+
+        assert_eq!(image.create_info.format, ImageFormat::BgraUnorm);
+        assert_eq!(image.create_info.kind, ViewKind::Cube);
+        assert_eq!(image.uploads.len(), 6);
+
+        file.pop();
+        assert_eq!(load_dds(&file).err(), Some(LoadError::Truncated));
+    }
+
+    #[test]
+    fn unsupported_files_are_rejected() {
+        let file = dds_header((4, 4), 1, DDPF_FOURCC, b"DXT3", 0, None);
+        assert_eq!(
+            load_dds(&file).err(),
+            Some(LoadError::UnsupportedFormat(fourcc(b"DXT3")))
+        );
+
+        let file = dds_header((4, 4), 1, DDPF_FOURCC, b"DXT1", DDSCAPS2_VOLUME, None);
+        assert_eq!(load_dds(&file).err(), Some(LoadError::VolumeImage));
+
+        let dx10 = [98, DX10_DIMENSION_TEXTURE3D, 0, 1, 0];
+        let file = dds_header((4, 4), 1, DDPF_FOURCC, b"DX10", 0, Some(dx10));
+        assert_eq!(load_dds(&file).err(), Some(LoadError::VolumeImage));
+
+        assert_eq!(
+            crate::resources::image_loader::load(b"not an image").err(),
+            Some(LoadError::UnknownContainer)
+        );
+    }
+}
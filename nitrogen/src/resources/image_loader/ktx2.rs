@@ -0,0 +1,264 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Parsing of KTX2 files.
+
+use super::{read_u32, read_u64, sub_slice, ImageFile, Layout, LoadError};
+
+use crate::image::ImageFormat;
+
+pub(crate) const IDENTIFIER: [u8; 12] = [
+    0xAB, b'K', b'T', b'X', b' ', b'2', b'0', 0xBB, b'\r', b'\n', 0x1A, b'\n',
+];
+
+/// Offset of the level index, which follows the header and the section index.
+const LEVEL_INDEX_OFFSET: usize = 80;
+/// Size of one level index entry (byte offset, byte length, uncompressed byte length).
+const LEVEL_INDEX_ENTRY_SIZE: usize = 24;
+
+/// Parse a KTX2 file.
+///
+/// The data of every level is stored as all layers and faces one after another,
+/// so the uploads borrow directly from `data`.
+pub fn load_ktx2(data: &[u8]) -> Result<ImageFile, LoadError> {
+    if !data.starts_with(&IDENTIFIER) {
+        return Err(LoadError::UnknownContainer);
+    }
+
+    let vk_format = read_u32(data, 12)?;
+    let width = read_u32(data, 20)?;
+    let height = read_u32(data, 24)?;
+    let depth = read_u32(data, 28)?;
+    let layer_count = read_u32(data, 32)?;
+    let face_count = read_u32(data, 36)?;
+    let level_count = read_u32(data, 40)?;
+    let supercompression = read_u32(data, 44)?;
+
+    if supercompression != 0 {
+        return Err(LoadError::Supercompressed);
+    }
+
+    if depth > 0 {
+        return Err(LoadError::VolumeImage);
+    }
+
+    let format = format_from_vk(vk_format).ok_or(LoadError::UnsupportedFormat(vk_format))?;
+
+    // A level count of zero asks for the mipmaps to be generated at load time,
+    // only the base level is stored in that case.
+    let layout = Layout {
+        format,
+        width,
+        height,
+        levels: level_count.max(1),
+        elements: layer_count.max(1),
+        faces: face_count,
+    };
+
+    let create_info = layout.create_info()?;
+
+    let mut uploads = vec![];
+
+    for level in 0..layout.levels as u8 {
+        let entry = LEVEL_INDEX_OFFSET + usize::from(level) * LEVEL_INDEX_ENTRY_SIZE;
+
+        let offset = read_u64(data, entry)? as usize;
+        let length = read_u64(data, entry + 8)? as usize;
+
+        let level_data = sub_slice(data, offset, length)?;
+
+        let layer_size = layout.level_size(level);
+
+        if level_data.len() / layer_size < layout.layers() as usize {
+            return Err(LoadError::Truncated);
+        }
+
+        let layers = level_data.chunks(layer_size).take(layout.layers() as usize);
+
+        for (layer, layer_data) in layers.enumerate() {
+            uploads.push(layout.upload(layer_data, level, layer as u16));
+        }
+    }
+
+    Ok(ImageFile {
+        create_info,
+        uploads,
+    })
+}
+
+/// Map a `VkFormat` value to the matching image format.
+fn format_from_vk(format: u32) -> Option<ImageFormat> {
+    let format = match format {
+        9 => ImageFormat::RUnorm,
+        16 => ImageFormat::RgUnorm,
+        23 => ImageFormat::RgbUnorm,
+        29 => ImageFormat::RgbSrgb,
+        37 => ImageFormat::RgbaUnorm,
+        43 => ImageFormat::RgbaSrgb,
+        44 => ImageFormat::BgraUnorm,
+        50 => ImageFormat::BgraSrgb,
+
+        76 => ImageFormat::R16Float,
+        83 => ImageFormat::Rg16Float,
+        97 => ImageFormat::Rgba16Float,
+
+        98 => ImageFormat::R32Uint,
+        100 => ImageFormat::R32Float,
+        109 => ImageFormat::Rgba32Float,
+
+        122 => ImageFormat::B10g11r11Float,
+        123 => ImageFormat::E5b9g9r9Float,
+
+        124 => ImageFormat::D16Unorm,
+        126 => ImageFormat::D32Float,
+        129 => ImageFormat::D24UnormS8Uint,
+        130 => ImageFormat::D32FloatS8Uint,
+
+        133 => ImageFormat::Bc1RgbaUnorm,
+        134 => ImageFormat::Bc1RgbaSrgb,
+        137 => ImageFormat::Bc3Unorm,
+        138 => ImageFormat::Bc3Srgb,
+        139 => ImageFormat::Bc4Unorm,
+        141 => ImageFormat::Bc5Unorm,
+        143 => ImageFormat::Bc6hUfloat,
+        145 => ImageFormat::Bc7Unorm,
+        146 => ImageFormat::Bc7Srgb,
+
+        147 => ImageFormat::Etc2RgbUnorm,
+        148 => ImageFormat::Etc2RgbSrgb,
+        151 => ImageFormat::Etc2RgbaUnorm,
+        152 => ImageFormat::Etc2RgbaSrgb,
+
+        157 => ImageFormat::Astc4x4Unorm,
+        158 => ImageFormat::Astc4x4Srgb,
+
+        _ => return None,
+    };
+
+    Some(format)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::image::ViewKind;
+
+    /// Build a KTX2 file with the given header values and level data.
+    fn ktx2_file(
+        vk_format: u32,
+        (width, height): (u32, u32),
+        layers: u32,
+        faces: u32,
+        supercompression: u32,
+        levels: &[Vec<u8>],
+    ) -> Vec<u8> {
+        let mut header = IDENTIFIER.to_vec();
+
+        let values = [
+            vk_format,
+            1,
+            width,
+            height,
+            0,
+            layers,
+            faces,
+            levels.len() as u32,
+            supercompression,
+        ];
+
+        for value in &values {
+            header.extend_from_slice(&value.to_le_bytes());
+        }
+
+        // empty DFD, KVD and SGD sections
+        header.resize(LEVEL_INDEX_OFFSET, 0);
+
+        let mut offset = LEVEL_INDEX_OFFSET + levels.len() * LEVEL_INDEX_ENTRY_SIZE;
+
+        for level in levels {
+            header.extend_from_slice(&(offset as u64).to_le_bytes());
+            header.extend_from_slice(&(level.len() as u64).to_le_bytes());
+            header.extend_from_slice(&(level.len() as u64).to_le_bytes());
+            offset += level.len();
+        }
+
+        for level in levels {
+            header.extend_from_slice(level);
+        }
+
+        header
+    }
+
+    #[test]
+    fn compressed_mip_chain_is_loaded() {
+        let levels = vec![vec![1; 4 * 16], vec![2; 16]];
+        let file = ktx2_file(145, (8, 8), 0, 1, 0, &levels);
+
+        let image = load_ktx2(&file).unwrap();
+
+        assert_eq!(image.create_info.format, ImageFormat::Bc7Unorm);
+        assert_eq!(image.create_info.num_mipmaps, 2);
+        assert_eq!(image.create_info.num_layers, 1);
+        assert_eq!(image.create_info.kind, ViewKind::D2);
+
+        assert_eq!(image.uploads.len(), 2);
+
+        assert_eq!(image.uploads[0].level, 0);
+        assert_eq!(image.uploads[0].dimension.as_triple(1), (8, 8, 1));
+        assert_eq!(image.uploads[0].data, &levels[0][..]);
+
+        assert_eq!(image.uploads[1].level, 1);
+        assert_eq!(image.uploads[1].dimension.as_triple(1), (4, 4, 1));
+        assert_eq!(image.uploads[1].data, &levels[1][..]);
+    }
+
+    #[test]
+    fn cube_array_faces_are_layers() {
+        let level = (0..12 * 16).map(|i| (i / 16) as u8).collect::<Vec<_>>();
+        let file = ktx2_file(37, (2, 2), 2, 6, 0, &[level]);
+
+        let image = crate::resources::image_loader::load(&file).unwrap();
+
+        assert_eq!(image.create_info.kind, ViewKind::CubeArray);
+        assert_eq!(image.create_info.num_layers, 12);
+        assert_eq!(image.uploads.len(), 12);
+
+        for (i, upload) in image.uploads.iter().enumerate() {
+            assert_eq!(upload.layer, i as u16);
+            assert_eq!(upload.data, &[i as u8; 16][..]);
+        }
+    }
+
+    #[test]
+    fn invalid_files_are_rejected() {
+        let supercompressed = ktx2_file(145, (4, 4), 0, 1, 1, &[vec![0; 16]]);
+        assert_eq!(
+            load_ktx2(&supercompressed).err(),
+            Some(LoadError::Supercompressed)
+        );
+
+        let unknown_format = ktx2_file(1000, (4, 4), 0, 1, 0, &[vec![0; 16]]);
+        assert_eq!(
+            load_ktx2(&unknown_format).err(),
+            Some(LoadError::UnsupportedFormat(1000))
+        );
+
+        let mut truncated = ktx2_file(145, (4, 4), 0, 1, 0, &[vec![0; 16]]);
+        truncated.pop();
+        assert_eq!(load_ktx2(&truncated).err(), Some(LoadError::Truncated));
+
+        let too_small = ktx2_file(145, (8, 4), 0, 1, 0, &[vec![0; 16]]);
+        assert_eq!(load_ktx2(&too_small).err(), Some(LoadError::Truncated));
+
+        let too_large = ktx2_file(145, (u32::max_value(), 4), 0, 1, 0, &[vec![0; 16]]);
+        assert_eq!(load_ktx2(&too_large).err(), Some(LoadError::InvalidHeader));
+
+        let too_many_levels = ktx2_file(145, (4, 4), 0, 1, 0, &vec![vec![0; 16]; 4]);
+        assert_eq!(
+            load_ktx2(&too_many_levels).err(),
+            Some(LoadError::InvalidHeader)
+        );
+    }
+}
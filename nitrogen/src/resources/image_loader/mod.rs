@@ -0,0 +1,190 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Loading of image data stored in KTX2 and DDS container files.
+//!
+//! The loader only parses the files, no device is involved. The resulting [`ImageFile`] contains
+//! everything needed to create an image with `Context::image_create` and to fill all of its
+//! mipmap levels and layers with `SubmitGroup::image_upload_data`.
+//!
+//! Supercompressed KTX2 files and 3D images are not supported.
+//!
+//! [`ImageFile`]: struct.ImageFile.html
+
+mod dds;
+mod ktx2;
+
+pub use self::dds::load_dds;
+pub use self::ktx2::load_ktx2;
+
+use crate::image::{
+    ImageCreateInfo, ImageDimension, ImageFormat, ImageUploadInfo, ImageUsage, Swizzle, ViewKind,
+};
+
+/// Errors that can occur while parsing an image container file.
+#[allow(missing_docs)]
+#[derive(Debug, Display, Clone, Copy, PartialEq)]
+pub enum LoadError {
+    #[display(fmt = "The data is not a KTX2 or DDS file")]
+    UnknownContainer,
+
+    #[display(fmt = "The file header is invalid")]
+    InvalidHeader,
+
+    #[display(fmt = "The file is truncated or contains invalid offsets")]
+    Truncated,
+
+    /// The format code stored in the file. This is a `VkFormat` for KTX2 files and a
+    /// DXGI format or FourCC code for DDS files.
+    #[display(fmt = "The format {} of the file is not supported", _0)]
+    UnsupportedFormat(u32),
+
+    #[display(fmt = "Supercompressed files are not supported")]
+    Supercompressed,
+
+    #[display(fmt = "3D images are not supported")]
+    VolumeImage,
+}
+
+impl std::error::Error for LoadError {}
+
+/// Image data parsed from a container file.
+pub struct ImageFile<'a> {
+    /// Description of an image that can hold all the data of the file.
+    ///
+    /// The image is usable as a transfer destination and for sampling. Other usages can be added
+    /// before creating the image.
+    pub create_info: ImageCreateInfo<ImageUsage>,
+    /// Uploads for every mipmap level and layer stored in the file.
+    ///
+    /// For cube images every face is a separate layer.
+    pub uploads: Vec<ImageUploadInfo<'a>>,
+}
+
+/// Parse a KTX2 or DDS file, depending on the identifier at the start of the data.
+pub fn load(data: &[u8]) -> Result<ImageFile, LoadError> {
+    if data.starts_with(&ktx2::IDENTIFIER) {
+        load_ktx2(data)
+    } else if data.starts_with(&dds::MAGIC) {
+        load_dds(data)
+    } else {
+        Err(LoadError::UnknownContainer)
+    }
+}
+
+/// Largest width and height accepted in a file header, which keeps all size computations of
+/// untrusted files far from overflowing.
+const MAX_DIMENSION: u32 = 1 << 16;
+
+/// Shape of the image stored in a container file.
+struct Layout {
+    format: ImageFormat,
+    /// Width of the base level.
+    width: u32,
+    /// Height of the base level, zero for 1D images.
+    height: u32,
+    levels: u32,
+    /// Number of array elements, each of which has `faces` layers.
+    elements: u32,
+    /// Number of faces, one for normal images and six for cube images.
+    faces: u32,
+}
+
+impl Layout {
+    fn dimension(&self) -> ImageDimension {
+        if self.height == 0 {
+            ImageDimension::D1 { x: self.width }
+        } else {
+            ImageDimension::D2 {
+                x: self.width,
+                y: self.height,
+            }
+        }
+    }
+
+    fn layers(&self) -> u32 {
+        self.elements.saturating_mul(self.faces)
+    }
+
+    /// Size in bytes of a single layer of the given mipmap level.
+    fn level_size(&self, level: u8) -> usize {
+        let (width, height, _) = self.dimension().mip_level(level).as_triple(1);
+        self.format.data_size((width, height))
+    }
+
+    fn create_info(&self) -> Result<ImageCreateInfo<ImageUsage>, LoadError> {
+        if self.width == 0 || self.width > MAX_DIMENSION || self.height > MAX_DIMENSION {
+            return Err(LoadError::InvalidHeader);
+        }
+
+        // The smallest level of a mip chain is one texel large.
+        let max_levels = 32 - self.width.max(self.height).leading_zeros();
+
+        if self.levels > max_levels {
+            return Err(LoadError::InvalidHeader);
+        }
+
+        if self.layers() > u32::from(u16::max_value()) {
+            return Err(LoadError::InvalidHeader);
+        }
+
+        let array = self.elements > 1;
+
+        let kind = match (self.faces, self.height) {
+            (6, _) if array => ViewKind::CubeArray,
+            (6, _) => ViewKind::Cube,
+            (1, 0) if array => ViewKind::D1Array,
+            (1, 0) => ViewKind::D1,
+            (1, _) if array => ViewKind::D2Array,
+            (1, _) => ViewKind::D2,
+            _ => return Err(LoadError::InvalidHeader),
+        };
+
+        Ok(ImageCreateInfo {
+            dimension: self.dimension(),
+            num_layers: self.layers() as u16,
+            num_samples: 1,
+            num_mipmaps: self.levels as u8,
+            format: self.format,
+            swizzle: Swizzle::NO,
+            kind,
+
+            usage: ImageUsage {
+                transfer_dst: true,
+                sampling: true,
+                ..Default::default()
+            },
+
+            is_transient: false,
+        })
+    }
+
+    fn upload<'a>(&self, data: &'a [u8], level: u8, layer: u16) -> ImageUploadInfo<'a> {
+        ImageUploadInfo {
+            data,
+            format: self.format,
+            dimension: self.dimension().mip_level(level),
+            target_offset: (0, 0, 0),
+            level,
+            layer,
+        }
+    }
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, LoadError> {
+    let mut bytes = [0; 4];
+    bytes.copy_from_slice(sub_slice(data, offset, 4)?);
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Result<u64, LoadError> {
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(sub_slice(data, offset, 8)?);
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn sub_slice(data: &[u8], offset: usize, len: usize) -> Result<&[u8], LoadError> {
+    let end = offset.checked_add(len).ok_or(LoadError::Truncated)?;
+    data.get(offset..end).ok_or(LoadError::Truncated)
+}
@@ -100,7 +100,7 @@ pub(crate) struct MaterialStorage {
 
 /// Type of material parameter.
 #[allow(missing_docs)]
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum MaterialParameterType {
     Sampler,
     SampledImage,
@@ -229,39 +229,36 @@ impl MaterialStorage {
         sets_per_pool: u8,
     ) -> Option<MaterialHandle>
     where
-        P: Iterator<Item = gfx::pso::DescriptorRangeDesc>,
+        P: Iterator<Item = (u32, gfx::pso::DescriptorType)>,
     {
+        let mut parameters = params
+            .map(|(binding, ty)| {
+                use gfx::pso::DescriptorType;
+
+                let ty = match ty {
+                    DescriptorType::Sampler => MaterialParameterType::Sampler,
+                    DescriptorType::SampledImage => MaterialParameterType::SampledImage,
+                    DescriptorType::StorageImage => MaterialParameterType::StorageImage,
+                    DescriptorType::UniformTexelBuffer => MaterialParameterType::UniformTexelBuffer,
+                    DescriptorType::StorageTexelBuffer => MaterialParameterType::StorageTexelBuffer,
+                    DescriptorType::UniformBuffer => MaterialParameterType::UniformBuffer,
+                    DescriptorType::StorageBuffer => MaterialParameterType::StorageBuffer,
+                    DescriptorType::UniformBufferDynamic => {
+                        MaterialParameterType::UniformBufferDynamic
+                    }
+                    DescriptorType::StorageBufferDynamic => {
+                        MaterialParameterType::StorageBufferDynamic
+                    }
+                    _ => unreachable!(),
+                };
+
+                (binding, ty)
+            })
+            .collect::<Vec<_>>();
+        parameters.sort_by_key(|(binding, _)| *binding);
+
         let mat = Material {
-            parameters: params
-                .map(|desc| {
-                    use gfx::pso::DescriptorType;
-                    // Since the binding is only used when creating a material
-                    // from a user-facing description, we can just put anything in here
-                    // since it will never be used afterwards.
-                    let ty = match desc.ty {
-                        DescriptorType::Sampler => MaterialParameterType::Sampler,
-                        DescriptorType::SampledImage => MaterialParameterType::SampledImage,
-                        DescriptorType::StorageImage => MaterialParameterType::StorageImage,
-                        DescriptorType::UniformTexelBuffer => {
-                            MaterialParameterType::UniformTexelBuffer
-                        }
-                        DescriptorType::StorageTexelBuffer => {
-                            MaterialParameterType::StorageTexelBuffer
-                        }
-                        DescriptorType::UniformBuffer => MaterialParameterType::UniformBuffer,
-                        DescriptorType::StorageBuffer => MaterialParameterType::StorageBuffer,
-                        DescriptorType::UniformBufferDynamic => {
-                            MaterialParameterType::UniformBufferDynamic
-                        }
-                        DescriptorType::StorageBufferDynamic => {
-                            MaterialParameterType::StorageBufferDynamic
-                        }
-                        _ => unreachable!(),
-                    };
-
-                    (0, ty)
-                })
-                .collect(),
+            parameters,
             sets_per_pool,
             desc_set_layout: layout,
             instances: Storage::new(),
@@ -378,6 +375,11 @@ impl MaterialStorage {
 }
 
 impl Material {
+    /// Binding and type of all parameters, sorted by binding.
+    pub(crate) fn parameters(&self) -> &[(u32, MaterialParameterType)] {
+        &self.parameters
+    }
+
     fn next_nonempty_pool(&self) -> Option<usize> {
         for (i, allocd) in self.pool_allocated.iter().enumerate() {
             if *allocd < self.sets_per_pool {
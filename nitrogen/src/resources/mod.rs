@@ -9,6 +9,8 @@ pub use super::*;
 pub mod buffer;
 pub(crate) mod command_pool;
 pub mod image;
+#[cfg(feature = "texture_loader")]
+pub mod image_loader;
 pub mod material;
 pub(crate) mod pipeline;
 pub(crate) mod render_pass;
@@ -5,7 +5,7 @@
 use crate::device::DeviceContext;
 use crate::storage::{Handle, Storage};
 
-use crate::graph::{BlendMode, DepthMode};
+use crate::graph::{BlendConstants, BlendDesc, DepthMode, StencilMode, StencilReference};
 use crate::render_pass::{RenderPassHandle, RenderPassStorage};
 use crate::vertex_attrib::VertexAttribResource;
 
@@ -33,6 +33,14 @@ pub enum PipelineError {
 
     #[display(fmt = "Ran out of memory: {}", _0)]
     OutOfMemory(gfx::device::OutOfMemory),
+
+    #[display(fmt = "Device features {:?} are required but not supported", _0)]
+    FeatureUnsupported(gfx::Features),
+
+    #[display(
+        fmt = "Tessellation needs both tessellation shaders and a patch list of a supported size"
+    )]
+    InvalidTessellation,
 }
 
 impl Error for PipelineError {}
@@ -67,18 +75,22 @@ pub(crate) struct ShaderInfo<'a> {
 #[derive(Clone)]
 pub(crate) struct GraphicsPipelineCreateInfo<'a> {
     pub(crate) primitive: crate::graph::Primitive,
+    pub(crate) rasterizer: crate::graph::Rasterizer,
 
     pub(crate) vertex_attribs: Option<VertexAttribResource>,
 
     pub(crate) descriptor_set_layout: &'a [&'a types::DescriptorSetLayout],
     // TODO shader stage flags
     pub(crate) push_constants: &'a [std::ops::Range<u32>],
-    pub(crate) blend_modes: &'a [BlendMode],
+    pub(crate) blend: &'a BlendDesc,
     pub(crate) depth_mode: Option<DepthMode>,
-    // TODO stencil mode
+    pub(crate) stencil_mode: Option<StencilMode>,
+    pub(crate) samples: u8,
     pub(crate) shader_vertex: ShaderInfo<'a>,
     pub(crate) shader_fragment: Option<ShaderInfo<'a>>,
     pub(crate) shader_geometry: Option<ShaderInfo<'a>>,
+    pub(crate) shader_tess_control: Option<ShaderInfo<'a>>,
+    pub(crate) shader_tess_eval: Option<ShaderInfo<'a>>,
 }
 
 #[derive(Clone)]
@@ -93,15 +105,60 @@ pub(crate) struct PipelineStorage {
     graphic_pipelines: BTreeMap<usize, GraphicsPipeline>,
     compute_pipelines: BTreeMap<usize, ComputePipeline>,
     storage: Storage<Pipeline>,
+    cache: types::PipelineCache,
 }
 
 impl PipelineStorage {
-    pub(crate) fn new() -> Self {
+    /// Create a new storage with a pipeline cache, optionally filled with previously saved data.
+    ///
+    /// Data saved on a different adapter or driver is discarded.
+    ///
+    /// Panics if not even an empty cache can be created. Creating a pipeline cache only fails
+    /// when the device is out of memory, which context creation treats like failing to open
+    /// the logical device.
+    pub(crate) unsafe fn new(device: &DeviceContext, cache_data: Option<&[u8]>) -> Self {
+        let (vendor, device_id) = (
+            device.adapter.info.vendor as u32,
+            device.adapter.info.device as u32,
+        );
+
+        let cache_data = cache_data.filter(|data| cache_is_compatible(data, vendor, device_id));
+
+        // If the driver still rejects the data it is better to start with an empty cache
+        // than to not have a cache at all.
+        let cache = match device.device.create_pipeline_cache(cache_data) {
+            Ok(cache) => cache,
+            Err(_) => device
+                .device
+                .create_pipeline_cache(None)
+                .expect("Can't create pipeline cache"),
+        };
+
         PipelineStorage {
             storage: Storage::new(),
             graphic_pipelines: BTreeMap::new(),
             compute_pipelines: BTreeMap::new(),
+            cache,
+        }
+    }
+
+    pub(crate) unsafe fn release(self, device: &DeviceContext) {
+        for (_, pipeline) in self.graphic_pipelines {
+            device.device.destroy_graphics_pipeline(pipeline.pipeline);
+            device.device.destroy_pipeline_layout(pipeline.layout);
         }
+
+        for (_, pipeline) in self.compute_pipelines {
+            device.device.destroy_compute_pipeline(pipeline.pipeline);
+            device.device.destroy_pipeline_layout(pipeline.layout);
+        }
+
+        device.device.destroy_pipeline_cache(self.cache);
+    }
+
+    /// Retrieve the data of the pipeline cache, which can be used to create a storage later.
+    pub(crate) unsafe fn cache_data(&self, device: &DeviceContext) -> Result<Vec<u8>> {
+        Ok(device.device.get_pipeline_cache_data(&self.cache)?)
     }
 
     pub(crate) unsafe fn create_graphics_pipeline(
@@ -111,10 +168,28 @@ impl PipelineStorage {
         render_pass_handle: RenderPassHandle,
         create_info: GraphicsPipelineCreateInfo,
     ) -> Result<PipelineHandle> {
+        let max_patch_size = device.adapter.physical_device.limits().max_patch_size;
+
+        if !is_tessellation_valid(&create_info, max_patch_size) {
+            return Err(PipelineError::InvalidTessellation);
+        }
+
+        {
+            let required = required_features(&create_info);
+
+            if !device.features.contains(required) {
+                return Err(PipelineError::FeatureUnsupported(
+                    required - device.features,
+                ));
+            }
+        }
+
         struct ShaderModules {
             vertex: ShaderModule,
             fragment: Option<ShaderModule>,
             geometry: Option<ShaderModule>,
+            tess_control: Option<ShaderModule>,
+            tess_eval: Option<ShaderModule>,
         }
 
         let module = ShaderModules {
@@ -134,6 +209,18 @@ impl PipelineStorage {
             } else {
                 None
             },
+
+            tess_control: if let Some(ref tesc) = create_info.shader_tess_control {
+                Some(device.device.create_shader_module(tesc.content)?)
+            } else {
+                None
+            },
+
+            tess_eval: if let Some(ref tese) = create_info.shader_tess_eval {
+                Some(device.device.create_shader_module(tese.content)?)
+            } else {
+                None
+            },
         };
 
         let layout = device.device.create_pipeline_layout(
@@ -149,6 +236,8 @@ impl PipelineStorage {
                 vertex: pso::EntryPoint<'a, back::Backend>,
                 fragment: Option<pso::EntryPoint<'a, back::Backend>>,
                 geometry: Option<pso::EntryPoint<'a, back::Backend>>,
+                tess_control: Option<pso::EntryPoint<'a, back::Backend>>,
+                tess_eval: Option<pso::EntryPoint<'a, back::Backend>>,
             };
 
             let shader_entries = {
@@ -183,20 +272,63 @@ impl PipelineStorage {
                                 data: Cow::Borrowed(&[]),
                             },
                         }),
+                    tess_control: create_info.shader_tess_control.as_ref().map(|s| {
+                        pso::EntryPoint {
+                            entry: s.entry,
+                            module: module.tess_control.as_ref().unwrap(),
+                            specialization: pso::Specialization {
+                                constants: Cow::Borrowed(&[]),
+                                data: Cow::Borrowed(&[]),
+                            },
+                        }
+                    }),
+                    tess_eval: create_info
+                        .shader_tess_eval
+                        .as_ref()
+                        .map(|s| pso::EntryPoint {
+                            entry: s.entry,
+                            module: module.tess_eval.as_ref().unwrap(),
+                            specialization: pso::Specialization {
+                                constants: Cow::Borrowed(&[]),
+                                data: Cow::Borrowed(&[]),
+                            },
+                        }),
                 }
             };
 
             let shaders = pso::GraphicsShaderSet {
                 vertex: shader_entries.vertex,
-                hull: None,
-                domain: None,
+                hull: shader_entries.tess_control,
+                domain: shader_entries.tess_eval,
                 geometry: shader_entries.geometry,
                 fragment: shader_entries.fragment,
             };
 
             let primitive = create_info.primitive.into();
 
-            let rasterizer = { pso::Rasterizer::FILL };
+            let rasterizer = {
+                use crate::graph::PolygonMode;
+
+                let raster = create_info.rasterizer;
+
+                pso::Rasterizer {
+                    polygon_mode: match raster.polygon_mode {
+                        PolygonMode::Fill => pso::PolygonMode::Fill,
+                        PolygonMode::Line => {
+                            pso::PolygonMode::Line(pso::State::Static(raster.line_width))
+                        }
+                        PolygonMode::Point => pso::PolygonMode::Point,
+                    },
+                    cull_face: raster.cull_mode.into(),
+                    front_face: raster.front_face.into(),
+                    depth_clamping: raster.depth_clamping,
+                    depth_bias: create_info
+                        .depth_mode
+                        .and_then(|depth| depth.bias)
+                        .map(|bias| pso::State::Static(bias.into())),
+                    conservative: false,
+                }
+            };
 
             let render_pass = render_pass_storage.raw(render_pass_handle).unwrap();
 
@@ -220,24 +352,33 @@ impl PipelineStorage {
                 desc.attributes.extend_from_slice(&data.attribs[..]);
             }
 
-            // TODO allow for finer control over blend modes?
+            desc.blender.logic_op = create_info.blend.logic_op.map(Into::into);
             desc.blender
                 .targets
-                .extend(create_info.blend_modes.iter().map(|mode| {
-                    pso::ColorBlendDesc(
-                        pso::ColorMask::ALL,
-                        match mode {
-                            BlendMode::Add => pso::BlendState::ADD,
-                            BlendMode::Alpha => pso::BlendState::ALPHA,
-                            BlendMode::Mul => pso::BlendState::MULTIPLY,
-                        },
-                    )
-                }));
+                .extend(create_info.blend.targets.iter().map(|&state| state.into()));
+
+            desc.baked_states.blend_color = match create_info.blend.constants {
+                BlendConstants::Static(constants) => Some(constants),
+                BlendConstants::Dynamic => None,
+            };
+
+            if create_info.samples > 1 {
+                desc.multisampling = Some(pso::Multisampling {
+                    rasterization_samples: create_info.samples,
+                    sample_shading: None,
+                    sample_mask: !0,
+                    alpha_coverage: false,
+                    alpha_to_one: false,
+                });
+            }
 
             // depth and stencil
             {
-                // TODO implement stencil
-                desc.depth_stencil.stencil = gfx::pso::StencilTest::default();
+                desc.depth_stencil.stencil = if let Some(stencil) = create_info.stencil_mode {
+                    stencil_test(stencil)
+                } else {
+                    gfx::pso::StencilTest::Off
+                };
 
                 desc.depth_stencil.depth = if let Some(depth) = create_info.depth_mode {
                     gfx::pso::DepthTest::On {
@@ -247,10 +388,16 @@ impl PipelineStorage {
                 } else {
                     gfx::pso::DepthTest::Off
                 };
-                desc.depth_stencil.depth_bounds = false;
+
+                let bounds = create_info.depth_mode.and_then(|depth| depth.bounds);
+
+                desc.depth_stencil.depth_bounds = bounds.is_some();
+                desc.baked_states.depth_bounds = bounds.map(|bounds| bounds.min..bounds.max);
             }
 
-            device.device.create_graphics_pipeline(&desc, None)?
+            device
+                .device
+                .create_graphics_pipeline(&desc, Some(&self.cache))?
         };
 
         // destroy shader modules
@@ -259,6 +406,8 @@ impl PipelineStorage {
                 vertex,
                 fragment,
                 geometry,
+                tess_control,
+                tess_eval,
             } = module;
 
             device.device.destroy_shader_module(vertex);
@@ -270,6 +419,14 @@ impl PipelineStorage {
             if let Some(geom) = geometry {
                 device.device.destroy_shader_module(geom);
             }
+
+            if let Some(tesc) = tess_control {
+                device.device.destroy_shader_module(tesc);
+            }
+
+            if let Some(tese) = tess_eval {
+                device.device.destroy_shader_module(tese);
+            }
         }
 
         let handle = self.storage.insert(Pipeline::Graphics);
@@ -329,7 +486,9 @@ impl PipelineStorage {
 
             let desc = pso::ComputePipelineDesc::new(shader_entry, &layout);
 
-            device.device.create_compute_pipeline(&desc, None)?
+            device
+                .device
+                .create_compute_pipeline(&desc, Some(&self.cache))?
         };
 
         device.device.destroy_shader_module(shader_module);
@@ -391,3 +550,229 @@ impl PipelineStorage {
         }
     }
 }
+
+/// Tessellation shaders have to be used together and exactly when patches are rasterized,
+/// with at most `max_patch_size` control points per patch.
+fn is_tessellation_valid(create_info: &GraphicsPipelineCreateInfo, max_patch_size: u8) -> bool {
+    use crate::graph::Primitive;
+
+    let tess_control = create_info.shader_tess_control.is_some();
+    let tess_eval = create_info.shader_tess_eval.is_some();
+
+    match create_info.primitive {
+        Primitive::PatchList(points) => {
+            tess_control && tess_eval && points > 0 && points <= max_patch_size
+        }
+        _ => !tess_control && !tess_eval,
+    }
+}
+
+/// Device features needed to create a graphics pipeline.
+fn required_features(create_info: &GraphicsPipelineCreateInfo) -> gfx::Features {
+    use crate::graph::PolygonMode;
+
+    let mut features = gfx::Features::empty();
+
+    if create_info.shader_tess_control.is_some() || create_info.shader_tess_eval.is_some() {
+        features |= gfx::Features::TESSELLATION_SHADER;
+    }
+
+    let raster = create_info.rasterizer;
+
+    if raster.polygon_mode != PolygonMode::Fill {
+        features |= gfx::Features::NON_FILL_POLYGON_MODE;
+    }
+
+    if raster.polygon_mode == PolygonMode::Line
+        && (raster.line_width - 1.0).abs() > std::f32::EPSILON
+    {
+        features |= gfx::Features::LINE_WIDTH;
+    }
+
+    if raster.depth_clamping {
+        features |= gfx::Features::DEPTH_CLAMP;
+    }
+
+    let blend = create_info.blend;
+
+    if blend.logic_op.is_some() {
+        features |= gfx::Features::LOGIC_OP;
+    }
+
+    if blend.targets.iter().any(|state| state.is_dual_source()) {
+        features |= gfx::Features::DUAL_SRC_BLENDING;
+    }
+
+    if blend
+        .targets
+        .windows(2)
+        .any(|states| states[0] != states[1])
+    {
+        features |= gfx::Features::INDEPENDENT_BLENDING;
+    }
+
+    if let Some(depth) = create_info.depth_mode {
+        if depth.bounds.is_some() {
+            features |= gfx::Features::DEPTH_BOUNDS;
+        }
+
+        if depth.bias.map(|bias| bias.clamp != 0.0).unwrap_or(false) {
+            features |= gfx::Features::DEPTH_BIAS_CLAMP;
+        }
+    }
+
+    features
+}
+
+fn stencil_test(mode: StencilMode) -> pso::StencilTest {
+    let reference = match mode.reference {
+        StencilReference::Static(value) => pso::State::Static(value),
+        StencilReference::Dynamic => pso::State::Dynamic,
+    };
+
+    let face = |face: crate::graph::StencilFace| pso::StencilFace {
+        fun: face.func.into(),
+        mask_read: pso::State::Static(mode.compare_mask),
+        mask_write: pso::State::Static(mode.write_mask),
+        op_fail: face.fail.into(),
+        op_depth_fail: face.depth_fail.into(),
+        op_pass: face.pass.into(),
+        reference,
+    };
+
+    pso::StencilTest::On {
+        front: face(mode.front),
+        back: face(mode.back),
+    }
+}
+
+/// Size of the header at the start of pipeline cache data.
+const CACHE_HEADER_SIZE: usize = 32;
+/// Version of the pipeline cache header layout.
+const CACHE_HEADER_VERSION_ONE: u32 = 1;
+
+/// Check whether saved pipeline cache data was created on the given adapter.
+///
+/// The cache header stores the vendor and device IDs least significant byte first. The driver
+/// checks the cache UUID itself and ignores incompatible data, but catching stale data early
+/// avoids passing data of other devices to the driver at all.
+fn cache_is_compatible(data: &[u8], vendor: u32, device: u32) -> bool {
+    if data.len() < CACHE_HEADER_SIZE {
+        return false;
+    }
+
+    let read = |index: usize| {
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(&data[index * 4..(index + 1) * 4]);
+        u32::from_le_bytes(bytes)
+    };
+
+    read(0) as usize >= CACHE_HEADER_SIZE
+        && read(1) == CACHE_HEADER_VERSION_ONE
+        && read(2) == vendor
+        && read(3) == device
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache_header(version: u32, vendor: u32, device: u32) -> Vec<u8> {
+        let mut data = vec![];
+
+        for value in &[CACHE_HEADER_SIZE as u32, version, vendor, device] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+
+        // pipeline cache UUID
+        data.extend_from_slice(&[0xAB; 16]);
+
+        data
+    }
+
+    #[test]
+    fn cache_header_is_validated() {
+        let data = cache_header(CACHE_HEADER_VERSION_ONE, 0x10DE, 0x1B80);
+
+        assert!(cache_is_compatible(&data, 0x10DE, 0x1B80));
+        assert!(!cache_is_compatible(&data, 0x1002, 0x1B80));
+        assert!(!cache_is_compatible(&data, 0x10DE, 0x1B81));
+
+        let data = cache_header(2, 0x10DE, 0x1B80);
+        assert!(!cache_is_compatible(&data, 0x10DE, 0x1B80));
+
+        assert!(!cache_is_compatible(&[], 0x10DE, 0x1B80));
+        assert!(!cache_is_compatible(&data[..16], 0x10DE, 0x1B80));
+    }
+
+    #[test]
+    fn tessellation_requires_patches() {
+        use crate::graph::{Primitive, Rasterizer};
+
+        let shader = ShaderInfo {
+            content: &[],
+            entry: "main",
+            specialization: &[],
+        };
+
+        let blend = BlendDesc::default();
+
+        let create_info =
+            |primitive, tess_control: bool, tess_eval: bool| GraphicsPipelineCreateInfo {
+                primitive,
+                rasterizer: Rasterizer::default(),
+                vertex_attribs: None,
+                descriptor_set_layout: &[],
+                push_constants: &[],
+                blend: &blend,
+                depth_mode: None,
+                stencil_mode: None,
+                samples: 1,
+                shader_vertex: shader.clone(),
+                shader_fragment: None,
+                shader_geometry: None,
+                shader_tess_control: if tess_control {
+                    Some(shader.clone())
+                } else {
+                    None
+                },
+                shader_tess_eval: if tess_eval {
+                    Some(shader.clone())
+                } else {
+                    None
+                },
+            };
+
+        let tessellated = create_info(Primitive::PatchList(3), true, true);
+        assert!(is_tessellation_valid(&tessellated, 32));
+        assert_eq!(
+            required_features(&tessellated),
+            gfx::Features::TESSELLATION_SHADER
+        );
+
+        let plain = create_info(Primitive::TriangleList, false, false);
+        assert!(is_tessellation_valid(&plain, 32));
+        assert_eq!(required_features(&plain), gfx::Features::empty());
+
+        assert!(!is_tessellation_valid(
+            &create_info(Primitive::TriangleList, true, true),
+            32
+        ));
+        assert!(!is_tessellation_valid(
+            &create_info(Primitive::PatchList(3), true, false),
+            32
+        ));
+        assert!(!is_tessellation_valid(
+            &create_info(Primitive::PatchList(3), false, false),
+            32
+        ));
+        assert!(!is_tessellation_valid(
+            &create_info(Primitive::PatchList(0), true, true),
+            32
+        ));
+        assert!(!is_tessellation_valid(
+            &create_info(Primitive::PatchList(33), true, true),
+            32
+        ));
+    }
+}
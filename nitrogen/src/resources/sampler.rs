@@ -75,6 +75,10 @@ pub struct SamplerCreateInfo {
     pub mip_filter: Filter,
     /// Wrap modes used when sampling outside of the `[0..1]` range occurs.
     pub wrap_mode: (WrapMode, WrapMode, WrapMode),
+    /// Lowest and highest mipmap level that can be sampled from.
+    ///
+    /// Only the first level is sampled when both values are `0.0`.
+    pub lod_range: (f32, f32),
     // TODO anisotropy?
 }
 
@@ -90,7 +94,7 @@ impl From<SamplerCreateInfo> for image::SamplerInfo {
                 create.wrap_mode.2.into(),
             ),
             lod_bias: 0.0.into(),
-            lod_range: (0.0.into())..(1.0.into()),
+            lod_range: create.lod_range.0.into()..create.lod_range.1.into(),
             comparison: None,
             border: image::PackedColor(0x0),
             anisotropic: image::Anisotropic::Off,
@@ -26,6 +26,15 @@ impl SemaphorePool {
         self.0.alloc()
     }
 
+    /// Allocate a semaphore which stays alive until the pool is reset.
+    pub(crate) unsafe fn alloc_idx(&self) -> usize {
+        self.0.alloc().into_idx()
+    }
+
+    pub(crate) fn lookup(&self, idx: usize) -> &types::Semaphore {
+        self.0.lookup(idx)
+    }
+
     pub(crate) fn list_prev_sems<'a>(
         &'a self,
         list: &'a SemaphoreList,
@@ -98,6 +107,14 @@ impl SemaphoreList {
         self.next_semaphores.push(unsafe { sem.into_idx() });
     }
 
+    pub(crate) fn add_next_semaphore_idx(&mut self, idx: usize) {
+        self.next_semaphores.push(idx);
+    }
+
+    pub(crate) fn has_prev_semaphores(&self) -> bool {
+        !self.prev_semaphores.is_empty()
+    }
+
     pub(crate) fn advance(&mut self) {
         self.prev_semaphores.clear();
         self.prev_semaphores
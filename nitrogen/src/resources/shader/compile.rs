@@ -0,0 +1,211 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Compilation of GLSL and HLSL source code to SPIR-V.
+//!
+//! This makes it possible to create shader variants at runtime, for example by compiling the
+//! same source with different macro definitions.
+
+use super::{EntryPoint, ShaderError, ShaderStage};
+
+use std::path::{Path, PathBuf};
+
+/// Language of a shader source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SourceLanguage {
+    /// GLSL, compiled with Vulkan semantics.
+    Glsl,
+    /// HLSL.
+    Hlsl,
+}
+
+/// Errors that can occur while compiling a shader source.
+#[allow(missing_docs)]
+#[derive(Debug, Display, Clone, PartialEq)]
+pub enum CompileError {
+    #[display(fmt = "The shader compiler could not be initialized")]
+    CompilerUnavailable,
+
+    /// `messages` contains the diagnostics of the compiler, including the file names and lines.
+    #[display(
+        fmt = "Compiling {} failed with {} error(s):\n{}",
+        name,
+        count,
+        messages
+    )]
+    Compilation {
+        name: String,
+        count: u32,
+        messages: String,
+    },
+
+    #[display(fmt = "Internal shader compiler error: {}", _0)]
+    Internal(String),
+
+    #[display(fmt = "The compiled shader is invalid: {}", _0)]
+    ShaderError(ShaderError),
+}
+
+impl std::error::Error for CompileError {}
+
+impl From<ShaderError> for CompileError {
+    fn from(err: ShaderError) -> Self {
+        CompileError::ShaderError(err)
+    }
+}
+
+/// Information describing a shader program in source form.
+pub struct ShaderSource<'a> {
+    /// Language of the source code.
+    pub language: SourceLanguage,
+
+    /// The source code of the shader.
+    pub source: &'a str,
+
+    /// Name of the source, usually the path of the file it was read from.
+    ///
+    /// The name is used in error messages and `#include "..."` directives are resolved relative
+    /// to the directory of the name.
+    pub name: &'a str,
+
+    /// The entry point (function name) of the shader program.
+    pub entry_point: EntryPoint,
+
+    /// Macros defined before the source code, given by the name and an optional value.
+    pub defines: &'a [(&'a str, Option<&'a str>)],
+
+    /// Directories searched for included files.
+    ///
+    /// `#include <...>` directives are only resolved in those directories, `#include "..."`
+    /// directives search them after the directory of the including file.
+    pub include_dirs: &'a [&'a Path],
+}
+
+impl<'a> ShaderSource<'a> {
+    /// Compile the source to SPIR-V code usable in a [`ShaderInfo`].
+    ///
+    /// [`ShaderInfo`]: ../struct.ShaderInfo.html
+    pub fn compile(&self, stage: ShaderStage) -> Result<Vec<u8>, CompileError> {
+        use shaderc::{CompileOptions, Compiler, IncludeType, ResolvedInclude, ShaderKind};
+
+        let mut compiler = Compiler::new().ok_or(CompileError::CompilerUnavailable)?;
+        let mut options = CompileOptions::new().ok_or(CompileError::CompilerUnavailable)?;
+
+        options.set_source_language(match self.language {
+            SourceLanguage::Glsl => shaderc::SourceLanguage::GLSL,
+            SourceLanguage::Hlsl => shaderc::SourceLanguage::HLSL,
+        });
+
+        for (name, value) in self.defines {
+            options.add_macro_definition(name, *value);
+        }
+
+        let include_dirs = self.include_dirs;
+
+        options.set_include_callback(move |requested, ty, requesting, _depth| {
+            let relative_to = match ty {
+                IncludeType::Relative => Path::new(requesting).parent(),
+                IncludeType::Standard => None,
+            };
+
+            let path = resolve_include(requested, relative_to, include_dirs)
+                .ok_or_else(|| format!("Included file \"{}\" could not be found", requested))?;
+
+            let content = std::fs::read_to_string(&path)
+                .map_err(|err| format!("Included file {:?} could not be read: {}", path, err))?;
+
+            Ok(ResolvedInclude {
+                resolved_name: path.to_string_lossy().into_owned(),
+                content,
+            })
+        });
+
+        let kind = match stage {
+            ShaderStage::Compute => ShaderKind::Compute,
+            ShaderStage::Vertex => ShaderKind::Vertex,
+            ShaderStage::Fragment => ShaderKind::Fragment,
+            ShaderStage::Geometry => ShaderKind::Geometry,
+            ShaderStage::TessControl => ShaderKind::TessControl,
+            ShaderStage::TessEval => ShaderKind::TessEvaluation,
+        };
+
+        let artifact = compiler
+            .compile_into_spirv(
+                self.source,
+                kind,
+                self.name,
+                &self.entry_point,
+                Some(&options),
+            )
+            .map_err(|err| match err {
+                shaderc::Error::CompilationError(count, messages) => CompileError::Compilation {
+                    name: self.name.to_string(),
+                    count,
+                    messages,
+                },
+                shaderc::Error::InternalError(msg)
+                | shaderc::Error::InvalidStage(msg)
+                | shaderc::Error::InvalidAssembly(msg)
+                | shaderc::Error::NullResultObject(msg) => CompileError::Internal(msg),
+            })?;
+
+        Ok(artifact.as_binary_u8().to_owned())
+    }
+}
+
+/// Find the file for an include directive.
+///
+/// `relative_to` is searched first, followed by the include directories in order.
+fn resolve_include(
+    requested: &str,
+    relative_to: Option<&Path>,
+    include_dirs: &[&Path],
+) -> Option<PathBuf> {
+    relative_to
+        .into_iter()
+        .chain(include_dirs.iter().cloned())
+        .map(|dir| dir.join(requested))
+        .find(|path| path.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn includes_are_resolved_in_order() {
+        let base = std::env::temp_dir().join(format!("nitrogen-include-{}", std::process::id()));
+
+        let local = base.join("local");
+        let first = base.join("first");
+        let second = base.join("second");
+
+        for dir in &[&local, &first, &second] {
+            std::fs::create_dir_all(dir).unwrap();
+        }
+
+        std::fs::write(local.join("common.glsl"), "").unwrap();
+        std::fs::write(first.join("common.glsl"), "").unwrap();
+        std::fs::write(second.join("common.glsl"), "").unwrap();
+        std::fs::write(second.join("lighting.glsl"), "").unwrap();
+
+        let dirs = [first.as_path(), second.as_path()];
+
+        assert_eq!(
+            resolve_include("common.glsl", Some(&local), &dirs),
+            Some(local.join("common.glsl"))
+        );
+        assert_eq!(
+            resolve_include("common.glsl", None, &dirs),
+            Some(first.join("common.glsl"))
+        );
+        assert_eq!(
+            resolve_include("lighting.glsl", Some(&local), &dirs),
+            Some(second.join("lighting.glsl"))
+        );
+        assert_eq!(resolve_include("missing.glsl", Some(&local), &dirs), None);
+
+        std::fs::remove_dir_all(&base).unwrap();
+    }
+}
@@ -0,0 +1,536 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Storage for shader programs.
+
+use crate::util::storage::{Handle, Storage};
+use crate::util::CowString;
+use std::marker::PhantomData;
+
+pub mod reflect;
+pub use self::reflect::*;
+
+#[cfg(feature = "shader_compile")]
+pub mod compile;
+#[cfg(feature = "shader_compile")]
+pub use self::compile::*;
+
+#[cfg(feature = "shader_watch")]
+pub mod watch;
+
+/// Name of the "entry point" of a shader program.
+///
+/// The entry point is the name of the function which will be invoked during
+/// the shader execution.
+pub type EntryPoint = CowString;
+
+/// The newest SPIR-V version accepted for shader programs, as major and minor version.
+///
+/// Devices are used through Vulkan 1.0, which only consumes SPIR-V 1.0.
+const MAX_SPIRV_VERSION: (u32, u32) = (1, 0);
+
+/// Errors that can occur when creating a shader program.
+#[allow(missing_docs)]
+#[derive(Debug, Display, Clone, PartialEq, From)]
+pub enum ShaderError {
+    #[display(fmt = "Invalid shader handle")]
+    InvalidHandle,
+
+    #[display(fmt = "The SPIR-V code is too short or not a multiple of 4 bytes long")]
+    InvalidLength,
+
+    #[display(fmt = "The data does not start with the SPIR-V magic number")]
+    InvalidMagic,
+
+    #[display(fmt = "SPIR-V version {}.{} is not supported", major, minor)]
+    UnsupportedVersion { major: u32, minor: u32 },
+
+    /// The module is malformed or does not contain the entry point for the shader stage.
+    #[display(fmt = "The shader program is invalid: {}", _0)]
+    ReflectError(ReflectError),
+}
+
+impl std::error::Error for ShaderError {}
+
+/// Stage of the pipeline a shader program is used in.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ShaderStage {
+    Compute,
+    Vertex,
+    Fragment,
+    Geometry,
+    TessControl,
+    TessEval,
+}
+
+/// Information describing a shader program.
+pub struct ShaderInfo<'a> {
+    /// The compiled SPIR-V code of the shader.
+    pub spirv_content: &'a [u8],
+
+    /// The entry point (function name) of the shader program.
+    pub entry_point: EntryPoint,
+}
+
+/// A strongly typed shader program resource.
+///
+/// The type parameter `T` denotes the shader type.
+pub struct Shader<T> {
+    pub(crate) spirv_content: Vec<u8>,
+    pub(crate) entry_point: EntryPoint,
+    /// Interface of the entry point, used to validate pipelines using the shader.
+    pub(crate) reflection: ShaderReflection,
+    /// Storage generation at which the code was last replaced.
+    pub(crate) generation: u64,
+
+    pub(crate) _marker: PhantomData<T>,
+}
+
+/// Type denoting a compute shader program.
+pub struct Compute;
+/// Type denoting a vertex shader program.
+pub struct Vertex;
+/// Type denoting a fragment shader program.
+pub struct Fragment;
+/// Type denoting a geometry shader program.
+pub struct Geometry;
+/// Type denoting a tessellation control shader program.
+pub struct TessControl;
+/// Type denoting a tessellation evaluation shader program.
+pub struct TessEval;
+
+/// Opaque handle to a compute shader program resource.
+pub type ComputeShaderHandle = Handle<Shader<Compute>>;
+/// Opaque handle to a vertex shader program resource.
+pub type VertexShaderHandle = Handle<Shader<Vertex>>;
+/// Opaque handle to a fragment shader program resource.
+pub type FragmentShaderHandle = Handle<Shader<Fragment>>;
+/// Opaque handle to a geometry shader program resource.
+pub type GeometryShaderHandle = Handle<Shader<Geometry>>;
+/// Opaque handle to a tessellation control shader program resource.
+pub type TessControlShaderHandle = Handle<Shader<TessControl>>;
+/// Opaque handle to a tessellation evaluation shader program resource.
+pub type TessEvalShaderHandle = Handle<Shader<TessEval>>;
+
+pub(crate) struct ShaderStorage {
+    pub(crate) compute_storage: Storage<Shader<Compute>>,
+    pub(crate) vertex_storage: Storage<Shader<Vertex>>,
+    pub(crate) fragment_storage: Storage<Shader<Fragment>>,
+    pub(crate) geometry_storage: Storage<Shader<Geometry>>,
+    pub(crate) tess_control_storage: Storage<Shader<TessControl>>,
+    pub(crate) tess_eval_storage: Storage<Shader<TessEval>>,
+
+    /// Incremented every time the code of a shader gets replaced.
+    ///
+    /// Pipelines remember the generation they were created at, so they can be identified as
+    /// outdated when one of their shaders has a newer generation.
+    pub(crate) generation: u64,
+}
+
+impl ShaderStorage {
+    pub(crate) fn new() -> Self {
+        ShaderStorage {
+            compute_storage: Storage::new(),
+            vertex_storage: Storage::new(),
+            fragment_storage: Storage::new(),
+            geometry_storage: Storage::new(),
+            tess_control_storage: Storage::new(),
+            tess_eval_storage: Storage::new(),
+
+            generation: 0,
+        }
+    }
+
+    fn create_shader<T>(
+        storage: &mut Storage<Shader<T>>,
+        info: ShaderInfo<'_>,
+        stage: ShaderStage,
+    ) -> Result<Handle<Shader<T>>, ShaderError> {
+        let reflection = validate(&info, stage)?;
+
+        let shader = Shader {
+            spirv_content: info.spirv_content.to_owned(),
+            entry_point: info.entry_point.clone(),
+            reflection,
+            generation: 0,
+
+            _marker: PhantomData,
+        };
+
+        Ok(storage.insert(shader))
+    }
+
+    fn replace_shader<T>(
+        storage: &mut Storage<Shader<T>>,
+        generation: &mut u64,
+        handle: Handle<Shader<T>>,
+        info: ShaderInfo<'_>,
+        stage: ShaderStage,
+    ) -> Result<(), ShaderError> {
+        let shader = storage.get_mut(handle).ok_or(ShaderError::InvalidHandle)?;
+
+        let reflection = validate(&info, stage)?;
+
+        *generation += 1;
+
+        shader.spirv_content = info.spirv_content.to_owned();
+        shader.entry_point = info.entry_point.clone();
+        shader.reflection = reflection;
+        shader.generation = *generation;
+
+        Ok(())
+    }
+
+    fn destroy_shader<T>(storage: &mut Storage<Shader<T>>, handle: Handle<Shader<T>>) {
+        if let Some(shader) = storage.remove(handle) {
+            // TODO some de-initialization behavior?
+            drop(shader);
+        }
+    }
+
+    fn raw<T>(storage: &Storage<Shader<T>>, handle: Handle<Shader<T>>) -> Option<&Shader<T>> {
+        storage.get(handle)
+    }
+
+    /// Check if the shader was replaced after the given storage generation.
+    ///
+    /// Destroyed shaders are never considered replaced.
+    pub(crate) fn replaced_since<T>(
+        storage: &Storage<Shader<T>>,
+        handle: Handle<Shader<T>>,
+        generation: u64,
+    ) -> bool {
+        storage
+            .get(handle)
+            .map(|shader| shader.generation > generation)
+            .unwrap_or(false)
+    }
+
+    // compute
+
+    pub(crate) fn create_compute_shader(
+        &mut self,
+        info: ShaderInfo<'_>,
+    ) -> Result<ComputeShaderHandle, ShaderError> {
+        ShaderStorage::create_shader(&mut self.compute_storage, info, ShaderStage::Compute)
+    }
+
+    pub(crate) fn replace_compute_shader(
+        &mut self,
+        handle: ComputeShaderHandle,
+        info: ShaderInfo<'_>,
+    ) -> Result<(), ShaderError> {
+        ShaderStorage::replace_shader(
+            &mut self.compute_storage,
+            &mut self.generation,
+            handle,
+            info,
+            ShaderStage::Compute,
+        )
+    }
+
+    pub(crate) fn destroy_compute_shader(&mut self, handle: ComputeShaderHandle) {
+        ShaderStorage::destroy_shader(&mut self.compute_storage, handle);
+    }
+
+    pub(crate) fn raw_compute(&self, handle: ComputeShaderHandle) -> Option<&Shader<Compute>> {
+        ShaderStorage::raw(&self.compute_storage, handle)
+    }
+
+    // vertex
+
+    pub(crate) fn create_vertex_shader(
+        &mut self,
+        info: ShaderInfo<'_>,
+    ) -> Result<VertexShaderHandle, ShaderError> {
+        ShaderStorage::create_shader(&mut self.vertex_storage, info, ShaderStage::Vertex)
+    }
+
+    pub(crate) fn replace_vertex_shader(
+        &mut self,
+        handle: VertexShaderHandle,
+        info: ShaderInfo<'_>,
+    ) -> Result<(), ShaderError> {
+        ShaderStorage::replace_shader(
+            &mut self.vertex_storage,
+            &mut self.generation,
+            handle,
+            info,
+            ShaderStage::Vertex,
+        )
+    }
+
+    pub(crate) fn destroy_vertex_shader(&mut self, handle: VertexShaderHandle) {
+        ShaderStorage::destroy_shader(&mut self.vertex_storage, handle);
+    }
+
+    pub(crate) fn raw_vertex(&self, handle: VertexShaderHandle) -> Option<&Shader<Vertex>> {
+        ShaderStorage::raw(&self.vertex_storage, handle)
+    }
+
+    // fragment
+
+    pub(crate) fn create_fragment_shader(
+        &mut self,
+        info: ShaderInfo<'_>,
+    ) -> Result<FragmentShaderHandle, ShaderError> {
+        ShaderStorage::create_shader(&mut self.fragment_storage, info, ShaderStage::Fragment)
+    }
+
+    pub(crate) fn replace_fragment_shader(
+        &mut self,
+        handle: FragmentShaderHandle,
+        info: ShaderInfo<'_>,
+    ) -> Result<(), ShaderError> {
+        ShaderStorage::replace_shader(
+            &mut self.fragment_storage,
+            &mut self.generation,
+            handle,
+            info,
+            ShaderStage::Fragment,
+        )
+    }
+
+    pub(crate) fn destroy_fragment_shader(&mut self, handle: FragmentShaderHandle) {
+        ShaderStorage::destroy_shader(&mut self.fragment_storage, handle);
+    }
+
+    pub(crate) fn raw_fragment(&self, handle: FragmentShaderHandle) -> Option<&Shader<Fragment>> {
+        ShaderStorage::raw(&self.fragment_storage, handle)
+    }
+
+    // geometry
+
+    pub(crate) fn create_geometry_shader(
+        &mut self,
+        info: ShaderInfo<'_>,
+    ) -> Result<GeometryShaderHandle, ShaderError> {
+        ShaderStorage::create_shader(&mut self.geometry_storage, info, ShaderStage::Geometry)
+    }
+
+    pub(crate) fn replace_geometry_shader(
+        &mut self,
+        handle: GeometryShaderHandle,
+        info: ShaderInfo<'_>,
+    ) -> Result<(), ShaderError> {
+        ShaderStorage::replace_shader(
+            &mut self.geometry_storage,
+            &mut self.generation,
+            handle,
+            info,
+            ShaderStage::Geometry,
+        )
+    }
+
+    pub(crate) fn destroy_geometry_shader(&mut self, handle: GeometryShaderHandle) {
+        ShaderStorage::destroy_shader(&mut self.geometry_storage, handle);
+    }
+
+    pub(crate) fn raw_geometry(&self, handle: GeometryShaderHandle) -> Option<&Shader<Geometry>> {
+        ShaderStorage::raw(&self.geometry_storage, handle)
+    }
+
+    // tessellation control
+
+    pub(crate) fn create_tess_control_shader(
+        &mut self,
+        info: ShaderInfo<'_>,
+    ) -> Result<TessControlShaderHandle, ShaderError> {
+        ShaderStorage::create_shader(
+            &mut self.tess_control_storage,
+            info,
+            ShaderStage::TessControl,
+        )
+    }
+
+    pub(crate) fn replace_tess_control_shader(
+        &mut self,
+        handle: TessControlShaderHandle,
+        info: ShaderInfo<'_>,
+    ) -> Result<(), ShaderError> {
+        ShaderStorage::replace_shader(
+            &mut self.tess_control_storage,
+            &mut self.generation,
+            handle,
+            info,
+            ShaderStage::TessControl,
+        )
+    }
+
+    pub(crate) fn destroy_tess_control_shader(&mut self, handle: TessControlShaderHandle) {
+        ShaderStorage::destroy_shader(&mut self.tess_control_storage, handle);
+    }
+
+    pub(crate) fn raw_tess_control(
+        &self,
+        handle: TessControlShaderHandle,
+    ) -> Option<&Shader<TessControl>> {
+        ShaderStorage::raw(&self.tess_control_storage, handle)
+    }
+
+    // tessellation evaluation
+
+    pub(crate) fn create_tess_eval_shader(
+        &mut self,
+        info: ShaderInfo<'_>,
+    ) -> Result<TessEvalShaderHandle, ShaderError> {
+        ShaderStorage::create_shader(&mut self.tess_eval_storage, info, ShaderStage::TessEval)
+    }
+
+    pub(crate) fn replace_tess_eval_shader(
+        &mut self,
+        handle: TessEvalShaderHandle,
+        info: ShaderInfo<'_>,
+    ) -> Result<(), ShaderError> {
+        ShaderStorage::replace_shader(
+            &mut self.tess_eval_storage,
+            &mut self.generation,
+            handle,
+            info,
+            ShaderStage::TessEval,
+        )
+    }
+
+    pub(crate) fn destroy_tess_eval_shader(&mut self, handle: TessEvalShaderHandle) {
+        ShaderStorage::destroy_shader(&mut self.tess_eval_storage, handle);
+    }
+
+    pub(crate) fn raw_tess_eval(&self, handle: TessEvalShaderHandle) -> Option<&Shader<TessEval>> {
+        ShaderStorage::raw(&self.tess_eval_storage, handle)
+    }
+}
+
+/// Check the header of the SPIR-V code and reflect the entry point of the shader.
+fn validate(info: &ShaderInfo<'_>, stage: ShaderStage) -> Result<ShaderReflection, ShaderError> {
+    let spirv = info.spirv_content;
+
+    if spirv.len() % 4 != 0 || spirv.len() < 8 {
+        return Err(ShaderError::InvalidLength);
+    }
+
+    let word = |index: usize| {
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(&spirv[index * 4..index * 4 + 4]);
+        u32::from_le_bytes(bytes)
+    };
+
+    let version = match word(0) {
+        MAGIC => word(1),
+        magic if magic == MAGIC.swap_bytes() => word(1).swap_bytes(),
+        _ => return Err(ShaderError::InvalidMagic),
+    };
+
+    let major = (version >> 16) & 0xff;
+    let minor = (version >> 8) & 0xff;
+
+    if major != MAX_SPIRV_VERSION.0 || minor > MAX_SPIRV_VERSION.1 {
+        return Err(ShaderError::UnsupportedVersion { major, minor });
+    }
+
+    Ok(reflect(spirv, &info.entry_point, stage)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static VERTEX: &[u8] = include_bytes!("test_shaders/textured.vert.spv");
+
+    #[test]
+    fn replaced_shaders_are_tracked() {
+        let mut storage = ShaderStorage::new();
+
+        let info = || ShaderInfo {
+            spirv_content: VERTEX,
+            entry_point: "main".into(),
+        };
+
+        let first = storage.create_vertex_shader(info()).unwrap();
+        let second = storage.create_vertex_shader(info()).unwrap();
+
+        let generation = storage.generation;
+
+        assert_eq!(storage.replace_vertex_shader(first, info()), Ok(()));
+        assert!(ShaderStorage::replaced_since(
+            &storage.vertex_storage,
+            first,
+            generation
+        ));
+        assert!(!ShaderStorage::replaced_since(
+            &storage.vertex_storage,
+            second,
+            generation
+        ));
+        assert!(!ShaderStorage::replaced_since(
+            &storage.vertex_storage,
+            first,
+            storage.generation
+        ));
+
+        storage.destroy_vertex_shader(second);
+
+        assert_eq!(
+            storage.replace_vertex_shader(second, info()),
+            Err(ShaderError::InvalidHandle)
+        );
+        assert!(!ShaderStorage::replaced_since(
+            &storage.vertex_storage,
+            second,
+            0
+        ));
+    }
+
+    #[test]
+    fn invalid_shaders_are_rejected() {
+        let mut storage = ShaderStorage::new();
+
+        let create = |storage: &mut ShaderStorage, spirv: &[u8], entry: &'static str| {
+            storage
+                .create_vertex_shader(ShaderInfo {
+                    spirv_content: spirv,
+                    entry_point: entry.into(),
+                })
+                .err()
+        };
+
+        assert_eq!(
+            create(&mut storage, &VERTEX[..VERTEX.len() - 1], "main"),
+            Some(ShaderError::InvalidLength)
+        );
+        assert_eq!(
+            create(&mut storage, &VERTEX[4..], "main"),
+            Some(ShaderError::InvalidMagic)
+        );
+
+        // minor version byte of the little endian version word
+        let mut future = VERTEX.to_vec();
+        future[5] = 1;
+        assert_eq!(
+            create(&mut storage, &future, "main"),
+            Some(ShaderError::UnsupportedVersion { major: 1, minor: 1 })
+        );
+
+        assert_eq!(
+            create(&mut storage, VERTEX, "VertexMain"),
+            Some(ShaderError::ReflectError(ReflectError::MissingEntryPoint(
+                "VertexMain".to_string()
+            )))
+        );
+
+        let fragment = storage.create_fragment_shader(ShaderInfo {
+            spirv_content: VERTEX,
+            entry_point: "main".into(),
+        });
+        assert_eq!(
+            fragment.err(),
+            Some(ShaderError::ReflectError(ReflectError::StageMismatch {
+                entry_point: "main".to_string(),
+                stage: ShaderStage::Fragment,
+            }))
+        );
+
+        assert_eq!(create(&mut storage, VERTEX, "main"), None);
+    }
+}
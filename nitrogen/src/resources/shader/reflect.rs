@@ -0,0 +1,1242 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Reflection of the resource interface of SPIR-V shader programs.
+//!
+//! The reflection reports everything a pipeline has to provide for a shader entry point:
+//! descriptor bindings, push constants, vertex inputs and specialization constants.
+//! Only resources that are statically used by the entry point (or any function called by it)
+//! are reported.
+//!
+//! Pipelines are validated against the reflection of their shaders when they are created.
+//! A reflection can also be used to derive materials and vertex attributes from a shader,
+//! see [`ShaderReflection::material_parameters`] and [`ShaderReflection::vertex_attrib`].
+//!
+//! [`ShaderReflection::material_parameters`]: struct.ShaderReflection.html#method.material_parameters
+//! [`ShaderReflection::vertex_attrib`]: struct.ShaderReflection.html#method.vertex_attrib
+
+use super::ShaderStage;
+
+use crate::graph::pass::Specialization;
+use crate::material::MaterialParameterType;
+use crate::vertex_attrib::{VertexAttrib, VertexAttribBufferElementInfo, VertexAttribBufferInfo};
+
+use gfx::format::Format;
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ops::Range;
+
+pub(crate) const MAGIC: u32 = 0x0723_0203;
+const HEADER_WORDS: usize = 5;
+
+// opcodes
+const OP_ENTRY_POINT: u32 = 15;
+const OP_TYPE_BOOL: u32 = 20;
+const OP_TYPE_INT: u32 = 21;
+const OP_TYPE_FLOAT: u32 = 22;
+const OP_TYPE_VECTOR: u32 = 23;
+const OP_TYPE_MATRIX: u32 = 24;
+const OP_TYPE_IMAGE: u32 = 25;
+const OP_TYPE_SAMPLER: u32 = 26;
+const OP_TYPE_SAMPLED_IMAGE: u32 = 27;
+const OP_TYPE_ARRAY: u32 = 28;
+const OP_TYPE_RUNTIME_ARRAY: u32 = 29;
+const OP_TYPE_STRUCT: u32 = 30;
+const OP_TYPE_POINTER: u32 = 32;
+const OP_CONSTANT: u32 = 43;
+const OP_SPEC_CONSTANT_TRUE: u32 = 48;
+const OP_SPEC_CONSTANT_FALSE: u32 = 49;
+const OP_SPEC_CONSTANT: u32 = 50;
+const OP_FUNCTION: u32 = 54;
+const OP_FUNCTION_END: u32 = 56;
+const OP_VARIABLE: u32 = 59;
+const OP_DECORATE: u32 = 71;
+const OP_MEMBER_DECORATE: u32 = 72;
+
+// decorations
+const DECORATION_SPEC_ID: u32 = 1;
+const DECORATION_BLOCK: u32 = 2;
+const DECORATION_BUFFER_BLOCK: u32 = 3;
+const DECORATION_ROW_MAJOR: u32 = 4;
+const DECORATION_ARRAY_STRIDE: u32 = 6;
+const DECORATION_MATRIX_STRIDE: u32 = 7;
+const DECORATION_BUILT_IN: u32 = 11;
+const DECORATION_LOCATION: u32 = 30;
+const DECORATION_BINDING: u32 = 33;
+const DECORATION_DESCRIPTOR_SET: u32 = 34;
+const DECORATION_OFFSET: u32 = 35;
+
+// storage classes
+const STORAGE_UNIFORM_CONSTANT: u32 = 0;
+const STORAGE_INPUT: u32 = 1;
+const STORAGE_UNIFORM: u32 = 2;
+const STORAGE_PUSH_CONSTANT: u32 = 9;
+const STORAGE_STORAGE_BUFFER: u32 = 12;
+
+// image dimensions
+const DIM_BUFFER: u32 = 5;
+const DIM_SUBPASS_DATA: u32 = 6;
+
+/// Errors that can occur when reflecting a shader program.
+#[allow(missing_docs)]
+#[derive(Debug, Display, Clone, PartialEq)]
+pub enum ReflectError {
+    #[display(fmt = "The data is not a SPIR-V module")]
+    InvalidModule,
+
+    #[display(fmt = "The SPIR-V module contains a truncated instruction")]
+    Truncated,
+
+    #[display(fmt = "The SPIR-V module has no entry point \"{}\"", _0)]
+    MissingEntryPoint(String),
+
+    #[display(
+        fmt = "The entry point \"{}\" is not a {:?} shader",
+        entry_point,
+        stage
+    )]
+    StageMismatch {
+        entry_point: String,
+        stage: ShaderStage,
+    },
+
+    #[display(
+        fmt = "The descriptor at set {} binding {} can not be used as a material parameter",
+        set,
+        binding
+    )]
+    UnsupportedDescriptor { set: u32, binding: u32 },
+}
+
+impl std::error::Error for ReflectError {}
+
+/// Errors that can occur when a pipeline does not provide what a shader uses.
+#[allow(missing_docs)]
+#[derive(Debug, Display, Clone, PartialEq)]
+pub enum ValidationError {
+    #[display(
+        fmt = "The shader uses descriptor set {} which is not part of the pipeline",
+        _0
+    )]
+    MissingSet(u32),
+
+    #[display(
+        fmt = "The shader uses binding {} of set {} which is not provided",
+        binding,
+        set
+    )]
+    MissingBinding { set: u32, binding: u32 },
+
+    #[display(
+        fmt = "The binding {} of set {} is provided with a different type than {:?}",
+        binding,
+        set,
+        expected
+    )]
+    DescriptorTypeMismatch {
+        set: u32,
+        binding: u32,
+        expected: DescriptorType,
+    },
+
+    #[display(
+        fmt = "The shader uses push constants in {:?} which are not provided",
+        _0
+    )]
+    PushConstantsOutOfRange(Range<u32>),
+
+    #[display(
+        fmt = "The shader uses vertex input location {} which is not provided",
+        _0
+    )]
+    MissingVertexInput(u32),
+
+    #[display(
+        fmt = "The specialization constant {} has a size of {} bytes, but {} bytes were given",
+        id,
+        expected,
+        found
+    )]
+    SpecializationSizeMismatch { id: u32, expected: u32, found: u32 },
+}
+
+impl std::error::Error for ValidationError {}
+
+/// Type of a descriptor used by a shader.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DescriptorType {
+    Sampler,
+    CombinedImageSampler,
+    SampledImage,
+    StorageImage,
+    UniformTexelBuffer,
+    StorageTexelBuffer,
+    UniformBuffer,
+    StorageBuffer,
+    InputAttachment,
+}
+
+impl DescriptorType {
+    /// The material parameter type matching this descriptor type.
+    ///
+    /// Returns `None` for types that can not be used in materials.
+    pub fn material_parameter(self) -> Option<MaterialParameterType> {
+        match self {
+            DescriptorType::Sampler => Some(MaterialParameterType::Sampler),
+            DescriptorType::SampledImage => Some(MaterialParameterType::SampledImage),
+            DescriptorType::StorageImage => Some(MaterialParameterType::StorageImage),
+            DescriptorType::UniformTexelBuffer => Some(MaterialParameterType::UniformTexelBuffer),
+            DescriptorType::StorageTexelBuffer => Some(MaterialParameterType::StorageTexelBuffer),
+            DescriptorType::UniformBuffer => Some(MaterialParameterType::UniformBuffer),
+            DescriptorType::StorageBuffer => Some(MaterialParameterType::StorageBuffer),
+            DescriptorType::CombinedImageSampler | DescriptorType::InputAttachment => None,
+        }
+    }
+
+    /// Whether a material parameter of type `ty` can be bound to this descriptor.
+    ///
+    /// Dynamic buffers are indistinguishable from normal buffers in shader programs.
+    pub fn accepts(self, ty: MaterialParameterType) -> bool {
+        match (self, ty) {
+            (DescriptorType::UniformBuffer, MaterialParameterType::UniformBufferDynamic) => true,
+            (DescriptorType::StorageBuffer, MaterialParameterType::StorageBufferDynamic) => true,
+            (this, ty) => this.material_parameter() == Some(ty),
+        }
+    }
+}
+
+/// A descriptor binding used by a shader.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DescriptorBinding {
+    /// Index of the descriptor set.
+    pub set: u32,
+    /// Binding inside the descriptor set.
+    pub binding: u32,
+    /// Type of the descriptor.
+    pub ty: DescriptorType,
+    /// Number of array elements, zero for runtime-sized arrays.
+    pub count: u32,
+}
+
+/// A vertex input (or any stage input) used by a shader.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StageInput {
+    /// Location of the input.
+    pub location: u32,
+    /// Format that matches the type of the input.
+    pub format: Format,
+}
+
+/// A specialization constant declared in a shader.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpecializationConstant {
+    /// The `constant_id` of the constant.
+    pub id: u32,
+    /// Size in bytes a value for this constant must have.
+    pub size: u32,
+}
+
+/// The resource interface of a shader entry point.
+#[derive(Debug, Clone)]
+pub struct ShaderReflection {
+    /// All descriptors used, sorted by set and binding.
+    pub descriptors: Vec<DescriptorBinding>,
+    /// Byte range of the push constants used.
+    pub push_constants: Option<Range<u32>>,
+    /// Inputs of the entry point, sorted by location.
+    ///
+    /// Built-in inputs are not included. Matrices and arrays take up one location per
+    /// column or element. Inputs with types that have no matching format are left out.
+    pub inputs: Vec<StageInput>,
+    /// All specialization constants, sorted by id.
+    pub specialization_constants: Vec<SpecializationConstant>,
+}
+
+/// The parts of a pipeline a shader is validated against.
+pub(crate) struct PipelineInterface<'a> {
+    /// Material parameters of every descriptor set in the pipeline layout.
+    pub(crate) sets: BTreeMap<u32, &'a [(u32, MaterialParameterType)]>,
+    pub(crate) push_constants: Option<Range<u32>>,
+    pub(crate) vertex_attrib: Option<&'a VertexAttrib>,
+}
+
+impl ShaderReflection {
+    /// All descriptors of the given descriptor set.
+    pub fn set(&self, set: u32) -> impl Iterator<Item = &DescriptorBinding> {
+        self.descriptors.iter().filter(move |desc| desc.set == set)
+    }
+
+    /// Material parameters for all descriptors of the given descriptor set.
+    ///
+    /// The result can be used as the `parameters` of a `MaterialCreateInfo`.
+    /// Descriptors that are arrays or have a type which is not supported by materials
+    /// result in an error.
+    pub fn material_parameters(
+        &self,
+        set: u32,
+    ) -> Result<Vec<(u32, MaterialParameterType)>, ReflectError> {
+        self.set(set)
+            .map(|desc| {
+                let unsupported = ReflectError::UnsupportedDescriptor {
+                    set: desc.set,
+                    binding: desc.binding,
+                };
+
+                if desc.count != 1 {
+                    return Err(unsupported);
+                }
+
+                let ty = desc.ty.material_parameter().ok_or(unsupported)?;
+
+                Ok((desc.binding, ty))
+            })
+            .collect()
+    }
+
+    /// Vertex attributes with a single, tightly packed vertex buffer for all inputs.
+    ///
+    /// Elements are laid out in the order of their locations.
+    pub fn vertex_attrib(&self) -> VertexAttrib {
+        let mut offset = 0;
+
+        let elements = self
+            .inputs
+            .iter()
+            .map(|input| {
+                let element = VertexAttribBufferElementInfo {
+                    location: input.location,
+                    format: input.format,
+                    offset,
+                };
+
+                offset += u32::from(input.format.surface_desc().bits / 8);
+
+                element
+            })
+            .collect();
+
+        VertexAttrib {
+            buffer_infos: vec![VertexAttribBufferInfo {
+                stride: offset as usize,
+                index: 0,
+                elements,
+            }],
+        }
+    }
+
+    /// Check that the pipeline provides everything the shader uses.
+    pub(crate) fn validate(
+        &self,
+        pipeline: &PipelineInterface,
+        specialization: &[Specialization],
+    ) -> Result<(), ValidationError> {
+        for desc in &self.descriptors {
+            let params = pipeline
+                .sets
+                .get(&desc.set)
+                .ok_or(ValidationError::MissingSet(desc.set))?;
+
+            let (_, ty) = params
+                .iter()
+                .find(|(binding, _)| *binding == desc.binding)
+                .ok_or(ValidationError::MissingBinding {
+                    set: desc.set,
+                    binding: desc.binding,
+                })?;
+
+            if !desc.ty.accepts(*ty) {
+                return Err(ValidationError::DescriptorTypeMismatch {
+                    set: desc.set,
+                    binding: desc.binding,
+                    expected: desc.ty,
+                });
+            }
+        }
+
+        if let Some(used) = &self.push_constants {
+            let provided = pipeline.push_constants.clone().unwrap_or(0..0);
+
+            if used.start < provided.start || used.end > provided.end {
+                return Err(ValidationError::PushConstantsOutOfRange(used.clone()));
+            }
+        }
+
+        if let Some(attrib) = pipeline.vertex_attrib {
+            for input in &self.inputs {
+                let provided = attrib
+                    .buffer_infos
+                    .iter()
+                    .flat_map(|buffer| buffer.elements.iter())
+                    .any(|elem| elem.location == input.location);
+
+                if !provided {
+                    return Err(ValidationError::MissingVertexInput(input.location));
+                }
+            }
+        }
+
+        // Constants the shader doesn't declare are ignored, the same specialization is given
+        // to all stages of a pipeline.
+        for spec in specialization {
+            let constant = self
+                .specialization_constants
+                .iter()
+                .find(|constant| constant.id == spec.id);
+
+            let constant = match constant {
+                Some(constant) => constant,
+                None => continue,
+            };
+
+            if constant.size as usize != spec.value.len() {
+                return Err(ValidationError::SpecializationSizeMismatch {
+                    id: spec.id,
+                    expected: constant.size,
+                    found: spec.value.len() as u32,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Reflect the interface of the entry point `entry_point` for the shader stage `stage` of a
+/// SPIR-V module.
+pub fn reflect(
+    spirv: &[u8],
+    entry_point: &str,
+    stage: ShaderStage,
+) -> Result<ShaderReflection, ReflectError> {
+    let words = words(spirv)?;
+    let module = Module::parse(&words)?;
+
+    let key = (execution_model(stage), entry_point.to_string());
+
+    let (entry, interface) = module.entry_points.get(&key).ok_or_else(|| {
+        let other_stage = module
+            .entry_points
+            .keys()
+            .any(|(_, name)| name == entry_point);
+
+        if other_stage {
+            ReflectError::StageMismatch {
+                entry_point: entry_point.to_string(),
+                stage,
+            }
+        } else {
+            ReflectError::MissingEntryPoint(entry_point.to_string())
+        }
+    })?;
+
+    let used = module.used_ids(*entry);
+
+    let mut descriptors = vec![];
+    let mut push_constants: Option<Range<u32>> = None;
+    let mut inputs = vec![];
+
+    for &(id, ty, storage) in &module.variables {
+        let pointee = match module.types.get(&ty) {
+            Some(Type::Pointer(pointee)) => *pointee,
+            _ => continue,
+        };
+
+        match storage {
+            STORAGE_UNIFORM_CONSTANT | STORAGE_UNIFORM | STORAGE_STORAGE_BUFFER => {
+                if !used.contains(&id) {
+                    continue;
+                }
+
+                let set = module.decoration(id, DECORATION_DESCRIPTOR_SET);
+                let binding = module.decoration(id, DECORATION_BINDING);
+
+                if let (Some(set), Some(binding)) = (set, binding) {
+                    if let Some((ty, count)) = module.descriptor_type(pointee, storage) {
+                        descriptors.push(DescriptorBinding {
+                            set,
+                            binding,
+                            ty,
+                            count,
+                        });
+                    }
+                }
+            }
+            STORAGE_PUSH_CONSTANT => {
+                if !used.contains(&id) {
+                    continue;
+                }
+
+                if let Some(range) = module.struct_range(pointee) {
+                    push_constants = Some(match push_constants {
+                        Some(prev) => prev.start.min(range.start)..prev.end.max(range.end),
+                        None => range,
+                    });
+                }
+            }
+            STORAGE_INPUT => {
+                if !interface.contains(&id) || module.decoration(id, DECORATION_BUILT_IN).is_some()
+                {
+                    continue;
+                }
+
+                if let Some(location) = module.decoration(id, DECORATION_LOCATION) {
+                    module.inputs(pointee, location, &mut inputs);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut specialization_constants = module
+        .spec_constants
+        .iter()
+        .filter_map(|&(id, ty)| {
+            let id = module.decoration(id, DECORATION_SPEC_ID)?;
+
+            let size = match module.types.get(&ty)? {
+                Type::Bool => 4,
+                Type::Int { width, .. } | Type::Float { width } => width / 8,
+                _ => return None,
+            };
+
+            Some(SpecializationConstant { id, size })
+        })
+        .collect::<Vec<_>>();
+
+    descriptors.sort_by_key(|desc| (desc.set, desc.binding));
+    inputs.sort_by_key(|input| input.location);
+    specialization_constants.sort_by_key(|constant| constant.id);
+
+    Ok(ShaderReflection {
+        descriptors,
+        push_constants,
+        inputs,
+        specialization_constants,
+    })
+}
+
+/// SPIR-V execution model of a shader stage.
+fn execution_model(stage: ShaderStage) -> u32 {
+    match stage {
+        ShaderStage::Vertex => 0,
+        ShaderStage::TessControl => 1,
+        ShaderStage::TessEval => 2,
+        ShaderStage::Geometry => 3,
+        ShaderStage::Fragment => 4,
+        ShaderStage::Compute => 5,
+    }
+}
+
+/// Split the module into words, handling both byte orders.
+fn words(spirv: &[u8]) -> Result<Vec<u32>, ReflectError> {
+    if spirv.len() % 4 != 0 || spirv.len() < HEADER_WORDS * 4 {
+        return Err(ReflectError::InvalidModule);
+    }
+
+    let words = spirv
+        .chunks(4)
+        .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect::<Vec<_>>();
+
+    if words[0] == MAGIC {
+        Ok(words)
+    } else if words[0] == MAGIC.swap_bytes() {
+        Ok(words.into_iter().map(u32::swap_bytes).collect())
+    } else {
+        Err(ReflectError::InvalidModule)
+    }
+}
+
+enum Type {
+    Bool,
+    Int { width: u32, signed: bool },
+    Float { width: u32 },
+    Vector { component: u32, count: u32 },
+    Matrix { column: u32, count: u32 },
+    Image { dim: u32, sampled: u32 },
+    Sampler,
+    SampledImage,
+    Array { element: u32, length: u32 },
+    RuntimeArray { element: u32 },
+    Struct { members: Vec<u32> },
+    Pointer(u32),
+}
+
+/// The parts of a SPIR-V module needed for reflection.
+#[derive(Default)]
+struct Module {
+    /// Entry point function and interface variables by execution model and name.
+    entry_points: HashMap<(u32, String), (u32, Vec<u32>)>,
+    types: HashMap<u32, Type>,
+    /// Values of scalar constants, used for array lengths.
+    constants: HashMap<u32, u32>,
+    /// Result id and type of all scalar specialization constants.
+    spec_constants: Vec<(u32, u32)>,
+    /// Result id, pointer type and storage class of all global variables.
+    variables: Vec<(u32, u32, u32)>,
+    /// Ids referenced by the instructions of each function.
+    function_refs: HashMap<u32, HashSet<u32>>,
+    decorations: HashMap<(u32, u32), u32>,
+    member_decorations: HashMap<(u32, u32, u32), u32>,
+}
+
+impl Module {
+    fn parse(words: &[u32]) -> Result<Self, ReflectError> {
+        let mut module = Module::default();
+        let mut function = None;
+
+        let mut offset = HEADER_WORDS;
+
+        while offset < words.len() {
+            let word_count = (words[offset] >> 16) as usize;
+            let opcode = words[offset] & 0xffff;
+
+            if word_count == 0 || offset + word_count > words.len() {
+                return Err(ReflectError::Truncated);
+            }
+
+            let ops = &words[offset + 1..offset + word_count];
+            offset += word_count;
+
+            let op = |index: usize| ops.get(index).cloned().ok_or(ReflectError::Truncated);
+
+            if let Some(function) = function {
+                let refs = module.function_refs.entry(function).or_default();
+                refs.extend(ops.iter().cloned());
+            }
+
+            match opcode {
+                OP_ENTRY_POINT => {
+                    let (name, name_words) = string(&ops[2.min(ops.len())..])?;
+                    let interface = ops[2 + name_words..].to_vec();
+
+                    module
+                        .entry_points
+                        .insert((op(0)?, name), (op(1)?, interface));
+                }
+                OP_TYPE_BOOL => {
+                    module.types.insert(op(0)?, Type::Bool);
+                }
+                OP_TYPE_INT => {
+                    let ty = Type::Int {
+                        width: op(1)?,
+                        signed: op(2)? != 0,
+                    };
+                    module.types.insert(op(0)?, ty);
+                }
+                OP_TYPE_FLOAT => {
+                    module.types.insert(op(0)?, Type::Float { width: op(1)? });
+                }
+                OP_TYPE_VECTOR => {
+                    let ty = Type::Vector {
+                        component: op(1)?,
+                        count: op(2)?,
+                    };
+                    module.types.insert(op(0)?, ty);
+                }
+                OP_TYPE_MATRIX => {
+                    let ty = Type::Matrix {
+                        column: op(1)?,
+                        count: op(2)?,
+                    };
+                    module.types.insert(op(0)?, ty);
+                }
+                OP_TYPE_IMAGE => {
+                    let ty = Type::Image {
+                        dim: op(2)?,
+                        sampled: op(6)?,
+                    };
+                    module.types.insert(op(0)?, ty);
+                }
+                OP_TYPE_SAMPLER => {
+                    module.types.insert(op(0)?, Type::Sampler);
+                }
+                OP_TYPE_SAMPLED_IMAGE => {
+                    module.types.insert(op(0)?, Type::SampledImage);
+                }
+                OP_TYPE_ARRAY => {
+                    let ty = Type::Array {
+                        element: op(1)?,
+                        length: op(2)?,
+                    };
+                    module.types.insert(op(0)?, ty);
+                }
+                OP_TYPE_RUNTIME_ARRAY => {
+                    let ty = Type::RuntimeArray { element: op(1)? };
+                    module.types.insert(op(0)?, ty);
+                }
+                OP_TYPE_STRUCT => {
+                    let ty = Type::Struct {
+                        members: ops[1.min(ops.len())..].to_vec(),
+                    };
+                    module.types.insert(op(0)?, ty);
+                }
+                OP_TYPE_POINTER => {
+                    module.types.insert(op(0)?, Type::Pointer(op(2)?));
+                }
+                OP_CONSTANT => {
+                    module.constants.insert(op(1)?, op(2)?);
+                }
+                OP_SPEC_CONSTANT_TRUE | OP_SPEC_CONSTANT_FALSE | OP_SPEC_CONSTANT => {
+                    module.spec_constants.push((op(1)?, op(0)?));
+                }
+                OP_VARIABLE if function.is_none() => {
+                    module.variables.push((op(1)?, op(0)?, op(2)?));
+                }
+                OP_DECORATE => {
+                    let value = ops.get(2).cloned().unwrap_or(0);
+                    module.decorations.insert((op(0)?, op(1)?), value);
+                }
+                OP_MEMBER_DECORATE => {
+                    let value = ops.get(3).cloned().unwrap_or(0);
+                    module
+                        .member_decorations
+                        .insert((op(0)?, op(1)?, op(2)?), value);
+                }
+                OP_FUNCTION => {
+                    function = Some(op(1)?);
+                }
+                OP_FUNCTION_END => {
+                    function = None;
+                }
+                _ => {}
+            }
+        }
+
+        Ok(module)
+    }
+
+    fn decoration(&self, id: u32, decoration: u32) -> Option<u32> {
+        self.decorations.get(&(id, decoration)).cloned()
+    }
+
+    fn member_decoration(&self, id: u32, member: u32, decoration: u32) -> Option<u32> {
+        self.member_decorations
+            .get(&(id, member, decoration))
+            .cloned()
+    }
+
+    /// All ids referenced by the function `entry` and the functions it calls.
+    fn used_ids(&self, entry: u32) -> HashSet<u32> {
+        let mut used = HashSet::new();
+        let mut visited = HashSet::new();
+        let mut stack = vec![entry];
+
+        while let Some(function) = stack.pop() {
+            if !visited.insert(function) {
+                continue;
+            }
+
+            if let Some(refs) = self.function_refs.get(&function) {
+                used.extend(refs.iter().cloned());
+
+                stack.extend(
+                    refs.iter()
+                        .filter(|id| self.function_refs.contains_key(id))
+                        .cloned(),
+                );
+            }
+        }
+
+        used
+    }
+
+    fn descriptor_type(&self, ty: u32, storage: u32) -> Option<(DescriptorType, u32)> {
+        let (ty, count) = match self.types.get(&ty)? {
+            Type::Array { element, length } => (*element, *self.constants.get(length)?),
+            Type::RuntimeArray { element } => (*element, 0),
+            _ => (ty, 1),
+        };
+
+        let desc_ty = match self.types.get(&ty)? {
+            Type::Sampler => DescriptorType::Sampler,
+            Type::SampledImage => DescriptorType::CombinedImageSampler,
+            Type::Image { dim, sampled } => match (*dim, *sampled) {
+                (DIM_SUBPASS_DATA, _) => DescriptorType::InputAttachment,
+                (DIM_BUFFER, 2) => DescriptorType::StorageTexelBuffer,
+                (DIM_BUFFER, _) => DescriptorType::UniformTexelBuffer,
+                (_, 2) => DescriptorType::StorageImage,
+                (_, _) => DescriptorType::SampledImage,
+            },
+            Type::Struct { .. } if storage == STORAGE_STORAGE_BUFFER => {
+                DescriptorType::StorageBuffer
+            }
+            Type::Struct { .. } if self.decoration(ty, DECORATION_BUFFER_BLOCK).is_some() => {
+                DescriptorType::StorageBuffer
+            }
+            Type::Struct { .. } if self.decoration(ty, DECORATION_BLOCK).is_some() => {
+                DescriptorType::UniformBuffer
+            }
+            _ => return None,
+        };
+
+        Some((desc_ty, count))
+    }
+
+    /// Byte range covered by the members of a struct.
+    fn struct_range(&self, ty: u32) -> Option<Range<u32>> {
+        let members = match self.types.get(&ty)? {
+            Type::Struct { members } => members,
+            _ => return None,
+        };
+
+        members
+            .iter()
+            .enumerate()
+            .filter_map(|(i, member)| {
+                let i = i as u32;
+
+                let offset = self
+                    .member_decoration(ty, i, DECORATION_OFFSET)
+                    .unwrap_or(0);
+                let layout =
+                    self.member_decoration(ty, i, DECORATION_MATRIX_STRIDE)
+                        .map(|stride| {
+                            let row_major = self
+                                .member_decoration(ty, i, DECORATION_ROW_MAJOR)
+                                .is_some();
+                            (stride, row_major)
+                        });
+
+                let size = self.size(*member, layout)?;
+
+                Some(offset..offset + size)
+            })
+            .fold(None, |range: Option<Range<u32>>, member| match range {
+                Some(range) => Some(range.start.min(member.start)..range.end.max(member.end)),
+                None => Some(member),
+            })
+    }
+
+    /// Size in bytes of a type inside a block.
+    ///
+    /// `matrix_layout` is the stride and whether the matrix is row-major for matrix members.
+    fn size(&self, ty: u32, matrix_layout: Option<(u32, bool)>) -> Option<u32> {
+        let size = match self.types.get(&ty)? {
+            Type::Bool => 4,
+            Type::Int { width, .. } | Type::Float { width } => width / 8,
+            Type::Vector { component, count } => count * self.size(*component, None)?,
+            Type::Matrix { column, count } => match matrix_layout {
+                Some((stride, false)) => count * stride,
+                Some((stride, true)) => match self.types.get(column)? {
+                    Type::Vector { count: rows, .. } => rows * stride,
+                    _ => return None,
+                },
+                None => count * self.size(*column, None)?,
+            },
+            Type::Array { element, length } => {
+                let length = *self.constants.get(length)?;
+
+                match self.decoration(ty, DECORATION_ARRAY_STRIDE) {
+                    Some(stride) => length * stride,
+                    None => length * self.size(*element, matrix_layout)?,
+                }
+            }
+            Type::RuntimeArray { .. } => 0,
+            Type::Struct { .. } => self.struct_range(ty).map(|range| range.end).unwrap_or(0),
+            _ => return None,
+        };
+
+        Some(size)
+    }
+
+    /// Add the inputs for a variable of type `ty` starting at `location`.
+    fn inputs(&self, ty: u32, location: u32, inputs: &mut Vec<StageInput>) -> Option<u32> {
+        let locations = match self.types.get(&ty)? {
+            Type::Matrix { column, count } => {
+                for i in 0..*count {
+                    self.inputs(*column, location + i, inputs)?;
+                }
+                *count
+            }
+            Type::Array { element, length } => {
+                let mut used = 0;
+                for _ in 0..*self.constants.get(length)? {
+                    used += self.inputs(*element, location + used, inputs)?;
+                }
+                used
+            }
+            _ => {
+                let format = self.format(ty)?;
+
+                inputs.push(StageInput { location, format });
+
+                // 64 bit vectors with three or four components take up two locations
+                if format.surface_desc().bits > 128 {
+                    2
+                } else {
+                    1
+                }
+            }
+        };
+
+        Some(locations)
+    }
+
+    /// The format matching a scalar or vector type.
+    fn format(&self, ty: u32) -> Option<Format> {
+        let (component, count) = match self.types.get(&ty)? {
+            Type::Vector { component, count } => (*component, *count),
+            _ => (ty, 1),
+        };
+
+        let formats = match self.types.get(&component)? {
+            Type::Float { width: 16 } => [
+                Format::R16Sfloat,
+                Format::Rg16Sfloat,
+                Format::Rgb16Sfloat,
+                Format::Rgba16Sfloat,
+            ],
+            Type::Float { width: 32 } => [
+                Format::R32Sfloat,
+                Format::Rg32Sfloat,
+                Format::Rgb32Sfloat,
+                Format::Rgba32Sfloat,
+            ],
+            Type::Float { width: 64 } => [
+                Format::R64Sfloat,
+                Format::Rg64Sfloat,
+                Format::Rgb64Sfloat,
+                Format::Rgba64Sfloat,
+            ],
+            Type::Int {
+                width: 8,
+                signed: false,
+            } => [
+                Format::R8Uint,
+                Format::Rg8Uint,
+                Format::Rgb8Uint,
+                Format::Rgba8Uint,
+            ],
+            Type::Int {
+                width: 8,
+                signed: true,
+            } => [
+                Format::R8Sint,
+                Format::Rg8Sint,
+                Format::Rgb8Sint,
+                Format::Rgba8Sint,
+            ],
+            Type::Int {
+                width: 16,
+                signed: false,
+            } => [
+                Format::R16Uint,
+                Format::Rg16Uint,
+                Format::Rgb16Uint,
+                Format::Rgba16Uint,
+            ],
+            Type::Int {
+                width: 16,
+                signed: true,
+            } => [
+                Format::R16Sint,
+                Format::Rg16Sint,
+                Format::Rgb16Sint,
+                Format::Rgba16Sint,
+            ],
+            Type::Int {
+                width: 32,
+                signed: false,
+            } => [
+                Format::R32Uint,
+                Format::Rg32Uint,
+                Format::Rgb32Uint,
+                Format::Rgba32Uint,
+            ],
+            Type::Int {
+                width: 32,
+                signed: true,
+            } => [
+                Format::R32Sint,
+                Format::Rg32Sint,
+                Format::Rgb32Sint,
+                Format::Rgba32Sint,
+            ],
+            _ => return None,
+        };
+
+        formats.get(count.checked_sub(1)? as usize).cloned()
+    }
+}
+
+/// Read a nul-terminated literal string, returning it and the number of words it occupies.
+fn string(words: &[u32]) -> Result<(String, usize), ReflectError> {
+    let mut bytes = vec![];
+
+    for (i, word) in words.iter().enumerate() {
+        for byte in &word.to_le_bytes() {
+            if *byte == 0 {
+                let string = String::from_utf8_lossy(&bytes).into_owned();
+                return Ok((string, i + 1));
+            }
+
+            bytes.push(*byte);
+        }
+    }
+
+    Err(ReflectError::Truncated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Hand-assembled SPIR-V 1.0 module equivalent to:
+    //
+    //     layout(location = 0) in vec3 position;
+    //     layout(location = 1) in vec2 uv;
+    //     layout(location = 2) in uint id;
+    //     layout(location = 0) out vec2 out_uv;
+    //
+    //     layout(set = 1, binding = 0) uniform Globals { mat4 view_proj; };
+    //     layout(set = 0, binding = 5) uniform texture2D unused;
+    //     layout(push_constant) uniform Push { mat4 model; vec4 color; };
+    //     layout(constant_id = 3) const float SCALE = 1.0;
+    //
+    // `main` reads all inputs, the uniform block and the push constants, but not `unused`.
+    static VERTEX: &[u8] = include_bytes!("test_shaders/textured.vert.spv");
+
+    // Hand-assembled SPIR-V 1.3 compute module equivalent to:
+    //
+    //     layout(set = 0, binding = 0) uniform texture2D tex;
+    //     layout(set = 0, binding = 1) uniform sampler samp;
+    //     layout(set = 0, binding = 2) uniform sampler2D combined;
+    //     layout(set = 0, binding = 3) buffer Data { float values[]; }; // BufferBlock
+    //     layout(set = 1, binding = 0, rgba8) uniform image2D images[4];
+    //     layout(set = 1, binding = 1) buffer Counter { uint counter; }; // StorageBuffer class
+    //     layout(set = 1, binding = 2) uniform textureBuffer texels;
+    //     layout(push_constant) uniform Push { layout(offset = 16) uint a; uint b; };
+    //     layout(constant_id = 0) const bool FLAG = false;
+    //     layout(constant_id = 7) const int COUNT = 2;
+    //
+    // All resources are used in a function that is called by `main`.
+    static COMPUTE: &[u8] = include_bytes!("test_shaders/storage.comp.spv");
+
+    #[test]
+    fn vertex_interface_is_reflected() {
+        let reflection = reflect(VERTEX, "main", ShaderStage::Vertex).unwrap();
+
+        assert_eq!(
+            reflection.descriptors,
+            vec![DescriptorBinding {
+                set: 1,
+                binding: 0,
+                ty: DescriptorType::UniformBuffer,
+                count: 1,
+            }]
+        );
+
+        assert_eq!(reflection.push_constants, Some(0..80));
+
+        assert_eq!(
+            reflection.inputs,
+            vec![
+                StageInput {
+                    location: 0,
+                    format: Format::Rgb32Sfloat,
+                },
+                StageInput {
+                    location: 1,
+                    format: Format::Rg32Sfloat,
+                },
+                StageInput {
+                    location: 2,
+                    format: Format::R32Uint,
+                },
+            ]
+        );
+
+        assert_eq!(
+            reflection.specialization_constants,
+            vec![SpecializationConstant { id: 3, size: 4 }]
+        );
+    }
+
+    #[test]
+    fn compute_descriptors_are_reflected() {
+        let reflection = reflect(COMPUTE, "main", ShaderStage::Compute).unwrap();
+
+        let descriptors = reflection
+            .descriptors
+            .iter()
+            .map(|desc| (desc.set, desc.binding, desc.ty, desc.count))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            descriptors,
+            vec![
+                (0, 0, DescriptorType::SampledImage, 1),
+                (0, 1, DescriptorType::Sampler, 1),
+                (0, 2, DescriptorType::CombinedImageSampler, 1),
+                (0, 3, DescriptorType::StorageBuffer, 1),
+                (1, 0, DescriptorType::StorageImage, 4),
+                (1, 1, DescriptorType::StorageBuffer, 1),
+                (1, 2, DescriptorType::UniformTexelBuffer, 1),
+            ]
+        );
+
+        assert_eq!(reflection.push_constants, Some(16..24));
+        assert!(reflection.inputs.is_empty());
+
+        assert_eq!(
+            reflection.specialization_constants,
+            vec![
+                SpecializationConstant { id: 0, size: 4 },
+                SpecializationConstant { id: 7, size: 4 },
+            ]
+        );
+    }
+
+    #[test]
+    fn big_endian_modules_are_supported() {
+        let swapped = VERTEX
+            .chunks(4)
+            .flat_map(|word| word.iter().rev().cloned())
+            .collect::<Vec<_>>();
+
+        let reflection = reflect(&swapped, "main", ShaderStage::Vertex).unwrap();
+
+        assert_eq!(reflection.inputs.len(), 3);
+    }
+
+    #[test]
+    fn invalid_modules_are_rejected() {
+        assert_eq!(
+            reflect(VERTEX, "VertexMain", ShaderStage::Vertex).err(),
+            Some(ReflectError::MissingEntryPoint("VertexMain".to_string()))
+        );
+
+        assert_eq!(
+            reflect(VERTEX, "main", ShaderStage::Fragment).err(),
+            Some(ReflectError::StageMismatch {
+                entry_point: "main".to_string(),
+                stage: ShaderStage::Fragment,
+            })
+        );
+
+        assert_eq!(
+            reflect(&VERTEX[4..], "main", ShaderStage::Vertex).err(),
+            Some(ReflectError::InvalidModule)
+        );
+
+        // let the last instruction claim one more word than the module contains
+        let mut truncated = VERTEX.to_vec();
+        let last = truncated.len() - 4;
+        truncated[last + 2] += 1;
+
+        assert_eq!(
+            reflect(&truncated, "main", ShaderStage::Vertex).err(),
+            Some(ReflectError::Truncated)
+        );
+    }
+
+    #[test]
+    fn materials_and_vertex_attribs_are_derived() {
+        let vertex = reflect(VERTEX, "main", ShaderStage::Vertex).unwrap();
+
+        assert_eq!(
+            vertex.material_parameters(1).unwrap(),
+            vec![(0, MaterialParameterType::UniformBuffer)]
+        );
+        assert!(vertex.material_parameters(0).unwrap().is_empty());
+
+        let attrib = vertex.vertex_attrib();
+
+        assert_eq!(attrib.buffer_infos.len(), 1);
+        assert_eq!(attrib.buffer_infos[0].stride, 24);
+
+        let elements = attrib.buffer_infos[0]
+            .elements
+            .iter()
+            .map(|elem| (elem.location, elem.offset))
+            .collect::<Vec<_>>();
+
+        assert_eq!(elements, vec![(0, 0), (1, 12), (2, 20)]);
+
+        let compute = reflect(COMPUTE, "main", ShaderStage::Compute).unwrap();
+
+        assert_eq!(
+            compute.material_parameters(0).err(),
+            Some(ReflectError::UnsupportedDescriptor { set: 0, binding: 2 })
+        );
+        assert_eq!(
+            compute.material_parameters(1).err(),
+            Some(ReflectError::UnsupportedDescriptor { set: 1, binding: 0 })
+        );
+    }
+
+    #[test]
+    fn pipelines_are_validated() {
+        let vertex = reflect(VERTEX, "main", ShaderStage::Vertex).unwrap();
+
+        let dynamic = [(0, MaterialParameterType::UniformBufferDynamic)];
+        let storage = [(0, MaterialParameterType::StorageBuffer)];
+
+        let attrib = vertex.vertex_attrib();
+        let mut partial_attrib = attrib.clone();
+        partial_attrib.buffer_infos[0].elements.pop();
+
+        let mut pipeline = PipelineInterface {
+            sets: BTreeMap::new(),
+            push_constants: Some(0..80),
+            vertex_attrib: Some(&attrib),
+        };
+        pipeline.sets.insert(1, &dynamic[..]);
+
+        assert_eq!(
+            vertex.validate(&pipeline, &[Specialization::new(3, 1.0f32)]),
+            Ok(())
+        );
+        assert_eq!(
+            vertex.validate(&pipeline, &[Specialization::new(3, 1.0f64)]),
+            Err(ValidationError::SpecializationSizeMismatch {
+                id: 3,
+                expected: 4,
+                found: 8,
+            })
+        );
+        assert_eq!(
+            vertex.validate(&pipeline, &[Specialization::new(4, 0u32)]),
+            Ok(())
+        );
+
+        pipeline.vertex_attrib = Some(&partial_attrib);
+        assert_eq!(
+            vertex.validate(&pipeline, &[]),
+            Err(ValidationError::MissingVertexInput(2))
+        );
+        pipeline.vertex_attrib = Some(&attrib);
+
+        pipeline.push_constants = Some(0..64);
+        assert_eq!(
+            vertex.validate(&pipeline, &[]),
+            Err(ValidationError::PushConstantsOutOfRange(0..80))
+        );
+        pipeline.push_constants = Some(0..80);
+
+        pipeline.sets.insert(1, &storage[..]);
+        assert_eq!(
+            vertex.validate(&pipeline, &[]),
+            Err(ValidationError::DescriptorTypeMismatch {
+                set: 1,
+                binding: 0,
+                expected: DescriptorType::UniformBuffer,
+            })
+        );
+
+        pipeline.sets.insert(1, &[]);
+        assert_eq!(
+            vertex.validate(&pipeline, &[]),
+            Err(ValidationError::MissingBinding { set: 1, binding: 0 })
+        );
+
+        pipeline.sets.clear();
+        assert_eq!(
+            vertex.validate(&pipeline, &[]),
+            Err(ValidationError::MissingSet(1))
+        );
+    }
+}
@@ -0,0 +1,156 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Reloading of shaders when their SPIR-V files change on disk.
+//!
+//! The [`ShaderWatcher`] polls the modification time of every watched file. Calling
+//! [`ShaderWatcher::poll`] once per frame replaces the programs of all shaders whose files
+//! changed since the last call, the graphs using them pick up the new programs on their
+//! next execution.
+//!
+//! [`ShaderWatcher`]: struct.ShaderWatcher.html
+//! [`ShaderWatcher::poll`]: struct.ShaderWatcher.html#method.poll
+
+use super::*;
+
+use crate::Context;
+
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Handle to a shader of any type.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, From)]
+pub enum WatchedShader {
+    Compute(ComputeShaderHandle),
+    Vertex(VertexShaderHandle),
+    Fragment(FragmentShaderHandle),
+    Geometry(GeometryShaderHandle),
+    TessControl(TessControlShaderHandle),
+    TessEval(TessEvalShaderHandle),
+}
+
+/// Outcome of reloading a watched file.
+#[derive(Debug)]
+pub enum WatchEvent {
+    /// The shader was replaced with the new content of the file.
+    Reloaded(WatchedShader, PathBuf),
+    /// The file changed but could not be read.
+    ReadFailed(WatchedShader, PathBuf, std::io::Error),
+    /// The new content of the file is not a valid program, the shader keeps its old program.
+    Invalid(WatchedShader, PathBuf, ShaderError),
+    /// The shader the file was watched for does not exist anymore and is no longer watched.
+    Removed(WatchedShader, PathBuf),
+}
+
+struct WatchEntry {
+    shader: WatchedShader,
+    path: PathBuf,
+    entry_point: EntryPoint,
+    modified: Option<SystemTime>,
+}
+
+/// Watches SPIR-V files and replaces the programs of the shaders created from them.
+#[derive(Default)]
+pub struct ShaderWatcher {
+    entries: Vec<WatchEntry>,
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+}
+
+impl ShaderWatcher {
+    /// Create a watcher without any watched files.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Watch the file at `path` and replace the program of `shader` when it changes.
+    ///
+    /// The current content of the file is assumed to be already used by the shader.
+    pub fn watch(
+        &mut self,
+        shader: impl Into<WatchedShader>,
+        path: impl Into<PathBuf>,
+        entry_point: impl Into<EntryPoint>,
+    ) {
+        let path = path.into();
+
+        self.entries.push(WatchEntry {
+            shader: shader.into(),
+            modified: modified(&path),
+            path,
+            entry_point: entry_point.into(),
+        });
+    }
+
+    /// Stop watching all files associated with `shader`.
+    pub fn unwatch(&mut self, shader: impl Into<WatchedShader>) {
+        let shader = shader.into();
+        self.entries.retain(|entry| entry.shader != shader);
+    }
+
+    /// Check all watched files for changes and replace the programs of the affected shaders.
+    ///
+    /// A file that can't be read is tried again on the next call.
+    pub fn poll(&mut self, ctx: &mut Context) -> Vec<WatchEvent> {
+        let mut events = vec![];
+        let mut removed = vec![];
+
+        for entry in &mut self.entries {
+            let current = modified(&entry.path);
+
+            if current.is_none() || current == entry.modified {
+                continue;
+            }
+
+            let data = match std::fs::read(&entry.path) {
+                Ok(data) => data,
+                Err(err) => {
+                    events.push(WatchEvent::ReadFailed(
+                        entry.shader,
+                        entry.path.clone(),
+                        err,
+                    ));
+                    continue;
+                }
+            };
+
+            entry.modified = current;
+
+            let info = ShaderInfo {
+                spirv_content: &data,
+                entry_point: entry.entry_point.clone(),
+            };
+
+            let result = match entry.shader {
+                WatchedShader::Compute(handle) => ctx.compute_shader_replace(handle, info),
+                WatchedShader::Vertex(handle) => ctx.vertex_shader_replace(handle, info),
+                WatchedShader::Fragment(handle) => ctx.fragment_shader_replace(handle, info),
+                WatchedShader::Geometry(handle) => ctx.geometry_shader_replace(handle, info),
+                WatchedShader::TessControl(handle) => ctx.tess_control_shader_replace(handle, info),
+                WatchedShader::TessEval(handle) => ctx.tess_eval_shader_replace(handle, info),
+            };
+
+            let path = entry.path.clone();
+
+            match result {
+                Ok(()) => events.push(WatchEvent::Reloaded(entry.shader, path)),
+                Err(ShaderError::InvalidHandle) => {
+                    events.push(WatchEvent::Removed(entry.shader, path));
+                    removed.push(entry.shader);
+                }
+                Err(err) => events.push(WatchEvent::Invalid(entry.shader, path, err)),
+            }
+        }
+
+        self.entries
+            .retain(|entry| !removed.contains(&entry.shader));
+
+        events
+    }
+}
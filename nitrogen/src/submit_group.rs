@@ -8,6 +8,7 @@ use gfx::Device;
 
 use crate::buffer::BufferTypeInternal;
 use crate::image::ImageType;
+use crate::util::allocator::Block;
 
 use crate::device::DeviceContext;
 use crate::*;
@@ -19,7 +20,9 @@ use crate::resources::semaphore_pool::{SemaphoreList, SemaphorePool};
 
 use smallvec::SmallVec;
 
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::rc::Rc;
 use std::sync::Arc;
 
 /// `SubmitGroup`s are used to synchronize access to resources and ensure
@@ -42,18 +45,21 @@ pub struct SubmitGroup {
     sem_pool: SemaphorePool,
 
     pool_graphics: CommandPoolGraphics,
+    pool_graphics_compute: CommandPoolCompute,
     pool_compute: CommandPoolCompute,
     pool_transfer: CommandPoolTransfer,
 
     sem_list: SemaphoreList,
     res_destroys: ResourceList,
 
+    readbacks: Vec<PendingReadback>,
+
     graph_resources: HashMap<graph::GraphHandle, graph::GraphResources>,
 }
 
 impl SubmitGroup {
     pub(crate) unsafe fn new(device: Arc<DeviceContext>) -> Self {
-        let (gfx, cmpt, trns) = {
+        let (gfx, gfx_cmpt, cmpt, trns) = {
             let gfx = device
                 .device
                 .create_command_pool_typed(
@@ -61,6 +67,13 @@ impl SubmitGroup {
                     gfx::pool::CommandPoolCreateFlags::empty(),
                 )
                 .unwrap();
+            let gfx_cmpt = device
+                .device
+                .create_command_pool_typed(
+                    device.graphics_compute_queue_group(),
+                    gfx::pool::CommandPoolCreateFlags::empty(),
+                )
+                .unwrap();
             let cmpt = device
                 .device
                 .create_command_pool_typed(
@@ -78,6 +91,7 @@ impl SubmitGroup {
 
             (
                 CommandPoolGraphics::new(gfx),
+                CommandPoolCompute::new(gfx_cmpt),
                 CommandPoolCompute::new(cmpt),
                 CommandPoolTransfer::new(trns),
             )
@@ -85,6 +99,7 @@ impl SubmitGroup {
 
         SubmitGroup {
             pool_graphics: gfx,
+            pool_graphics_compute: gfx_cmpt,
             pool_compute: cmpt,
             pool_transfer: trns,
 
@@ -93,6 +108,8 @@ impl SubmitGroup {
 
             res_destroys: ResourceList::new(device),
 
+            readbacks: vec![],
+
             graph_resources: HashMap::new(),
         }
     }
@@ -125,9 +142,14 @@ impl SubmitGroup {
 
         self.sem_list.advance();
 
+        for readback in self.readbacks.drain(..) {
+            readback.finish(&ctx.device_ctx, &mut self.res_destroys);
+        }
+
         self.res_destroys.free_resources(ctx);
 
         self.pool_graphics.reset();
+        self.pool_graphics_compute.reset();
         self.pool_compute.reset();
         self.pool_transfer.reset();
 
@@ -191,11 +213,7 @@ impl SubmitGroup {
                 ),
             target: img.image.raw(),
             families: None,
-            range: gfx::image::SubresourceRange {
-                aspects: img.aspect,
-                levels: 0..1,
-                layers: 0..1,
-            },
+            range: img.subresource_range(),
         };
 
         cmd.pipeline_barrier(
@@ -217,11 +235,7 @@ impl SubmitGroup {
                 }
                 _ => gfx::command::ClearDepthStencil(1.0, 0),
             },
-            &[gfx::image::SubresourceRange {
-                aspects: img.aspect,
-                levels: 0..1,
-                layers: 0..1,
-            }],
+            &[img.subresource_range()],
         );
 
         let exit_barrier = gfx::memory::Barrier::Image {
@@ -231,11 +245,7 @@ impl SubmitGroup {
             )..(gfx::image::Access::empty(), gfx::image::Layout::General),
             target: img.image.raw(),
             families: None,
-            range: gfx::image::SubresourceRange {
-                aspects: img.aspect,
-                levels: 0..1,
-                layers: 0..1,
-            },
+            range: img.subresource_range(),
         };
 
         cmd.pipeline_barrier(
@@ -267,42 +277,54 @@ impl SubmitGroup {
     }
 
     /// Queue the blitting of one image into another.
+    ///
+    /// Both regions need to cover the same number of layers. Returns `None` if a handle is
+    /// invalid or a region lies outside of the levels or layers of its image.
     pub unsafe fn blit_image(
         &mut self,
         ctx: &mut Context,
         dst: image::ImageHandle,
         src: image::ImageHandle,
-        dst_region: std::ops::Range<gfx::image::Offset>,
-        src_region: std::ops::Range<gfx::image::Offset>,
+        dst_region: image::ImageBlitRegion,
+        src_region: image::ImageBlitRegion,
     ) -> Option<()> {
         let image_storage = ctx.image_storage.borrow();
 
         let dst = image_storage.raw(dst)?;
         let src = image_storage.raw(src)?;
 
+        let fits = |img: &image::Image, region: &image::ImageBlitRegion| {
+            region.level < img.levels
+                && region.layers.start < region.layers.end
+                && region.layers.end <= img.layers
+        };
+
+        let layer_count = |region: &image::ImageBlitRegion| region.layers.end - region.layers.start;
+
+        if !fits(dst, &dst_region)
+            || !fits(src, &src_region)
+            || layer_count(&dst_region) != layer_count(&src_region)
+        {
+            return None;
+        }
+
         let blit = transfer::ImageBlit {
             dst: &dst.image,
             src: &src.image,
             filter: gfx::image::Filter::Nearest,
-            subresource_range: gfx::image::SubresourceRange {
-                aspects: dst.aspect,
-                levels: 0..1,
-                layers: 0..1,
-            },
-            // TODO layers? mipmaps?
             copy_information: gfx::command::ImageBlit {
                 src_subresource: gfx::image::SubresourceLayers {
                     aspects: src.aspect,
-                    level: 0,
-                    layers: 0..1,
+                    level: src_region.level,
+                    layers: src_region.layers,
                 },
-                src_bounds: src_region,
+                src_bounds: src_region.bounds,
                 dst_subresource: gfx::image::SubresourceLayers {
                     aspects: dst.aspect,
-                    level: 0,
-                    layers: 0..1,
+                    level: dst_region.level,
+                    layers: dst_region.layers,
                 },
-                dst_bounds: dst_region,
+                dst_bounds: dst_region.bounds,
             },
         };
 
@@ -348,7 +370,11 @@ impl SubmitGroup {
             &ctx.device_ctx,
             &mut sync,
             &mut storages,
-            (&self.pool_graphics, &self.pool_compute),
+            (
+                &self.pool_graphics,
+                &self.pool_graphics_compute,
+                &self.pool_compute,
+            ),
             store,
             graph,
             res,
@@ -444,6 +470,110 @@ impl SubmitGroup {
         )
     }
 
+    /// Queue the generation of all mipmap levels of an [`Image`] from its base level.
+    ///
+    /// Every level is blitted from the previous one, so the image needs to be usable as a
+    /// transfer source and destination and its format has to support blits with `filter`.
+    /// Depth and stencil images are not supported.
+    ///
+    /// [`Image`]: ../../resources/image/struct.Image.html
+    pub unsafe fn image_generate_mipmaps(
+        &mut self,
+        ctx: &mut Context,
+        image: image::ImageHandle,
+        filter: sampler::Filter,
+    ) -> Result<(), image::ImageError> {
+        use gfx::image::Usage;
+
+        let image_storage = ctx.image_storage.borrow();
+
+        let img = image_storage
+            .raw(image)
+            .ok_or(image::ImageError::HandleInvalid)?;
+
+        if !img
+            .usage
+            .contains(Usage::TRANSFER_SRC | Usage::TRANSFER_DST)
+        {
+            return Err(image::ImageError::CantGenerateMipmaps);
+        }
+
+        // Depth and stencil values can't be averaged, so blitting those is not supported.
+        {
+            use gfx::format::{Aspects, ImageFeature};
+            use gfx::PhysicalDevice;
+
+            let mut required = ImageFeature::BLIT_SRC | ImageFeature::BLIT_DST;
+
+            if filter == sampler::Filter::Linear {
+                required |= ImageFeature::SAMPLED_LINEAR;
+            }
+
+            let supported = ctx
+                .device_ctx
+                .adapter
+                .physical_device
+                .format_properties(Some(img.format))
+                .optimal_tiling;
+
+            let depth_stencil = img.aspect.intersects(Aspects::DEPTH | Aspects::STENCIL);
+
+            if depth_stencil || !supported.contains(required) {
+                return Err(image::ImageError::MipmapsUnsupported(img.format));
+            }
+        }
+
+        let generation = transfer::MipmapGeneration {
+            image: &img.image,
+            aspects: img.aspect,
+            dimension: img.dimension,
+            levels: img.levels,
+            layers: img.layers,
+            filter: filter.into(),
+        };
+
+        transfer::generate_mipmaps(
+            &ctx.device_ctx,
+            &self.sem_pool,
+            &mut self.sem_list,
+            &self.pool_graphics,
+            &generation,
+        );
+
+        Ok(())
+    }
+
+    /// Queue the reading of a region of an [`Image`] object into host memory.
+    ///
+    /// The data is available in the returned [`Readback`] once [`wait`] has been called. Rows are
+    /// tightly packed and texels are laid out as described by the requested format.
+    ///
+    /// [`Image`]: ../../resources/image/struct.Image.html
+    /// [`Readback`]: struct.Readback.html
+    /// [`wait`]: #method.wait
+    pub unsafe fn image_read_data(
+        &mut self,
+        ctx: &mut Context,
+        image: image::ImageHandle,
+        info: image::ImageReadInfo,
+    ) -> Result<Readback, image::ImageError> {
+        let mut sync = QueueSyncRefs {
+            res_list: &mut self.res_destroys,
+            sem_list: &mut self.sem_list,
+            sem_pool: &self.sem_pool,
+        };
+
+        let pending = ctx.image_storage.borrow().read_data(
+            &ctx.device_ctx,
+            &mut sync,
+            &self.pool_transfer,
+            image,
+            info,
+        )?;
+
+        Ok(self.add_readback(pending))
+    }
+
     /// Queue the deletion of an [`Image`] object.
     ///
     /// [`Image`]: ../../resources/image/struct.Image.html
@@ -505,6 +635,47 @@ impl SubmitGroup {
         )
     }
 
+    /// Queue the reading of a range of a [device-local] buffer object into host memory.
+    ///
+    /// The data is available in the returned [`Readback`] once [`wait`] has been called.
+    ///
+    /// [device-local]: ../../resources/buffer/struct.DeviceLocalCreateInfo.html
+    /// [`Readback`]: struct.Readback.html
+    /// [`wait`]: #method.wait
+    pub unsafe fn buffer_device_local_read(
+        &mut self,
+        ctx: &mut Context,
+        buffer: buffer::BufferHandle,
+        offset: u64,
+        size: u64,
+    ) -> Result<Readback, buffer::BufferError> {
+        let mut sync = QueueSyncRefs {
+            res_list: &mut self.res_destroys,
+            sem_list: &mut self.sem_list,
+            sem_pool: &self.sem_pool,
+        };
+
+        let pending = ctx.buffer_storage.borrow().device_local_read(
+            &ctx.device_ctx,
+            &mut sync,
+            &self.pool_transfer,
+            buffer,
+            offset..offset + size,
+        )?;
+
+        Ok(self.add_readback(pending))
+    }
+
+    fn add_readback(&mut self, pending: PendingReadback) -> Readback {
+        let readback = Readback {
+            data: pending.target.clone(),
+        };
+
+        self.readbacks.push(pending);
+
+        readback
+    }
+
     /// Queue the deltion of a [`Buffer`] object.
     ///
     /// [`Buffer`]: ../../resources/buffer/struct.Buffer.html
@@ -566,6 +737,12 @@ impl SubmitGroup {
                 .device
                 .destroy_command_pool(pool.pool.into_raw());
         }
+        {
+            let pool = self.pool_graphics_compute.0.into_impl();
+            ctx.device_ctx
+                .device
+                .destroy_command_pool(pool.pool.into_raw());
+        }
         {
             let pool = self.pool_compute.0.into_impl();
             ctx.device_ctx
@@ -589,12 +766,122 @@ pub(crate) struct QueueSyncRefs<'a> {
     pub(crate) res_list: &'a mut ResourceList,
 }
 
+/// Shared slot a readback result is written to.
+type ReadbackSlot = Rc<RefCell<Option<Result<Vec<u8>, buffer::BufferError>>>>;
+
+/// Data read back from the device into host memory.
+///
+/// The data becomes available once [`SubmitGroup::wait`] has been called on the `SubmitGroup`
+/// which queued the read.
+///
+/// [`SubmitGroup::wait`]: struct.SubmitGroup.html#method.wait
+pub struct Readback {
+    data: ReadbackSlot,
+}
+
+impl Readback {
+    /// Check whether the read has finished already, successfully or not.
+    pub fn is_ready(&self) -> bool {
+        self.data.borrow().is_some()
+    }
+
+    /// Take the result out of the readback, returning `None` if it's not available (yet).
+    ///
+    /// The read fails if the memory it was copied to can't be mapped.
+    pub fn take(&self) -> Option<Result<Vec<u8>, buffer::BufferError>> {
+        self.data.borrow_mut().take()
+    }
+}
+
+/// Layout of data in a staging buffer used for reading back data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ReadbackLayout {
+    /// Number of rows of texels.
+    pub(crate) rows: usize,
+    /// Distance in bytes between the starts of two rows.
+    pub(crate) row_pitch: usize,
+    /// Number of texels in each row.
+    pub(crate) row_texels: usize,
+    /// Size in bytes of a texel in the staging buffer.
+    pub(crate) src_texel_size: usize,
+    /// Size in bytes of a texel in the read back data. Only the first bytes of each source texel
+    /// are kept if this is smaller than `src_texel_size`.
+    pub(crate) dst_texel_size: usize,
+}
+
+impl ReadbackLayout {
+    /// Layout of plain bytes without any padding.
+    pub(crate) fn bytes(size: usize) -> Self {
+        ReadbackLayout {
+            rows: 1,
+            row_pitch: size,
+            row_texels: size,
+            src_texel_size: 1,
+            dst_texel_size: 1,
+        }
+    }
+
+    /// Size in bytes of the staging buffer.
+    pub(crate) fn staging_size(&self) -> u64 {
+        (self.rows * self.row_pitch) as u64
+    }
+
+    /// Remove the row padding and convert the texels of data in the staging buffer.
+    pub(crate) fn unpack(&self, staging: &[u8]) -> Vec<u8> {
+        let mut data = Vec::with_capacity(self.rows * self.row_texels * self.dst_texel_size);
+
+        for row in staging.chunks(self.row_pitch).take(self.rows) {
+            let row = &row[..self.row_texels * self.src_texel_size];
+
+            if self.src_texel_size == self.dst_texel_size {
+                data.extend_from_slice(row);
+            } else {
+                for texel in row.chunks(self.src_texel_size) {
+                    data.extend_from_slice(&texel[..self.dst_texel_size]);
+                }
+            }
+        }
+
+        data
+    }
+}
+
+/// A read which has been submitted but whose data is not available yet.
+pub(crate) struct PendingReadback {
+    staging: BufferTypeInternal,
+    layout: ReadbackLayout,
+    target: ReadbackSlot,
+}
+
+impl PendingReadback {
+    pub(crate) fn new(staging: BufferTypeInternal, layout: ReadbackLayout) -> Self {
+        PendingReadback {
+            staging,
+            layout,
+            target: Rc::new(RefCell::new(None)),
+        }
+    }
+
+    /// Copy the data out of the staging buffer. The submission has to be finished already.
+    unsafe fn finish(mut self, device: &DeviceContext, res_list: &mut ResourceList) {
+        let mut data = vec![0; self.layout.staging_size() as usize];
+
+        let result = buffer::read_data_from_buffer(device, &mut self.staging, 0, &mut data)
+            .map(|_| self.layout.unpack(&data));
+
+        *self.target.borrow_mut() = Some(result);
+
+        res_list.queue_buffer(self.staging);
+    }
+}
+
 pub(crate) struct ResourceList {
     device: Arc<DeviceContext>,
 
     framebuffers: SmallVec<[types::Framebuffer; 16]>,
     buffers: SmallVec<[BufferTypeInternal; 16]>,
     images: SmallVec<[ImageType; 16]>,
+    memory: SmallVec<[Block; 16]>,
     samplers: SmallVec<[types::Sampler; 16]>,
     image_views: SmallVec<[types::ImageView; 16]>,
     render_passes: SmallVec<[types::RenderPass; 16]>,
@@ -613,6 +900,7 @@ impl ResourceList {
             framebuffers: SmallVec::new(),
             buffers: SmallVec::new(),
             images: SmallVec::new(),
+            memory: SmallVec::new(),
             samplers: SmallVec::new(),
             image_views: SmallVec::new(),
             render_passes: SmallVec::new(),
@@ -637,6 +925,13 @@ impl ResourceList {
         self.images.push(image);
     }
 
+    /// Queue memory that is shared by multiple resources.
+    ///
+    /// The memory is freed after all queued buffers and images are destroyed.
+    pub(crate) fn queue_memory(&mut self, block: Block) {
+        self.memory.push(block);
+    }
+
     pub(crate) fn queue_sampler(&mut self, sampler: types::Sampler) {
         self.samplers.push(sampler);
     }
@@ -682,6 +977,10 @@ impl ResourceList {
             alloc.destroy_image(device, image);
         }
 
+        for block in self.memory.drain() {
+            alloc.free(device, block);
+        }
+
         for sampler in self.samplers.drain() {
             device.destroy_sampler(sampler);
         }
@@ -725,3 +1024,34 @@ impl ResourceList {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn readback_rows_are_unpacked() {
+        // 2x2 RGB texels stored as RGBA with rows padded to 12 bytes.
+        let layout = ReadbackLayout {
+            rows: 2,
+            row_pitch: 12,
+            row_texels: 2,
+            src_texel_size: 4,
+            dst_texel_size: 3,
+        };
+
+        let staging = [
+            1, 2, 3, 255, 4, 5, 6, 255, 0, 0, 0, 0, //
+            7, 8, 9, 255, 10, 11, 12, 255, 0, 0, 0, 0,
+        ];
+
+        assert_eq!(layout.staging_size(), 24);
+        assert_eq!(
+            layout.unpack(&staging),
+            vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12]
+        );
+
+        let bytes = ReadbackLayout::bytes(4);
+        assert_eq!(bytes.unpack(&[1, 2, 3, 4]), vec![1, 2, 3, 4]);
+    }
+}
@@ -4,6 +4,7 @@
 
 pub(crate) type GraphicsPipeline = <back::Backend as gfx::Backend>::GraphicsPipeline;
 pub(crate) type ComputePipeline = <back::Backend as gfx::Backend>::ComputePipeline;
+pub(crate) type PipelineCache = <back::Backend as gfx::Backend>::PipelineCache;
 pub(crate) type PipelineLayout = <back::Backend as gfx::Backend>::PipelineLayout;
 pub(crate) type DescriptorSetLayout = <back::Backend as gfx::Backend>::DescriptorSetLayout;
 pub(crate) type DescriptorSet = <back::Backend as gfx::Backend>::DescriptorSet;
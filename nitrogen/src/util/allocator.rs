@@ -50,10 +50,40 @@ pub(crate) struct ImageRequest {
     pub(crate) view_caps: gfx::image::ViewCapabilities,
 }
 
+/// A buffer without any memory bound to it yet.
+#[derive(Debug)]
+pub(crate) struct UnboundBuffer {
+    buffer: crate::types::Buffer,
+    requirements: gfx::memory::Requirements,
+    properties: gfx::memory::Properties,
+    transient: bool,
+}
+
+/// An image without any memory bound to it yet.
+pub(crate) struct UnboundImage {
+    image: crate::types::Image,
+    requirements: gfx::memory::Requirements,
+    properties: gfx::memory::Properties,
+    transient: bool,
+}
+
+impl UnboundBuffer {
+    pub(crate) fn requirements(&self) -> gfx::memory::Requirements {
+        self.requirements
+    }
+}
+
+impl UnboundImage {
+    pub(crate) fn requirements(&self) -> gfx::memory::Requirements {
+        self.requirements
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct Buffer {
     buffer: crate::types::Buffer,
-    block: Block,
+    // `None` if the buffer is bound to memory that is shared with other resources.
+    block: Option<Block>,
 }
 
 impl Buffer {
@@ -61,14 +91,16 @@ impl Buffer {
         &self.buffer
     }
 
-    pub(crate) fn block_mut(&mut self) -> &mut Block {
-        &mut self.block
+    /// The memory block owned by the buffer, `None` if it is bound to shared memory.
+    pub(crate) fn block_mut(&mut self) -> Option<&mut Block> {
+        self.block.as_mut()
     }
 }
 
 pub(crate) struct Image {
     image: crate::types::Image,
-    block: Block,
+    // `None` if the image is bound to memory that is shared with other resources.
+    block: Option<Block>,
 }
 
 impl Image {
@@ -77,8 +109,8 @@ impl Image {
     }
 
     #[allow(unused)]
-    pub(crate) fn block(&self) -> &Block {
-        &self.block
+    pub(crate) fn block(&self) -> Option<&Block> {
+        self.block.as_ref()
     }
 }
 
@@ -171,23 +203,64 @@ impl Allocator {
         device: &back::Device,
         request: BufferRequest,
     ) -> Result<Buffer, AllocatorError> {
+        let buffer = self.create_buffer_unbound(device, request)?;
+        self.bind_buffer(device, buffer, None)
+    }
+
+    pub(crate) unsafe fn create_buffer_unbound(
+        &mut self,
+        device: &back::Device,
+        request: BufferRequest,
+    ) -> Result<UnboundBuffer, AllocatorError> {
         use gfx::Device;
-        use rendy_memory::Block as _;
 
-        let mut buf = device.create_buffer(request.size, request.usage)?;
-        let reqs = device.get_buffer_requirements(&buf);
+        let buffer = device.create_buffer(request.size, request.usage)?;
+        let requirements = device.get_buffer_requirements(&buffer);
 
-        let request = Request {
-            size: reqs.size,
-            alignment: reqs.alignment,
-            type_mask: reqs.type_mask,
+        Ok(UnboundBuffer {
+            buffer,
+            requirements,
             properties: request.properties,
             transient: request.transient,
-        };
+        })
+    }
 
-        let block = self.alloc(device, request)?;
+    /// Bind memory to a buffer.
+    ///
+    /// If no `shared` block is given, a new block is allocated which is owned by the buffer.
+    /// Otherwise the buffer is bound to the start of the shared block, which has to outlive the
+    /// buffer and has to satisfy the buffer's memory requirements.
+    pub(crate) unsafe fn bind_buffer(
+        &mut self,
+        device: &back::Device,
+        buffer: UnboundBuffer,
+        shared: Option<&Block>,
+    ) -> Result<Buffer, AllocatorError> {
+        use gfx::Device;
+        use rendy_memory::Block as _;
+
+        let mut buf = buffer.buffer;
 
-        device.bind_buffer_memory(block.memory(), block.range().start, &mut buf)?;
+        let block = match shared {
+            Some(block) => {
+                device.bind_buffer_memory(block.memory(), block.range().start, &mut buf)?;
+                None
+            }
+            None => {
+                let request = Request {
+                    size: buffer.requirements.size,
+                    alignment: buffer.requirements.alignment,
+                    type_mask: buffer.requirements.type_mask,
+                    properties: buffer.properties,
+                    transient: buffer.transient,
+                };
+
+                let block = self.alloc(device, request)?;
+
+                device.bind_buffer_memory(block.memory(), block.range().start, &mut buf)?;
+                Some(block)
+            }
+        };
 
         Ok(Buffer { buffer: buf, block })
     }
@@ -197,10 +270,18 @@ impl Allocator {
         device: &back::Device,
         request: ImageRequest,
     ) -> Result<Image, AllocatorError> {
+        let image = self.create_image_unbound(device, request)?;
+        self.bind_image(device, image, None)
+    }
+
+    pub(crate) unsafe fn create_image_unbound(
+        &mut self,
+        device: &back::Device,
+        request: ImageRequest,
+    ) -> Result<UnboundImage, AllocatorError> {
         use gfx::Device;
-        use rendy_memory::Block as _;
 
-        let mut img = device.create_image(
+        let image = device.create_image(
             request.kind,
             request.level,
             request.format,
@@ -209,19 +290,52 @@ impl Allocator {
             request.view_caps,
         )?;
 
-        let reqs = device.get_image_requirements(&img);
+        let requirements = device.get_image_requirements(&image);
 
-        let request = Request {
-            size: reqs.size,
-            alignment: reqs.alignment,
-            type_mask: reqs.type_mask,
+        Ok(UnboundImage {
+            image,
+            requirements,
             properties: request.properties,
             transient: request.transient,
-        };
+        })
+    }
+
+    /// Bind memory to an image.
+    ///
+    /// If no `shared` block is given, a new block is allocated which is owned by the image.
+    /// Otherwise the image is bound to the start of the shared block, which has to outlive the
+    /// image and has to satisfy the image's memory requirements.
+    pub(crate) unsafe fn bind_image(
+        &mut self,
+        device: &back::Device,
+        image: UnboundImage,
+        shared: Option<&Block>,
+    ) -> Result<Image, AllocatorError> {
+        use gfx::Device;
+        use rendy_memory::Block as _;
 
-        let block = self.alloc(device, request)?;
+        let mut img = image.image;
 
-        device.bind_image_memory(block.memory(), block.range().start, &mut img)?;
+        let block = match shared {
+            Some(block) => {
+                device.bind_image_memory(block.memory(), block.range().start, &mut img)?;
+                None
+            }
+            None => {
+                let request = Request {
+                    size: image.requirements.size,
+                    alignment: image.requirements.alignment,
+                    type_mask: image.requirements.type_mask,
+                    properties: image.properties,
+                    transient: image.transient,
+                };
+
+                let block = self.alloc(device, request)?;
+
+                device.bind_image_memory(block.memory(), block.range().start, &mut img)?;
+                Some(block)
+            }
+        };
 
         Ok(Image { image: img, block })
     }
@@ -229,13 +343,17 @@ impl Allocator {
     pub(crate) unsafe fn destroy_buffer(&mut self, device: &back::Device, buffer: Buffer) {
         use gfx::Device;
         device.destroy_buffer(buffer.buffer);
-        self.free(device, buffer.block);
+        if let Some(block) = buffer.block {
+            self.free(device, block);
+        }
     }
 
     pub(crate) unsafe fn destroy_image(&mut self, device: &back::Device, image: Image) {
         use gfx::Device;
         device.destroy_image(image.image);
-        self.free(device, image.block);
+        if let Some(block) = image.block {
+            self.free(device, block);
+        }
     }
 
     pub(crate) unsafe fn dispose(self, device: &back::Device) {
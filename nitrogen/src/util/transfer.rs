@@ -6,7 +6,7 @@ use std;
 
 use crate::buffer::BufferTypeInternal;
 
-use crate::image::ImageType;
+use crate::image::{ImageDimension, ImageType};
 
 use crate::device::DeviceContext;
 use crate::resources::command_pool::CommandPoolGraphics;
@@ -184,18 +184,199 @@ pub(crate) unsafe fn copy_buffers_to_images(
     sem_list.advance();
 }
 
+pub(crate) struct BufferReadTransfer<'a> {
+    pub(crate) src: &'a BufferTypeInternal,
+    pub(crate) dst: &'a BufferTypeInternal,
+    pub(crate) offset: u64,
+    pub(crate) size: u64,
+}
+
+/// Copy ranges of buffers into host-visible buffers so they can be read by the host.
+pub(crate) unsafe fn read_buffers(
+    device: &DeviceContext,
+    sem_pool: &SemaphorePool,
+    sem_list: &mut SemaphoreList,
+    cmd_pool: &CommandPoolTransfer,
+    buffers: &[BufferReadTransfer],
+) {
+    use gfx::buffer::Access;
+    use gfx::pso::PipelineStage;
+
+    let submit = {
+        let mut cmd = cmd_pool.alloc();
+        cmd.begin();
+
+        for transfer in buffers {
+            let entry_barrier = gfx::memory::Barrier::Buffer {
+                states: Access::MEMORY_WRITE..Access::TRANSFER_READ,
+                families: None,
+                target: transfer.src.raw(),
+                range: None..None,
+            };
+
+            cmd.pipeline_barrier(
+                PipelineStage::TOP_OF_PIPE..PipelineStage::TRANSFER,
+                gfx::memory::Dependencies::empty(),
+                &[entry_barrier],
+            );
+
+            cmd.copy_buffer(
+                transfer.src.raw(),
+                transfer.dst.raw(),
+                &[gfx::command::BufferCopy {
+                    src: transfer.offset,
+                    dst: 0,
+                    size: transfer.size,
+                }],
+            );
+
+            // The host reads the data once the submission finished.
+            let exit_barrier = gfx::memory::Barrier::Buffer {
+                states: Access::TRANSFER_WRITE..Access::HOST_READ,
+                families: None,
+                target: transfer.dst.raw(),
+                range: None..None,
+            };
+
+            cmd.pipeline_barrier(
+                PipelineStage::TRANSFER..PipelineStage::HOST,
+                gfx::memory::Dependencies::empty(),
+                &[exit_barrier],
+            );
+        }
+
+        cmd.finish();
+        cmd
+    };
+
+    let sem = sem_pool.alloc();
+    sem_list.add_next_semaphore(sem);
+
+    {
+        let submission = gfx::Submission {
+            command_buffers: Some(&*submit),
+            wait_semaphores: sem_pool
+                .list_prev_sems(sem_list)
+                .map(|sem| (sem, gfx::pso::PipelineStage::BOTTOM_OF_PIPE)),
+            signal_semaphores: sem_pool.list_next_sems(sem_list),
+        };
+
+        device.transfer_queue().submit(submission, None);
+    }
+
+    sem_list.advance();
+}
+
+pub(crate) struct ImageBufferTransfer<'a> {
+    pub(crate) src: &'a ImageType,
+    pub(crate) dst: &'a BufferTypeInternal,
+
+    pub(crate) subresource_range: gfx::image::SubresourceRange,
+    pub(crate) copy_information: gfx::command::BufferImageCopy,
+}
+
+/// Copy regions of images into host-visible buffers so they can be read by the host.
+///
+/// The images have to be in the `General` layout and are put back into it afterwards.
+pub(crate) unsafe fn copy_images_to_buffers(
+    device: &DeviceContext,
+    sem_pool: &SemaphorePool,
+    sem_list: &mut SemaphoreList,
+    cmd_pool: &CommandPoolTransfer,
+    images: &[ImageBufferTransfer],
+) {
+    use gfx::image::Access;
+    use gfx::image::Layout;
+    use gfx::memory::Barrier;
+    use gfx::pso::PipelineStage;
+
+    let submit = {
+        let mut cmd = cmd_pool.alloc();
+        cmd.begin();
+
+        for transfer in images {
+            let entry_barrier = Barrier::Image {
+                states: (Access::MEMORY_WRITE, Layout::General)
+                    ..(Access::TRANSFER_READ, Layout::TransferSrcOptimal),
+                target: transfer.src.raw(),
+                families: None,
+                range: transfer.subresource_range.clone(),
+            };
+
+            cmd.pipeline_barrier(
+                PipelineStage::TOP_OF_PIPE..PipelineStage::TRANSFER,
+                gfx::memory::Dependencies::empty(),
+                &[entry_barrier],
+            );
+
+            cmd.copy_image_to_buffer(
+                transfer.src.raw(),
+                Layout::TransferSrcOptimal,
+                transfer.dst.raw(),
+                &[transfer.copy_information.clone()],
+            );
+
+            let exit_barrier_src = Barrier::Image {
+                states: (Access::TRANSFER_READ, Layout::TransferSrcOptimal)
+                    ..(Access::empty(), Layout::General),
+                target: transfer.src.raw(),
+                families: None,
+                range: transfer.subresource_range.clone(),
+            };
+            let exit_barrier_dst = Barrier::Buffer {
+                states: gfx::buffer::Access::TRANSFER_WRITE..gfx::buffer::Access::HOST_READ,
+                families: None,
+                target: transfer.dst.raw(),
+                range: None..None,
+            };
+
+            cmd.pipeline_barrier(
+                PipelineStage::TRANSFER..PipelineStage::BOTTOM_OF_PIPE | PipelineStage::HOST,
+                gfx::memory::Dependencies::empty(),
+                &[exit_barrier_src, exit_barrier_dst],
+            );
+        }
+
+        cmd.finish();
+        cmd
+    };
+
+    let sem = sem_pool.alloc();
+    sem_list.add_next_semaphore(sem);
+
+    {
+        let submission = gfx::Submission {
+            command_buffers: Some(&*submit),
+            wait_semaphores: sem_pool
+                .list_prev_sems(sem_list)
+                .map(|sem| (sem, gfx::pso::PipelineStage::BOTTOM_OF_PIPE)),
+            signal_semaphores: sem_pool.list_next_sems(sem_list),
+        };
+
+        device.transfer_queue().submit(submission, None);
+    }
+
+    sem_list.advance();
+}
+
 pub(crate) struct ImageBlit<'a> {
     pub(crate) dst: &'a ImageType,
     pub(crate) src: &'a ImageType,
 
-    // TODO mipmaps?
-    // TODO layers?
     pub(crate) filter: gfx::image::Filter,
 
-    pub(crate) subresource_range: gfx::image::SubresourceRange,
     pub(crate) copy_information: gfx::command::ImageBlit,
 }
 
+/// The subresource range covering a single level and a range of layers.
+fn level_range(layers: &gfx::image::SubresourceLayers) -> gfx::image::SubresourceRange {
+    gfx::image::SubresourceRange {
+        aspects: layers.aspects,
+        levels: layers.level..(layers.level + 1),
+        layers: layers.layers.clone(),
+    }
+}
+
 pub(crate) unsafe fn blit_image(
     device: &DeviceContext,
     sem_pool: &SemaphorePool,
@@ -213,19 +394,22 @@ pub(crate) unsafe fn blit_image(
         cmd.begin();
 
         for transfer in blits {
+            let dst_range = level_range(&transfer.copy_information.dst_subresource);
+            let src_range = level_range(&transfer.copy_information.src_subresource);
+
             let entry_barrier_dst = Barrier::Image {
                 states: (Access::empty(), Layout::General)
                     ..(Access::TRANSFER_WRITE, Layout::TransferDstOptimal),
                 target: transfer.dst.raw(),
                 families: None,
-                range: transfer.subresource_range.clone(),
+                range: dst_range.clone(),
             };
             let entry_barrier_src = Barrier::Image {
                 states: (Access::empty(), Layout::General)
                     ..(Access::TRANSFER_READ, Layout::TransferSrcOptimal),
                 target: transfer.src.raw(),
                 families: None,
-                range: transfer.subresource_range.clone(),
+                range: src_range.clone(),
             };
 
             cmd.pipeline_barrier(
@@ -248,14 +432,14 @@ pub(crate) unsafe fn blit_image(
                     ..(Access::empty(), Layout::General),
                 target: transfer.dst.raw(),
                 families: None,
-                range: transfer.subresource_range.clone(),
+                range: dst_range,
             };
             let exit_barrier_src = Barrier::Image {
                 states: (Access::TRANSFER_READ, Layout::TransferSrcOptimal)
                     ..(Access::empty(), Layout::General),
                 target: transfer.src.raw(),
                 families: None,
-                range: transfer.subresource_range.clone(),
+                range: src_range,
             };
 
             cmd.pipeline_barrier(
@@ -286,3 +470,153 @@ pub(crate) unsafe fn blit_image(
 
     sem_list.advance();
 }
+
+pub(crate) struct MipmapGeneration<'a> {
+    pub(crate) image: &'a ImageType,
+    pub(crate) aspects: gfx::format::Aspects,
+    pub(crate) dimension: ImageDimension,
+    pub(crate) levels: u8,
+    pub(crate) layers: u16,
+    pub(crate) filter: gfx::image::Filter,
+}
+
+/// Fill all mipmap levels of an image by blitting each level into the next smaller one.
+///
+/// The base level has to be in the `General` layout, afterwards all levels are in the `General`
+/// layout.
+pub(crate) unsafe fn generate_mipmaps(
+    device: &DeviceContext,
+    sem_pool: &SemaphorePool,
+    sem_list: &mut SemaphoreList,
+    cmd_pool: &CommandPoolGraphics,
+    generation: &MipmapGeneration,
+) {
+    use gfx::image::Access;
+    use gfx::image::Layout;
+    use gfx::memory::Barrier;
+    use gfx::pso::PipelineStage;
+
+    let range = |level: u8| gfx::image::SubresourceRange {
+        aspects: generation.aspects,
+        levels: level..level + 1,
+        layers: 0..generation.layers,
+    };
+
+    let bounds = |level: u8| {
+        let (x, y, z) = generation.dimension.mip_level(level).as_triple(1);
+        gfx::image::Offset { x: 0, y: 0, z: 0 }..gfx::image::Offset {
+            x: x as i32,
+            y: y as i32,
+            z: z as i32,
+        }
+    };
+
+    let submit = {
+        let mut cmd = cmd_pool.alloc();
+        cmd.begin();
+
+        for level in 1..generation.levels {
+            let src = level - 1;
+
+            // The base level comes from outside, every other source level
+            // was written by the previous blit.
+            let src_state = if src == 0 {
+                (Access::MEMORY_WRITE, Layout::General)
+            } else {
+                (Access::TRANSFER_WRITE, Layout::TransferDstOptimal)
+            };
+
+            let entry_barrier_src = Barrier::Image {
+                states: src_state..(Access::TRANSFER_READ, Layout::TransferSrcOptimal),
+                target: generation.image.raw(),
+                families: None,
+                range: range(src),
+            };
+            let entry_barrier_dst = Barrier::Image {
+                states: (Access::empty(), Layout::Undefined)
+                    ..(Access::TRANSFER_WRITE, Layout::TransferDstOptimal),
+                target: generation.image.raw(),
+                families: None,
+                range: range(level),
+            };
+
+            cmd.pipeline_barrier(
+                PipelineStage::TRANSFER..PipelineStage::TRANSFER,
+                gfx::memory::Dependencies::empty(),
+                &[entry_barrier_src, entry_barrier_dst],
+            );
+
+            cmd.blit_image(
+                generation.image.raw(),
+                Layout::TransferSrcOptimal,
+                generation.image.raw(),
+                Layout::TransferDstOptimal,
+                generation.filter,
+                &[gfx::command::ImageBlit {
+                    src_subresource: gfx::image::SubresourceLayers {
+                        aspects: generation.aspects,
+                        level: src,
+                        layers: 0..generation.layers,
+                    },
+                    src_bounds: bounds(src),
+                    dst_subresource: gfx::image::SubresourceLayers {
+                        aspects: generation.aspects,
+                        level,
+                        layers: 0..generation.layers,
+                    },
+                    dst_bounds: bounds(level),
+                }],
+            );
+
+            let exit_barrier_src = Barrier::Image {
+                states: (Access::TRANSFER_READ, Layout::TransferSrcOptimal)
+                    ..(Access::empty(), Layout::General),
+                target: generation.image.raw(),
+                families: None,
+                range: range(src),
+            };
+
+            cmd.pipeline_barrier(
+                PipelineStage::TRANSFER..PipelineStage::BOTTOM_OF_PIPE,
+                gfx::memory::Dependencies::empty(),
+                &[exit_barrier_src],
+            );
+        }
+
+        if generation.levels > 1 {
+            let exit_barrier_last = Barrier::Image {
+                states: (Access::TRANSFER_WRITE, Layout::TransferDstOptimal)
+                    ..(Access::MEMORY_READ, Layout::General),
+                target: generation.image.raw(),
+                families: None,
+                range: range(generation.levels - 1),
+            };
+
+            cmd.pipeline_barrier(
+                PipelineStage::TRANSFER..PipelineStage::BOTTOM_OF_PIPE,
+                gfx::memory::Dependencies::empty(),
+                &[exit_barrier_last],
+            );
+        }
+
+        cmd.finish();
+        cmd
+    };
+
+    let sem = sem_pool.alloc();
+    sem_list.add_next_semaphore(sem);
+
+    {
+        let submission = gfx::Submission {
+            command_buffers: Some(&*submit),
+            wait_semaphores: sem_pool
+                .list_prev_sems(sem_list)
+                .map(|sem| (sem, gfx::pso::PipelineStage::BOTTOM_OF_PIPE)),
+            signal_semaphores: sem_pool.list_next_sems(sem_list),
+        };
+
+        device.graphics_queue().submit(submission, None);
+    }
+
+    sem_list.advance();
+}